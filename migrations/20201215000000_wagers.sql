@@ -0,0 +1,17 @@
+-- Wagers
+-- the init migration created a placeholder wagers table that was never used
+drop table if exists wagers;
+
+create table wagers (
+    wager_id serial primary key,
+    home_user_id integer not null references users(user_id),
+    away_user_id integer not null references users(user_id),
+    team_id integer not null references events_teams(team_id) on delete cascade,
+    state text not null default 'pending', -- pending, accepted, declined, cancelled
+    currency integer not null check (currency > 0),
+    created_at timestamp not null default current_timestamp,
+    check (home_user_id <> away_user_id)
+);
+
+create index wagers_home_user_id_idx on wagers (home_user_id);
+create index wagers_away_user_id_idx on wagers (away_user_id);
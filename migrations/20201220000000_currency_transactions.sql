@@ -0,0 +1,23 @@
+-- Currency Transactions
+-- every row moves currency between a user and a system account (treasury or escrow),
+-- so the user side and the counter account side of each entry always balance
+create table currency_transactions (
+    transaction_id serial primary key,
+    user_id integer not null references users(user_id),
+    amount integer not null, -- positive when the user is credited, negative when debited
+    reason text not null, -- signup_grant, wager_escrow, wager_payout, refund, admin_adjustment
+    counter_account text not null, -- treasury, escrow
+    reference_id integer, -- the row that caused the transaction, i.e. the wager_id for wager reasons
+    created_at timestamp not null default current_timestamp
+);
+
+create index currency_transactions_user_id_idx on currency_transactions (user_id, transaction_id desc);
+
+-- a balance can never be overdrawn
+alter table users add constraint users_currency_non_negative check (currency >= 0);
+
+-- give existing users their signup grant so the ledger matches their balance
+insert into currency_transactions (user_id, amount, reason, counter_account)
+select user_id, 1000, 'signup_grant', 'treasury' from users;
+
+update users set currency = currency + 1000;
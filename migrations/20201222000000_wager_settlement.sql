@@ -0,0 +1,9 @@
+-- Wager Settlement
+-- settled wagers keep track of who was paid, a null winner means both sides were refunded
+alter table wagers add column winner_user_id integer references users(user_id);
+alter table wagers add column settled_at timestamp;
+
+-- an event is finalised once all of its wagers have been settled by an admin
+alter table events add column finalized_at timestamp;
+
+create index wagers_team_id_idx on wagers (team_id);
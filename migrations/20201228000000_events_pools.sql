@@ -0,0 +1,27 @@
+-- Event Pools
+create table events_pools (
+    event_id integer primary key references events(event_id) on delete cascade,
+    house_cut integer not null default 0 check (house_cut >= 0 and house_cut <= 100), -- percent of the pot kept by the house
+    locks_at timestamp, -- bets are locked automatically once the event starts
+    locked_at timestamp, -- bets are locked manually by an admin
+    settled_at timestamp,
+    house_take integer, -- currency kept by the house once the pool is settled
+    created_at timestamp not null default current_timestamp
+);
+
+-- every existing event gets a pool
+insert into events_pools (event_id) select event_id from events;
+
+-- Event Pool Bets
+create table events_pools_bets (
+    bet_id serial primary key,
+    event_id integer not null references events_pools(event_id) on delete cascade,
+    user_id integer not null references users(user_id),
+    team_id integer not null references events_teams(team_id) on delete cascade,
+    currency integer not null check (currency > 0),
+    state text not null default 'active', -- active, cancelled, settled
+    payout integer,
+    created_at timestamp not null default current_timestamp
+);
+
+create index events_pools_bets_event_id_idx on events_pools_bets (event_id);
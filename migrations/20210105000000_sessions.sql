@@ -0,0 +1,21 @@
+-- Sessions
+-- a session is a single login, every refresh token issued for it belongs to the same family
+create table sessions (
+    session_id serial primary key,
+    user_id integer not null references users(user_id) on delete cascade,
+    revoked_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index sessions_user_id_idx on sessions (user_id);
+
+-- Session Refresh Tokens
+create table sessions_refresh_tokens (
+    token_hash text primary key, -- sha256 of the opaque refresh token
+    session_id integer not null references sessions(session_id) on delete cascade,
+    expires_at timestamp not null,
+    rotated_at timestamp, -- set once the token has been exchanged for a new one
+    created_at timestamp not null default current_timestamp
+);
+
+create index sessions_refresh_tokens_session_id_idx on sessions_refresh_tokens (session_id);
@@ -0,0 +1,5 @@
+-- Session Devices
+alter table sessions add column device_name text;
+alter table sessions add column user_agent text;
+alter table sessions add column ip_address text;
+alter table sessions add column last_used_at timestamp not null default current_timestamp;
@@ -0,0 +1,11 @@
+-- Users Verification Tokens
+-- single use tokens that are emailed to a user to verify they own their email
+create table users_verification_tokens (
+    token_hash text primary key, -- sha256 of the opaque verification token
+    user_id integer not null references users(user_id) on delete cascade,
+    expires_at timestamp not null,
+    used_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index users_verification_tokens_user_id_idx on users_verification_tokens (user_id);
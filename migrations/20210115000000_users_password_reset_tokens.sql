@@ -0,0 +1,11 @@
+-- Users Password Reset Tokens
+-- single use tokens that are emailed to a user so they can pick a new password
+create table users_password_reset_tokens (
+    token_hash text primary key, -- sha256 of the opaque reset token
+    user_id integer not null references users(user_id) on delete cascade,
+    expires_at timestamp not null,
+    used_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index users_password_reset_tokens_user_id_idx on users_password_reset_tokens (user_id);
@@ -0,0 +1,12 @@
+-- Username History
+-- previous usernames keep resolving to their user for a grace period after a rename
+create table username_history (
+    username varchar(100) not null,
+    user_id integer not null references users(user_id) on delete cascade,
+    changed_at timestamp not null default current_timestamp
+);
+
+create index username_history_username_idx on username_history (username);
+
+-- a verification token with an email changes the email of the user once it is verified
+alter table users_verification_tokens add column email text;
@@ -0,0 +1,3 @@
+-- Deleted Users
+-- deleted users are anonymised instead of removed so events and comments stay intact
+alter table users add column deleted_at timestamp;
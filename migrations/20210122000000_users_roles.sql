@@ -0,0 +1,19 @@
+-- User Roles
+alter table users add column role text not null default 'user';
+alter table users add constraint users_role_check check (role in ('user', 'moderator', 'admin'));
+
+-- the invite codes used to be limited to this user
+update users set role = 'admin' where username = 'adamaho';
+
+-- Audit Log
+-- every action taken by a moderator or admin
+create table audit_log (
+    audit_id serial primary key,
+    actor_user_id integer not null references users(user_id),
+    action text not null,
+    target_user_id integer references users(user_id),
+    details text,
+    created_at timestamp not null default current_timestamp
+);
+
+create index audit_log_target_user_id_idx on audit_log (target_user_id);
@@ -0,0 +1,5 @@
+-- User Suspensions
+-- a suspension without an end lasts until it is lifted by a moderator
+alter table users add column suspended_at timestamp;
+alter table users add column suspended_until timestamp;
+alter table users add column suspension_reason text;
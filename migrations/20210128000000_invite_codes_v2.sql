@@ -0,0 +1,21 @@
+-- Invite Codes v2
+-- codes can be shared by any verified user, used more than once and expire
+alter table invite_codes add column created_by integer references users(user_id);
+alter table invite_codes add column max_uses integer not null default 1;
+alter table invite_codes add column uses integer not null default 0;
+alter table invite_codes add column expires_at timestamp;
+alter table invite_codes add column created_at timestamp not null default current_timestamp;
+alter table invite_codes add constraint invite_codes_uses_check check (uses >= 0 and uses <= max_uses);
+
+create index invite_codes_created_by_idx on invite_codes (created_by);
+
+-- a code is no longer limited to a single user
+alter table users drop constraint users_invite_code_key;
+
+update invite_codes set uses = 1 where id in (select invite_code from users);
+
+-- Invite Tree
+-- the user that created the invite code the user signed up with
+alter table users add column invited_by integer references users(user_id);
+
+create index users_invited_by_idx on users (invited_by);
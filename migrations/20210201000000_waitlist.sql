@@ -0,0 +1,22 @@
+-- Waitlist
+-- people without an invite code, approved in batches by an admin
+create table waitlist (
+    waitlist_id serial primary key,
+    email text not null unique,
+    referrer_user_id integer references users(user_id),
+    vouches integer not null default 0,
+    invite_code varchar(10) references invite_codes(id),
+    approved_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index waitlist_pending_idx on waitlist (vouches desc, created_at) where approved_at is null;
+
+-- Waitlist Vouches
+-- every user can vouch for a person once to move them up the waitlist
+create table waitlist_vouches (
+    waitlist_id integer not null references waitlist(waitlist_id) on delete cascade,
+    user_id integer not null references users(user_id),
+    created_at timestamp not null default current_timestamp,
+    primary key (waitlist_id, user_id)
+);
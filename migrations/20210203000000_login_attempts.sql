@@ -0,0 +1,8 @@
+-- Login Attempts
+-- failed logins per ip address or email, only used when LOGIN_ATTEMPT_STORE is "postgres"
+create table login_attempts (
+    key text primary key,
+    failures integer not null,
+    last_failed_at timestamp not null,
+    locked_until timestamp
+);
@@ -0,0 +1,33 @@
+-- Users Two Factor
+-- the totp secret of a user, 2fa is only on once enabled_at is set
+create table users_two_factor (
+    user_id integer primary key references users(user_id) on delete cascade,
+    secret text not null, -- base32 encoded totp secret
+    last_used_step bigint, -- the time step of the last accepted code, used to reject replays
+    enabled_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+-- Users Recovery Codes
+-- single use codes that replace a totp code when the user lost their device
+create table users_recovery_codes (
+    code_hash text primary key, -- sha256 of the recovery code
+    user_id integer not null references users(user_id) on delete cascade,
+    used_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index users_recovery_codes_user_id_idx on users_recovery_codes (user_id);
+
+-- Login Challenges
+-- short lived tokens handed out by login when the user still has to provide a 2fa code
+create table login_challenges (
+    token_hash text primary key, -- sha256 of the opaque challenge token
+    user_id integer not null references users(user_id) on delete cascade,
+    device_name text,
+    expires_at timestamp not null,
+    used_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index login_challenges_user_id_idx on login_challenges (user_id);
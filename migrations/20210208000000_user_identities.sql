@@ -0,0 +1,12 @@
+-- User Identities
+-- accounts at OpenID Connect providers the user can log in with
+create table user_identities (
+    identity_id serial primary key,
+    user_id integer not null references users(user_id) on delete cascade,
+    provider text not null,
+    subject text not null, -- the sub claim of the id token, stable per provider
+    email text,
+    created_at timestamp not null default current_timestamp,
+    unique (provider, subject),
+    unique (user_id, provider)
+);
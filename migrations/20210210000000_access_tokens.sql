@@ -0,0 +1,15 @@
+-- Access Tokens
+-- personal access tokens users create for their scripts and bots
+create table access_tokens (
+    token_id serial primary key,
+    token_hash text not null unique, -- sha256 of the token, the token itself is only shown once
+    user_id integer not null references users(user_id) on delete cascade,
+    name text not null,
+    scopes text[] not null,
+    expires_at timestamp,
+    last_used_at timestamp,
+    revoked_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index access_tokens_user_id_idx on access_tokens (user_id);
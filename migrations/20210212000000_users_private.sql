@@ -0,0 +1,8 @@
+-- Private Profiles
+-- only approved followers can see the followers, following and events of a private user
+alter table users add column private boolean not null default false;
+
+-- following a private user creates a pending row until the user approves it
+alter table friends add constraint friends_state_check check (state in ('active', 'pending', 'blocked'));
+
+create index friends_friend_user_id_state_idx on friends (friend_user_id, state);
@@ -0,0 +1,7 @@
+-- Friend Suggestions
+-- the join paths of the suggestion and mutuals queries, friends is already covered by its primary key
+-- and friends_friend_user_id_state_idx
+create index events_members_user_id_idx on events_members (user_id, state);
+create index events_members_event_id_idx on events_members (event_id, state, user_id);
+create index events_teams_members_member_id_idx on events_teams_members (member_id);
+create index events_teams_members_team_id_idx on events_teams_members (team_id, member_id);
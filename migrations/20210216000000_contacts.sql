@@ -0,0 +1,18 @@
+-- Contact Discovery
+-- users that opted in can be found by the salted hash of their verified email, the hash is only kept
+-- while the user is discoverable
+alter table users add column discoverable boolean not null default false;
+alter table users add column contact_hash text;
+
+create index users_contact_hash_idx on users (contact_hash) where contact_hash is not null;
+
+-- how many hashes each user matched, used to rate limit the matching. the hashes themselves are
+-- never stored
+create table contact_matches (
+    match_id serial primary key,
+    user_id integer not null references users(user_id) on delete cascade,
+    hashes integer not null,
+    created_at timestamp not null default current_timestamp
+);
+
+create index contact_matches_user_id_idx on contact_matches (user_id, created_at);
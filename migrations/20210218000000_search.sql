@@ -0,0 +1,15 @@
+-- Search
+-- users and events are searched by trigram similarity, ignoring case and accents
+create extension if not exists pg_trgm;
+create extension if not exists unaccent;
+
+-- unaccent is only stable because its dictionary can change, the wrapper pins the dictionary so
+-- it can be used in indexes
+create or replace function f_unaccent(text) returns text as $$
+    select public.unaccent('public.unaccent', $1)
+$$ language sql immutable parallel safe strict;
+
+create index users_username_search_idx on users using gin (f_unaccent(lower(username)) gin_trgm_ops);
+create index users_bio_search_idx on users using gin (f_unaccent(lower(bio)) gin_trgm_ops);
+create index events_name_search_idx on events using gin (f_unaccent(lower(name)) gin_trgm_ops);
+create index events_description_search_idx on events using gin (f_unaccent(lower(description)) gin_trgm_ops);
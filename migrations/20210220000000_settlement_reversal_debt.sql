@@ -0,0 +1,5 @@
+-- Settlement Reversal Debt
+-- reversing a settlement takes the payout back even when it was already spent, leaving the
+-- user with a negative balance that is paid back by the next credits. every other transaction
+-- still checks the balance before it is applied.
+alter table users drop constraint users_currency_non_negative;
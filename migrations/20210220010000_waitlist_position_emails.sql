@@ -0,0 +1,4 @@
+-- Waitlist Position Emails
+-- the position on the waitlist is emailed rather than shown to whoever asks for an email, the
+-- time of the last email throttles how often it can be requested
+alter table waitlist add column position_sent_at timestamp;
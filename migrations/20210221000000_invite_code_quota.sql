@@ -0,0 +1,8 @@
+-- Invite Code Quota
+-- only the codes handed out to a user count against their quota, codes minted by an admin or
+-- for the waitlist do not
+alter table invite_codes add column quota boolean not null default false;
+
+-- admins could mint codes directly, so their codes are left out and they get a fresh quota
+update invite_codes set quota = true
+where created_by in (select user_id from users where role <> 'admin');
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use dotenv::dotenv;
@@ -5,7 +6,12 @@ use serde::Deserialize;
 use tracing::{info, instrument};
 
 use crate::crypto::Crypto;
+use crate::keys::JwtKeys;
+use crate::limiter::{AttemptStore, LoginLimiter};
+use crate::mailer::{MailTransport, Mailer, OutboxMailTransport, SmtpMailTransport};
+use crate::oidc::OidcVerifier;
 use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
@@ -13,8 +19,19 @@ pub struct Config {
     pub port: i16,
     pub cors_url: String,
     pub database_url: String,
-    pub jwt_secret: String,
+    pub jwt_secret: Option<String>,
+    pub jwt_keys: Option<String>,
     pub hash_secret: String,
+    pub app_url: Option<String>,
+    pub mail_transport: Option<String>,
+    pub mail_from: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub outbox_dir: Option<String>,
+    pub login_attempt_store: Option<String>,
+    pub oidc_config: Option<String>,
+    pub contacts_salt: Option<String>,
 }
 
 impl Config {
@@ -47,11 +64,77 @@ impl Config {
             .await
     }
 
-    /// Configures the Crypto struct to provide hashing methods
-    pub fn get_crypto(&self) -> Crypto {
-        Crypto {
-            jwt_secret: Arc::new(self.jwt_secret.clone()),
+    /// Configures the Crypto struct to provide hashing methods, jwts are signed with the keys
+    /// in the json file JWT_KEYS points to or otherwise with JWT_SECRET. CONTACTS_SALT is required
+    /// so every deployment hashes contacts with its own salt.
+    pub fn get_crypto(&self) -> Result<Crypto, String> {
+        let jwt_keys = match (&self.jwt_keys, &self.jwt_secret) {
+            (Some(path), _) => JwtKeys::from_file(path)?,
+            (None, Some(secret)) => JwtKeys::from_secret(secret)?,
+            (None, None) => return Err(String::from("JWT_KEYS or JWT_SECRET is required")),
+        };
+
+        let contacts_salt = self
+            .contacts_salt
+            .clone()
+            .filter(|salt| !salt.trim().is_empty())
+            .ok_or("CONTACTS_SALT is required")?;
+
+        Ok(Crypto {
+            jwt_keys: Arc::new(jwt_keys),
             hash_secret: Arc::new(self.hash_secret.clone()),
+            contacts_salt: Arc::new(contacts_salt),
+        })
+    }
+
+    /// Configures the Mailer used to send emails, smtp is used when MAIL_TRANSPORT is "smtp"
+    /// otherwise emails are written to the outbox directory
+    pub fn get_mailer(&self) -> Result<Mailer, String> {
+        let app_url = self
+            .app_url
+            .clone()
+            .unwrap_or_else(|| self.cors_url.clone());
+
+        let transport: Arc<dyn MailTransport> = match self.mail_transport.as_deref() {
+            Some("smtp") => {
+                let from = self.mail_from.clone().ok_or("MAIL_FROM is required")?;
+                let host = self.smtp_host.as_deref().ok_or("SMTP_HOST is required")?;
+
+                Arc::new(SmtpMailTransport::new(
+                    from,
+                    host,
+                    self.smtp_username.clone().unwrap_or_default(),
+                    self.smtp_password.clone().unwrap_or_default(),
+                )?)
+            }
+            _ => {
+                let dir = self
+                    .outbox_dir
+                    .clone()
+                    .unwrap_or_else(|| "outbox".to_string());
+
+                Arc::new(OutboxMailTransport::new(PathBuf::from(dir)))
+            }
+        };
+
+        Ok(Mailer::new(app_url, transport))
+    }
+
+    /// Configures the LoginLimiter, failed logins are kept in postgres when LOGIN_ATTEMPT_STORE
+    /// is "postgres" so they are shared between instances, otherwise they are kept in memory
+    pub fn get_login_limiter(&self, pool: &PgPool) -> LoginLimiter {
+        match self.login_attempt_store.as_deref() {
+            Some("postgres") => LoginLimiter::new(AttemptStore::Postgres(pool.clone())),
+            _ => LoginLimiter::in_memory(),
+        }
+    }
+
+    /// Configures the OidcVerifier from the json file OIDC_CONFIG points to, without it no
+    /// providers are available
+    pub fn get_oidc_verifier(&self) -> Result<OidcVerifier, String> {
+        match &self.oidc_config {
+            Some(path) => OidcVerifier::from_file(path),
+            None => OidcVerifier::new(Vec::new()),
         }
     }
 }
@@ -4,45 +4,64 @@ use actix_web::web;
 use argonautica::{Hasher, Verifier};
 use chrono::{Duration, Utc};
 use futures::compat::Future01CompatExt;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::info;
 
 use crate::errors::TalliiError;
+use crate::keys::{JwtKeys, PublicJwks};
+
+/// Minutes an access token is valid for
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+
+/// Days a refresh token is valid for
+pub const REFRESH_TOKEN_DAYS: i64 = 30;
+
+/// Length of the opaque tokens handed out to clients
+const OPAQUE_TOKEN_LENGTH: usize = 64;
 
 /// Represents the contents of a jwt
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: i32,
     pub username: String,
+    pub sid: i32,
     pub exp: i64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Crypto {
     pub hash_secret: Arc<String>,
-    pub jwt_secret: Arc<String>,
+    pub jwt_keys: Arc<JwtKeys>,
+    pub contacts_salt: Arc<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct TokenResponse {
     pub token: String,
+    pub refresh_token: String,
 }
 
 impl Crypto {
-    /// Decodes the provided token to the Token struct
+    /// Decodes the provided token to the Token struct with the key matching its kid
     pub async fn verify_jwt(
         &self,
         token: String,
     ) -> Result<jsonwebtoken::TokenData<Claims>, TalliiError> {
-        let jwt_secret = self.jwt_secret.clone();
+        let jwt_keys = self.jwt_keys.clone();
 
         web::block(move || {
-            decode::<Claims>(
-                &token,
-                &DecodingKey::from_secret(jwt_secret.as_bytes()),
-                &Validation::default(),
-            )
+            let header = decode_header(&token)?;
+
+            // the key may have been retired, which makes every token it signed invalid
+            let (algorithm, key) = jwt_keys
+                .verification_key(header.kid.as_deref())
+                .ok_or(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)?;
+
+            decode::<Claims>(&token, key, &Validation::new(algorithm))
         })
         .await
         .map_err(|_err| TalliiError::UNAUTHORIZED.default())
@@ -53,23 +72,27 @@ impl Crypto {
         &self,
         user_id: i32,
         username: String,
+        session_id: i32,
     ) -> Result<String, TalliiError> {
-        let jwt_secret = self.jwt_secret.clone();
+        let jwt_keys = self.jwt_keys.clone();
 
         web::block(move || {
-            // TODO: remove this and change to be a day again
-            let now = Utc::now() + Duration::days(7); // Expires in 1 year
+            let (kid, algorithm, key) = jwt_keys
+                .signing_key()
+                .ok_or(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)?;
+
+            let mut header = Header::new(algorithm);
+            header.kid = Some(kid.to_string());
+
+            let expires_at = Utc::now() + Duration::minutes(ACCESS_TOKEN_MINUTES);
             let claims = Claims {
                 sub: user_id,
                 username,
-                exp: now.timestamp(),
+                sid: session_id,
+                exp: expires_at.timestamp(),
             };
 
-            encode(
-                &Header::default(),
-                &claims,
-                &EncodingKey::from_secret(jwt_secret.as_bytes()),
-            )
+            encode(&header, &claims, key)
         })
         .await
         .map_err(|_err| {
@@ -77,6 +100,33 @@ impl Crypto {
         })
     }
 
+    /// The public keys other services can verify our tokens with
+    pub fn public_jwks(&self) -> PublicJwks {
+        self.jwt_keys.public_jwks()
+    }
+
+    /// Generates a random opaque token, i.e. a refresh token
+    pub fn generate_opaque_token(&self) -> String {
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(OPAQUE_TOKEN_LENGTH)
+            .map(char::from)
+            .collect()
+    }
+
+    /// Hashes an opaque token so it can be stored and looked up without storing the token itself
+    pub fn hash_token(&self, token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    /// Hashes an email the way clients hash the emails in their address book for contact
+    /// discovery, the sha256 of the salt and the lowercased email
+    pub fn hash_contact(&self, email: &str) -> String {
+        let contact = format!("{}:{}", self.contacts_salt, email.trim().to_lowercase());
+
+        format!("{:x}", Sha256::digest(contact.as_bytes()))
+    }
+
     // Hashes the provided password
     pub async fn hash_password(&self, password: &str) -> Result<String, TalliiError> {
         match Hasher::default()
@@ -99,6 +149,11 @@ impl Crypto {
         password: &str,
         hashed_password: &str,
     ) -> Result<bool, TalliiError> {
+        // users that only log in through an oidc provider have no password
+        if hashed_password.is_empty() {
+            return Ok(false);
+        }
+
         match Verifier::default()
             .with_hash(hashed_password)
             .with_password(password)
@@ -1,3 +1,4 @@
+use actix_web::http::header::RETRY_AFTER;
 use actix_web::http::StatusCode;
 use actix_web::web::HttpResponse;
 use actix_web::ResponseError;
@@ -9,6 +10,8 @@ use serde::Serialize;
 pub struct TalliiError {
     pub code: TalliiErrorCode,
     pub message: String,
+    #[serde(skip)]
+    pub retry_after: Option<i64>,
 }
 
 /// Display trait for a TalliiError
@@ -28,6 +31,21 @@ impl TalliiError {
     pub const INVALID_LOGIN: TalliiErrorCode = TalliiErrorCode("INVALID_LOGIN");
     pub const USERNAME_TAKEN: TalliiErrorCode = TalliiErrorCode("USERNAME_TAKEN");
     pub const EMAIL_TAKEN: TalliiErrorCode = TalliiErrorCode("EMAIL_TAKEN");
+    pub const INSUFFICIENT_CURRENCY: TalliiErrorCode = TalliiErrorCode("INSUFFICIENT_CURRENCY");
+    pub const INVALID_TOKEN: TalliiErrorCode = TalliiErrorCode("INVALID_TOKEN");
+    pub const EMAIL_NOT_VERIFIED: TalliiErrorCode = TalliiErrorCode("EMAIL_NOT_VERIFIED");
+    pub const TOO_MANY_REQUESTS: TalliiErrorCode = TalliiErrorCode("TOO_MANY_REQUESTS");
+    pub const ACCOUNT_SUSPENDED: TalliiErrorCode = TalliiErrorCode("ACCOUNT_SUSPENDED");
+    pub const TOO_MANY_ATTEMPTS: TalliiErrorCode = TalliiErrorCode("TOO_MANY_ATTEMPTS");
+    pub const INVALID_TWO_FACTOR_CODE: TalliiErrorCode = TalliiErrorCode("INVALID_TWO_FACTOR_CODE");
+    pub const INSUFFICIENT_SCOPE: TalliiErrorCode = TalliiErrorCode("INSUFFICIENT_SCOPE");
+    pub const PRIVATE_ACCOUNT: TalliiErrorCode = TalliiErrorCode("PRIVATE_ACCOUNT");
+
+    /// Tells the client how many seconds to wait before trying again
+    pub fn retry_after(mut self, seconds: i64) -> TalliiError {
+        self.retry_after = Some(seconds.max(1));
+        self
+    }
 }
 
 /// Representation of the TalliiErrorCodes
@@ -52,6 +70,21 @@ impl TalliiErrorCode {
             TalliiError::INVALID_LOGIN => "User has provided invalid login credentials.",
             TalliiError::USERNAME_TAKEN => "The provided username is not available",
             TalliiError::EMAIL_TAKEN => "The provided email is not available",
+            TalliiError::INSUFFICIENT_CURRENCY => "User does not have enough currency.",
+            TalliiError::INVALID_TOKEN => "The provided token is invalid or has expired.",
+            TalliiError::EMAIL_NOT_VERIFIED => "User must verify their email to fulfill request.",
+            TalliiError::TOO_MANY_REQUESTS => "Too many requests. Please try again later.",
+            TalliiError::ACCOUNT_SUSPENDED => "The account of the user is suspended.",
+            TalliiError::TOO_MANY_ATTEMPTS => {
+                "Too many failed login attempts. Please try again later."
+            }
+            TalliiError::INVALID_TWO_FACTOR_CODE => "The provided two factor code is invalid.",
+            TalliiError::INSUFFICIENT_SCOPE => {
+                "The provided access token does not have the scope to fulfill request."
+            }
+            TalliiError::PRIVATE_ACCOUNT => {
+                "The account of the user is private. Follow the user to see it."
+            }
             _ => "Oops, something seems to have gone wrong on our end.",
         };
 
@@ -59,6 +92,7 @@ impl TalliiErrorCode {
         TalliiError {
             code: self,
             message: message.to_string(),
+            retry_after: None,
         }
     }
 
@@ -67,6 +101,7 @@ impl TalliiErrorCode {
         TalliiError {
             code: self,
             message,
+            retry_after: None,
         }
     }
 }
@@ -106,11 +141,26 @@ impl ResponseError for TalliiError {
             TalliiError::INVALID_LOGIN => StatusCode::BAD_REQUEST,
             TalliiError::USERNAME_TAKEN => StatusCode::BAD_REQUEST,
             TalliiError::EMAIL_TAKEN => StatusCode::BAD_REQUEST,
+            TalliiError::INSUFFICIENT_CURRENCY => StatusCode::BAD_REQUEST,
+            TalliiError::INVALID_TOKEN => StatusCode::BAD_REQUEST,
+            TalliiError::EMAIL_NOT_VERIFIED => StatusCode::FORBIDDEN,
+            TalliiError::TOO_MANY_REQUESTS => StatusCode::TOO_MANY_REQUESTS,
+            TalliiError::ACCOUNT_SUSPENDED => StatusCode::FORBIDDEN,
+            TalliiError::TOO_MANY_ATTEMPTS => StatusCode::TOO_MANY_REQUESTS,
+            TalliiError::INVALID_TWO_FACTOR_CODE => StatusCode::BAD_REQUEST,
+            TalliiError::INSUFFICIENT_SCOPE => StatusCode::FORBIDDEN,
+            TalliiError::PRIVATE_ACCOUNT => StatusCode::FORBIDDEN,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(self)
+        let mut response = HttpResponse::build(self.status_code());
+
+        if let Some(seconds) = self.retry_after {
+            response.header(RETRY_AFTER, seconds.to_string());
+        }
+
+        response.json(self)
     }
 }
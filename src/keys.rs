@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::fs;
+
+use chrono::{NaiveDateTime, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use serde::{Deserialize, Serialize};
+use simple_asn1::ASN1Block;
+
+/// Kid of the key made from JWT_SECRET, tokens signed before keys had a kid are checked with
+/// the key of this kid
+pub const DEFAULT_KID: &str = "default";
+
+/// The configuration of a key jwts are signed or verified with. HS256 keys use the secret,
+/// RS256 and EdDSA keys use the pem files and only need the private key to sign.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub algorithm: String,
+    pub secret: Option<String>,
+    pub private_key_file: Option<String>,
+    pub public_key_file: Option<String>,
+    /// Tokens signed with the key are rejected after this time, used to retire old keys
+    pub accept_until: Option<NaiveDateTime>,
+}
+
+/// The file JWT_KEYS points to
+#[derive(Debug, Deserialize)]
+pub struct JwtKeysConfig {
+    pub signing_kid: String,
+    pub keys: Vec<JwtKeyConfig>,
+}
+
+/// A public key in a json web key set
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicJwk {
+    pub kid: String,
+    pub kty: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+}
+
+/// A json web key set of the public keys other services can verify our tokens with
+#[derive(Debug, Serialize)]
+pub struct PublicJwks {
+    pub keys: Vec<PublicJwk>,
+}
+
+/// A loaded key, the encoding key is only there when the key can sign
+struct JwtKey {
+    algorithm: Algorithm,
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    public_jwk: Option<PublicJwk>,
+    accept_until: Option<NaiveDateTime>,
+}
+
+fn base64_url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Reads the der blocks of a pem file along with the tag of the pem, i.e. PUBLIC KEY
+fn read_pem(path: &str) -> Result<(Vec<u8>, String, Vec<ASN1Block>), String> {
+    let contents = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    let parsed = pem::parse(&contents).map_err(|err| format!("{}: {}", path, err))?;
+    let blocks =
+        simple_asn1::from_der(&parsed.contents).map_err(|err| format!("{}: {}", path, err))?;
+
+    Ok((contents, parsed.tag, blocks))
+}
+
+/// Gets the key out of a SubjectPublicKeyInfo, which is the first bit string in it
+fn first_bit_string(blocks: &[ASN1Block]) -> Option<&[u8]> {
+    blocks.iter().find_map(|block| match block {
+        ASN1Block::Sequence(_, entries) => first_bit_string(entries),
+        ASN1Block::BitString(_, _, bits) => Some(bits.as_slice()),
+        _ => None,
+    })
+}
+
+/// Gets the modulus and exponent of a PKCS#1 rsa public key
+fn rsa_components(blocks: &[ASN1Block]) -> Option<(Vec<u8>, Vec<u8>)> {
+    match blocks.first()? {
+        ASN1Block::Sequence(_, entries) => match (entries.get(0)?, entries.get(1)?) {
+            (ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)) => {
+                Some((n.to_bytes_be().1, e.to_bytes_be().1))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Builds the public jwk of an RS256 public key in either the PKCS#1 or the SubjectPublicKeyInfo
+/// format
+fn rsa_public_jwk(kid: &str, tag: &str, blocks: &[ASN1Block]) -> Option<PublicJwk> {
+    let (n, e) = if tag == "RSA PUBLIC KEY" {
+        rsa_components(blocks)?
+    } else {
+        rsa_components(&simple_asn1::from_der(first_bit_string(blocks)?).ok()?)?
+    };
+
+    Some(PublicJwk {
+        kid: kid.to_string(),
+        kty: String::from("RSA"),
+        alg: String::from("RS256"),
+        key_use: String::from("sig"),
+        n: Some(base64_url(&n)),
+        e: Some(base64_url(&e)),
+        crv: None,
+        x: None,
+    })
+}
+
+/// Builds the public jwk of an Ed25519 public key
+fn ed_public_jwk(kid: &str, blocks: &[ASN1Block]) -> Option<PublicJwk> {
+    Some(PublicJwk {
+        kid: kid.to_string(),
+        kty: String::from("OKP"),
+        alg: String::from("EdDSA"),
+        key_use: String::from("sig"),
+        n: None,
+        e: None,
+        crv: Some(String::from("Ed25519")),
+        x: Some(base64_url(first_bit_string(blocks)?)),
+    })
+}
+
+impl JwtKey {
+    fn load(config: &JwtKeyConfig) -> Result<Self, String> {
+        let kid = &config.kid;
+
+        if config.algorithm == "HS256" {
+            let secret = config
+                .secret
+                .as_deref()
+                .ok_or_else(|| format!("{} needs a secret", kid))?;
+
+            return Ok(JwtKey {
+                algorithm: Algorithm::HS256,
+                encoding: Some(EncodingKey::from_secret(secret.as_bytes())),
+                decoding: DecodingKey::from_secret(secret.as_bytes()),
+                public_jwk: None,
+                accept_until: config.accept_until,
+            });
+        }
+
+        let public_key_file = config
+            .public_key_file
+            .as_deref()
+            .ok_or_else(|| format!("{} needs a public_key_file", kid))?;
+        let (public_pem, tag, blocks) = read_pem(public_key_file)?;
+        let private_pem = match &config.private_key_file {
+            Some(file) => Some(fs::read(file).map_err(|err| format!("{}: {}", file, err))?),
+            None => None,
+        };
+
+        let (algorithm, encoding, decoding, public_jwk) = match config.algorithm.as_str() {
+            "RS256" => (
+                Algorithm::RS256,
+                private_pem
+                    .map(|pem| EncodingKey::from_rsa_pem(&pem))
+                    .transpose(),
+                DecodingKey::from_rsa_pem(&public_pem),
+                rsa_public_jwk(kid, &tag, &blocks),
+            ),
+            "EdDSA" => (
+                Algorithm::EdDSA,
+                private_pem
+                    .map(|pem| EncodingKey::from_ed_pem(&pem))
+                    .transpose(),
+                DecodingKey::from_ed_pem(&public_pem),
+                ed_public_jwk(kid, &blocks),
+            ),
+            algorithm => {
+                return Err(format!(
+                    "{} uses {}, only HS256, RS256 and EdDSA are supported",
+                    kid, algorithm
+                ))
+            }
+        };
+
+        Ok(JwtKey {
+            algorithm,
+            encoding: encoding.map_err(|err| format!("{}: {}", kid, err))?,
+            decoding: decoding.map_err(|err| format!("{}: {}", kid, err))?,
+            public_jwk: Some(
+                public_jwk.ok_or_else(|| format!("{}: the public key could not be read", kid))?,
+            ),
+            accept_until: config.accept_until,
+        })
+    }
+
+    /// Checks if tokens signed with the key are still accepted
+    fn is_accepted(&self, now: NaiveDateTime) -> bool {
+        self.accept_until.map_or(true, |until| now < until)
+    }
+}
+
+/// The keys jwts are signed with. New tokens are signed with the signing key and tokens signed
+/// with any other key are accepted until the cutoff of that key, so keys can be rotated without
+/// logging everyone out.
+pub struct JwtKeys {
+    signing_kid: String,
+    keys: HashMap<String, JwtKey>,
+}
+
+impl JwtKeys {
+    /// Creates the keys and checks that the signing key can sign
+    pub fn new(config: JwtKeysConfig) -> Result<Self, String> {
+        let mut keys = HashMap::new();
+
+        for key in &config.keys {
+            if keys.insert(key.kid.clone(), JwtKey::load(key)?).is_some() {
+                return Err(format!("{} is used by more than one key", key.kid));
+            }
+        }
+
+        match keys.get(&config.signing_kid) {
+            Some(key) if key.encoding.is_none() => Err(format!(
+                "{} needs a private_key_file to sign tokens",
+                config.signing_kid
+            )),
+            Some(key) if key.accept_until.is_some() => Err(format!(
+                "{} signs tokens so it can not have an accept_until",
+                config.signing_kid
+            )),
+            Some(_) => Ok(JwtKeys {
+                signing_kid: config.signing_kid,
+                keys,
+            }),
+            None => Err(format!("{} is not one of the keys", config.signing_kid)),
+        }
+    }
+
+    /// Creates the keys from the json file JWT_KEYS points to
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let config: JwtKeysConfig =
+            serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+
+        JwtKeys::new(config)
+    }
+
+    /// Creates a single HS256 key from a shared secret
+    pub fn from_secret(secret: &str) -> Result<Self, String> {
+        JwtKeys::new(JwtKeysConfig {
+            signing_kid: DEFAULT_KID.to_string(),
+            keys: vec![JwtKeyConfig {
+                kid: DEFAULT_KID.to_string(),
+                algorithm: String::from("HS256"),
+                secret: Some(secret.to_string()),
+                private_key_file: None,
+                public_key_file: None,
+                accept_until: None,
+            }],
+        })
+    }
+
+    /// The kid, algorithm and key new tokens are signed with
+    pub fn signing_key(&self) -> Option<(&str, Algorithm, &EncodingKey)> {
+        let key = self.keys.get(&self.signing_kid)?;
+
+        Some((&self.signing_kid, key.algorithm, key.encoding.as_ref()?))
+    }
+
+    /// The algorithm and key to verify a token with the kid, if the key is still accepted
+    pub fn verification_key(&self, kid: Option<&str>) -> Option<(Algorithm, &DecodingKey)> {
+        let key = self.keys.get(kid.unwrap_or(DEFAULT_KID))?;
+
+        if !key.is_accepted(Utc::now().naive_utc()) {
+            return None;
+        }
+
+        Some((key.algorithm, &key.decoding))
+    }
+
+    /// The public keys that are still accepted, shared secrets are never published
+    pub fn public_jwks(&self) -> PublicJwks {
+        let now = Utc::now().naive_utc();
+
+        let mut keys: Vec<PublicJwk> = self
+            .keys
+            .values()
+            .filter(|key| key.is_accepted(now))
+            .filter_map(|key| key.public_jwk.clone())
+            .collect();
+
+        keys.sort_by(|a, b| a.kid.cmp(&b.kid));
+
+        PublicJwks { keys }
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::PgPool;
+
+use crate::errors::TalliiError;
+
+/// Entries the in-process store keeps before it drops the stale ones
+const MAX_MEMORY_ENTRIES: usize = 10_000;
+
+/// How many failures a key can have before it is locked out and for how long
+#[derive(Debug, Clone, Copy)]
+pub struct AttemptPolicy {
+    /// Failures allowed before the first lockout
+    pub threshold: i32,
+    /// Seconds of the first lockout, every further failure doubles it
+    pub base_lockout_seconds: i64,
+    /// Longest possible lockout in seconds
+    pub max_lockout_seconds: i64,
+    /// Seconds without a failure after which the failures are forgotten
+    pub reset_after_seconds: i64,
+}
+
+/// Policy for failed logins of a single account
+pub const EMAIL_POLICY: AttemptPolicy = AttemptPolicy {
+    threshold: 5,
+    base_lockout_seconds: 60,
+    max_lockout_seconds: 24 * 60 * 60,
+    reset_after_seconds: 24 * 60 * 60,
+};
+
+/// Policy for failed logins from a single ip address, which may be shared by many people
+pub const IP_POLICY: AttemptPolicy = AttemptPolicy {
+    threshold: 20,
+    base_lockout_seconds: 60,
+    max_lockout_seconds: 60 * 60,
+    reset_after_seconds: 60 * 60,
+};
+
+impl AttemptPolicy {
+    /// The lockout after the provided amount of failures, if there is one
+    fn lockout(&self, failures: i32) -> Option<Duration> {
+        if failures < self.threshold {
+            return None;
+        }
+
+        // cap the exponent so the shift can not overflow
+        let doublings = (failures - self.threshold).min(30) as u32;
+        let seconds = self
+            .base_lockout_seconds
+            .saturating_mul(1_i64 << doublings)
+            .min(self.max_lockout_seconds);
+
+        Some(Duration::seconds(seconds))
+    }
+}
+
+/// The failed attempts of a single key
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct AttemptRecord {
+    pub failures: i32,
+    pub last_failed_at: NaiveDateTime,
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+impl AttemptRecord {
+    /// Adds a failure to the record, forgetting the previous failures when they are too old
+    fn fail(record: Option<AttemptRecord>, policy: &AttemptPolicy, now: NaiveDateTime) -> Self {
+        let failures = match record {
+            Some(record)
+                if now - record.last_failed_at < Duration::seconds(policy.reset_after_seconds) =>
+            {
+                record.failures + 1
+            }
+            _ => 1,
+        };
+
+        AttemptRecord {
+            failures,
+            last_failed_at: now,
+            locked_until: policy.lockout(failures).map(|lockout| now + lockout),
+        }
+    }
+
+    /// Checks if the record is no longer locked and its failures would be forgotten by any policy
+    fn is_stale(&self, now: NaiveDateTime) -> bool {
+        self.locked_until.map_or(true, |until| until <= now)
+            && now - self.last_failed_at > Duration::seconds(EMAIL_POLICY.reset_after_seconds)
+    }
+}
+
+/// Where the failed attempts are kept, the in-process store only works for a single instance
+pub enum AttemptStore {
+    Memory(Mutex<HashMap<String, AttemptRecord>>),
+    Postgres(PgPool),
+}
+
+impl AttemptStore {
+    async fn get(&self, key: &str) -> Result<Option<AttemptRecord>, TalliiError> {
+        match self {
+            AttemptStore::Memory(records) => Ok(records
+                .lock()
+                .map_err(|_err| TalliiError::INTERNAL_SERVER_ERROR.default())?
+                .get(key)
+                .cloned()),
+            AttemptStore::Postgres(pool) => {
+                let record = sqlx::query_as::<_, AttemptRecord>(
+                    r#"
+                        select
+                            failures,
+                            last_failed_at,
+                            locked_until
+                        from
+                            login_attempts
+                        where
+                            key = $1
+                    "#,
+                )
+                .bind(key)
+                .fetch_optional(pool)
+                .await?;
+
+                Ok(record)
+            }
+        }
+    }
+
+    async fn fail(
+        &self,
+        key: &str,
+        policy: &AttemptPolicy,
+        now: NaiveDateTime,
+    ) -> Result<AttemptRecord, TalliiError> {
+        match self {
+            AttemptStore::Memory(records) => {
+                let mut records = records
+                    .lock()
+                    .map_err(|_err| TalliiError::INTERNAL_SERVER_ERROR.default())?;
+
+                if records.len() > MAX_MEMORY_ENTRIES {
+                    records.retain(|_key, record| !record.is_stale(now));
+                }
+
+                let record = AttemptRecord::fail(records.remove(key), policy, now);
+                records.insert(key.to_string(), record.clone());
+
+                Ok(record)
+            }
+            AttemptStore::Postgres(pool) => {
+                // start the transaction
+                let mut tx = pool.begin().await?;
+
+                let existing = sqlx::query_as::<_, AttemptRecord>(
+                    r#"
+                        select
+                            failures,
+                            last_failed_at,
+                            locked_until
+                        from
+                            login_attempts
+                        where
+                            key = $1
+                        for update
+                    "#,
+                )
+                .bind(key)
+                .fetch_optional(&mut tx)
+                .await?;
+
+                let record = AttemptRecord::fail(existing, policy, now);
+
+                sqlx::query(
+                    r#"
+                        insert into
+                            login_attempts (key, failures, last_failed_at, locked_until)
+                        values
+                            ($1, $2, $3, $4)
+                        on conflict (key) do update set
+                            failures = excluded.failures,
+                            last_failed_at = excluded.last_failed_at,
+                            locked_until = excluded.locked_until
+                    "#,
+                )
+                .bind(key)
+                .bind(record.failures)
+                .bind(record.last_failed_at)
+                .bind(record.locked_until)
+                .execute(&mut tx)
+                .await?;
+
+                // commit the transaction
+                tx.commit().await?;
+
+                Ok(record)
+            }
+        }
+    }
+
+    async fn clear(&self, key: &str) -> Result<(), TalliiError> {
+        match self {
+            AttemptStore::Memory(records) => {
+                records
+                    .lock()
+                    .map_err(|_err| TalliiError::INTERNAL_SERVER_ERROR.default())?
+                    .remove(key);
+
+                Ok(())
+            }
+            AttemptStore::Postgres(pool) => {
+                sqlx::query("delete from login_attempts where key = $1")
+                    .bind(key)
+                    .execute(pool)
+                    .await?;
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Limits failed logins per ip address and per account
+#[derive(Clone)]
+pub struct LoginLimiter {
+    store: Arc<AttemptStore>,
+}
+
+impl LoginLimiter {
+    pub fn new(store: AttemptStore) -> Self {
+        LoginLimiter {
+            store: Arc::new(store),
+        }
+    }
+
+    /// Creates a limiter that keeps the failed attempts in memory
+    pub fn in_memory() -> Self {
+        LoginLimiter::new(AttemptStore::Memory(Mutex::new(HashMap::new())))
+    }
+
+    fn ip_key(ip_address: &str) -> String {
+        format!("ip:{}", ip_address)
+    }
+
+    fn email_key(email: &str) -> String {
+        format!("email:{}", email.trim().to_lowercase())
+    }
+
+    /// Fails with TOO_MANY_ATTEMPTS while the ip address or the email is locked out
+    pub async fn check(&self, ip_address: Option<&str>, email: &str) -> Result<(), TalliiError> {
+        let now = Utc::now().naive_utc();
+
+        let mut keys = vec![LoginLimiter::email_key(email)];
+
+        if let Some(ip_address) = ip_address {
+            keys.push(LoginLimiter::ip_key(ip_address));
+        }
+
+        let mut locked_until: Option<NaiveDateTime> = None;
+
+        for key in keys {
+            if let Some(until) = self.store.get(&key).await?.and_then(|r| r.locked_until) {
+                if until > now && locked_until.map_or(true, |latest| until > latest) {
+                    locked_until = Some(until);
+                }
+            }
+        }
+
+        match locked_until {
+            Some(until) => {
+                Err(TalliiError::TOO_MANY_ATTEMPTS.retry_after((until - now).num_seconds()))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Records a failed login for the ip address and the email, returns the seconds the account
+    /// is locked out for when this failure locked it
+    pub async fn record_failure(
+        &self,
+        ip_address: Option<&str>,
+        email: &str,
+    ) -> Result<Option<i64>, TalliiError> {
+        let now = Utc::now().naive_utc();
+
+        if let Some(ip_address) = ip_address {
+            self.store
+                .fail(&LoginLimiter::ip_key(ip_address), &IP_POLICY, now)
+                .await?;
+        }
+
+        let record = self
+            .store
+            .fail(&LoginLimiter::email_key(email), &EMAIL_POLICY, now)
+            .await?;
+
+        Ok(record.locked_until.map(|until| (until - now).num_seconds()))
+    }
+
+    /// Forgets the failed logins of the email after a successful login
+    pub async fn record_success(&self, email: &str) -> Result<(), TalliiError> {
+        self.store.clear(&LoginLimiter::email_key(email)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_policy_locks_out_at_the_threshold() {
+        assert_eq!(EMAIL_POLICY.lockout(4), None);
+        assert_eq!(EMAIL_POLICY.lockout(5), Some(Duration::seconds(60)));
+    }
+
+    #[test]
+    fn ip_policy_locks_out_at_the_threshold() {
+        assert_eq!(IP_POLICY.lockout(19), None);
+        assert_eq!(IP_POLICY.lockout(20), Some(Duration::seconds(60)));
+    }
+
+    #[test]
+    fn lockout_doubles_with_every_failure() {
+        assert_eq!(EMAIL_POLICY.lockout(6), Some(Duration::seconds(120)));
+        assert_eq!(EMAIL_POLICY.lockout(7), Some(Duration::seconds(240)));
+        assert_eq!(IP_POLICY.lockout(21), Some(Duration::seconds(120)));
+    }
+
+    #[test]
+    fn lockout_is_capped_without_overflowing() {
+        assert_eq!(EMAIL_POLICY.lockout(16), Some(Duration::hours(24)));
+        assert_eq!(EMAIL_POLICY.lockout(i32::MAX), Some(Duration::hours(24)));
+        assert_eq!(IP_POLICY.lockout(26), Some(Duration::hours(1)));
+        assert_eq!(IP_POLICY.lockout(i32::MAX), Some(Duration::hours(1)));
+    }
+
+    #[test]
+    fn fail_counts_recent_failures() {
+        let now = NaiveDateTime::from_timestamp(1_600_000_000, 0);
+        let record = AttemptRecord {
+            failures: 4,
+            last_failed_at: now - Duration::minutes(1),
+            locked_until: None,
+        };
+
+        let record = AttemptRecord::fail(Some(record), &EMAIL_POLICY, now);
+
+        assert_eq!(record.failures, 5);
+        assert_eq!(record.locked_until, Some(now + Duration::seconds(60)));
+    }
+
+    #[test]
+    fn fail_forgets_old_failures() {
+        let now = NaiveDateTime::from_timestamp(1_600_000_000, 0);
+        let record = AttemptRecord {
+            failures: 10,
+            last_failed_at: now - Duration::hours(25),
+            locked_until: Some(now - Duration::hours(1)),
+        };
+
+        let record = AttemptRecord::fail(Some(record), &EMAIL_POLICY, now);
+
+        assert_eq!(record.failures, 1);
+        assert_eq!(record.locked_until, None);
+    }
+
+    #[actix_rt::test]
+    async fn success_resets_the_failures_of_the_email() {
+        let limiter = LoginLimiter::in_memory();
+
+        for _ in 0..EMAIL_POLICY.threshold {
+            assert!(limiter.record_failure(None, "adam@hey.com").await.is_ok());
+        }
+
+        assert!(limiter.check(None, "adam@hey.com").await.is_err());
+
+        assert!(limiter.record_success("adam@hey.com").await.is_ok());
+
+        assert!(limiter.check(None, "adam@hey.com").await.is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use actix_web::web;
+use chrono::Utc;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+use crate::errors::TalliiError;
+
+/// Represents an email to send
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Something that is able to deliver an email, the delivery happens on a blocking thread
+pub trait MailTransport: Send + Sync {
+    fn send(&self, email: &Email) -> Result<(), String>;
+}
+
+/// Delivers emails through an smtp relay
+pub struct SmtpMailTransport {
+    from: String,
+    transport: SmtpTransport,
+}
+
+impl SmtpMailTransport {
+    /// Creates a transport that relays through the provided host using the credentials
+    pub fn new(
+        from: String,
+        host: &str,
+        username: String,
+        password: String,
+    ) -> Result<Self, String> {
+        let transport = SmtpTransport::relay(host)
+            .map_err(|err| err.to_string())?
+            .credentials(Credentials::new(username, password))
+            .build();
+
+        Ok(SmtpMailTransport { from, transport })
+    }
+}
+
+impl MailTransport for SmtpMailTransport {
+    fn send(&self, email: &Email) -> Result<(), String> {
+        let message = Message::builder()
+            .from(self.from.parse().map_err(|_err| "invalid from address")?)
+            .to(email.to.parse().map_err(|_err| "invalid to address")?)
+            .subject(email.subject.clone())
+            .body(email.body.clone())
+            .map_err(|err| err.to_string())?;
+
+        self.transport
+            .send(&message)
+            .map(|_response| ())
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Writes emails to a directory and the log instead of sending them, used for local development
+pub struct OutboxMailTransport {
+    dir: PathBuf,
+}
+
+impl OutboxMailTransport {
+    pub fn new(dir: PathBuf) -> Self {
+        OutboxMailTransport { dir }
+    }
+}
+
+impl MailTransport for OutboxMailTransport {
+    fn send(&self, email: &Email) -> Result<(), String> {
+        info!(
+            "outbox email to {}: {}\n{}",
+            email.to, email.subject, email.body
+        );
+
+        fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+
+        // the recipient is hashed so an address can never escape the outbox directory
+        let path = self.dir.join(format!(
+            "{}-{:x}.txt",
+            Utc::now().format("%Y%m%d%H%M%S%f"),
+            Sha256::digest(email.to.as_bytes())
+        ));
+
+        fs::write(
+            path,
+            format!(
+                "To: {}\nSubject: {}\n\n{}\n",
+                email.to, email.subject, email.body
+            ),
+        )
+        .map_err(|err| err.to_string())
+    }
+}
+
+/// Sends emails to users through the configured transport
+#[derive(Clone)]
+pub struct Mailer {
+    pub app_url: Arc<String>,
+    transport: Arc<dyn MailTransport>,
+}
+
+impl Mailer {
+    pub fn new(app_url: String, transport: Arc<dyn MailTransport>) -> Self {
+        Mailer {
+            app_url: Arc::new(app_url),
+            transport,
+        }
+    }
+
+    /// Sends the provided email
+    pub async fn send(&self, email: Email) -> Result<(), TalliiError> {
+        let transport = self.transport.clone();
+
+        web::block(move || transport.send(&email))
+            .await
+            .map_err(|err| {
+                error!("Failed to send email: {:?}", err);
+                TalliiError::INTERNAL_SERVER_ERROR.message("Failed to send email".to_string())
+            })
+    }
+}
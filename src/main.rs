@@ -5,10 +5,14 @@ use tracing::{info, instrument};
 mod config;
 mod crypto;
 mod errors;
+mod keys;
+mod limiter;
+mod mailer;
+mod oidc;
 mod routes;
 mod services;
 
-use crate::routes::define_routes;
+use crate::routes::{define_routes, define_well_known_routes};
 
 #[actix_rt::main]
 #[instrument]
@@ -23,7 +27,18 @@ async fn main() -> std::io::Result<()> {
         .expect("failed to create database pool");
 
     // get instance of crypto to be used for hashing
-    let crypto = config.get_crypto();
+    let crypto = config.get_crypto().expect("failed to configure crypto");
+
+    // get the mailer used to send emails to users
+    let mailer = config.get_mailer().expect("failed to configure mailer");
+
+    // get the limiter that protects logins from brute forcing
+    let login_limiter = config.get_login_limiter(&pool);
+
+    // get the verifier for id tokens of the oidc providers
+    let oidc_verifier = config
+        .get_oidc_verifier()
+        .expect("failed to configure oidc providers");
 
     info!(
         "starting server at http://{}:{}",
@@ -46,7 +61,11 @@ async fn main() -> std::io::Result<()> {
             .wrap(Logger::default())
             .data(pool.clone())
             .data(crypto.clone())
+            .data(mailer.clone())
+            .data(login_limiter.clone())
+            .data(oidc_verifier.clone())
             .service(web::scope("/api/v1").configure(define_routes))
+            .configure(define_well_known_routes)
             .route(
                 "/",
                 web::get().to(|| web::HttpResponse::Ok().json("Healthy")),
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, RwLock};
+
+use actix_web::client::Client;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::errors::TalliiError;
+
+/// Minutes to wait before fetching the keys of a provider again when a token has an unknown kid
+const JWKS_REFRESH_MINUTES: i64 = 10;
+
+/// Max size of a jwks response
+const MAX_JWKS_BYTES: usize = 1024 * 1024;
+
+/// The configuration of an OpenID Connect provider, the keys are loaded from the url or the file
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcProviderConfig {
+    pub name: String,
+    pub issuers: Vec<String>,
+    pub client_ids: Vec<String>,
+    pub jwks_url: Option<String>,
+    pub jwks_file: Option<String>,
+}
+
+/// The file OIDC_CONFIG points to
+#[derive(Debug, Deserialize)]
+pub struct OidcConfig {
+    pub providers: Vec<OidcProviderConfig>,
+}
+
+/// A single json web key, only rsa keys are supported
+#[derive(Debug, Clone, Deserialize)]
+pub struct Jwk {
+    pub kid: String,
+    pub kty: String,
+    pub n: Option<String>,
+    pub e: Option<String>,
+}
+
+/// A json web key set
+#[derive(Debug, Deserialize)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+/// The claims of an id token that are used to find or create the user
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub email: Option<String>,
+    pub email_verified: Option<serde_json::Value>,
+}
+
+impl IdTokenClaims {
+    /// Checks if the provider verified the email, some providers send the flag as a string
+    pub fn is_email_verified(&self) -> bool {
+        match &self.email_verified {
+            Some(serde_json::Value::Bool(verified)) => *verified,
+            Some(serde_json::Value::String(verified)) => verified == "true",
+            _ => false,
+        }
+    }
+}
+
+/// A provider with the keys it signs id tokens with
+struct OidcProvider {
+    config: OidcProviderConfig,
+    keys: RwLock<HashMap<String, Jwk>>,
+    fetched_at: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl OidcProvider {
+    fn set_keys(&self, jwks: Jwks) {
+        let keys = jwks
+            .keys
+            .into_iter()
+            .filter(|key| key.kty == "RSA")
+            .map(|key| (key.kid.clone(), key))
+            .collect();
+
+        if let Ok(mut current) = self.keys.write() {
+            *current = keys;
+        }
+    }
+
+    fn get_key(&self, kid: &str) -> Option<Jwk> {
+        self.keys.read().ok()?.get(kid).cloned()
+    }
+
+    /// Fetches the keys from the jwks url, at most once every few minutes
+    async fn refresh(&self) -> Result<(), String> {
+        let url = match &self.config.jwks_url {
+            Some(url) => url,
+            None => return Ok(()),
+        };
+
+        {
+            let mut fetched_at = self.fetched_at.write().map_err(|err| err.to_string())?;
+            let now = Utc::now();
+
+            if fetched_at.map_or(false, |at| {
+                now - at < Duration::minutes(JWKS_REFRESH_MINUTES)
+            }) {
+                return Ok(());
+            }
+
+            *fetched_at = Some(now);
+        }
+
+        let jwks = Client::default()
+            .get(url)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?
+            .json::<Jwks>()
+            .limit(MAX_JWKS_BYTES)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        info!("loaded {} keys for {}", jwks.keys.len(), self.config.name);
+
+        self.set_keys(jwks);
+
+        Ok(())
+    }
+}
+
+/// Validates id tokens against the keys of the configured providers
+#[derive(Clone)]
+pub struct OidcVerifier {
+    providers: Arc<HashMap<String, OidcProvider>>,
+}
+
+impl OidcVerifier {
+    /// Creates a verifier, the keys of providers with a jwks file are loaded right away while
+    /// the keys of providers with a jwks url are fetched when they are first needed
+    pub fn new(providers: Vec<OidcProviderConfig>) -> Result<Self, String> {
+        let mut loaded = HashMap::new();
+
+        for config in providers {
+            let provider = OidcProvider {
+                config: config.clone(),
+                keys: RwLock::new(HashMap::new()),
+                fetched_at: RwLock::new(None),
+            };
+
+            match (&config.jwks_file, &config.jwks_url) {
+                (Some(file), _) => {
+                    let contents = fs::read_to_string(file).map_err(|err| err.to_string())?;
+                    let jwks: Jwks =
+                        serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+
+                    provider.set_keys(jwks);
+                }
+                (None, Some(_)) => {}
+                (None, None) => {
+                    return Err(format!(
+                        "{} needs either a jwks_url or a jwks_file",
+                        config.name
+                    ))
+                }
+            }
+
+            loaded.insert(config.name.clone(), provider);
+        }
+
+        Ok(OidcVerifier {
+            providers: Arc::new(loaded),
+        })
+    }
+
+    /// Creates a verifier from the json file OIDC_CONFIG points to
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let config: OidcConfig = serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+
+        OidcVerifier::new(config.providers)
+    }
+
+    /// Validates the signature, issuer, audience and expiry of the id token of the provider
+    pub async fn verify(
+        &self,
+        provider: &str,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, TalliiError> {
+        let provider = self.providers.get(provider).ok_or_else(|| {
+            TalliiError::BAD_REQUEST
+                .message(String::from("The provided provider is not supported."))
+        })?;
+
+        let header =
+            decode_header(id_token).map_err(|_err| TalliiError::INVALID_TOKEN.default())?;
+        let kid = header
+            .kid
+            .ok_or_else(|| TalliiError::INVALID_TOKEN.default())?;
+
+        // the provider may have rotated its keys since they were last fetched
+        let key = match provider.get_key(&kid) {
+            Some(key) => key,
+            None => {
+                if let Err(err) = provider.refresh().await {
+                    error!("Failed to fetch jwks of {}: {}", provider.config.name, err);
+                }
+
+                provider
+                    .get_key(&kid)
+                    .ok_or_else(|| TalliiError::INVALID_TOKEN.default())?
+            }
+        };
+
+        let (n, e) = match (&key.n, &key.e) {
+            (Some(n), Some(e)) => (n, e),
+            _ => return Err(TalliiError::INVALID_TOKEN.default()),
+        };
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&provider.config.client_ids);
+
+        let decoding_key = DecodingKey::from_rsa_components(n, e)
+            .map_err(|_err| TalliiError::INVALID_TOKEN.default())?;
+
+        let claims = decode::<IdTokenClaims>(id_token, &decoding_key, &validation)
+            .map_err(|_err| TalliiError::INVALID_TOKEN.default())?
+            .claims;
+
+        if !provider.config.issuers.contains(&claims.iss) {
+            return Err(TalliiError::INVALID_TOKEN.default());
+        }
+
+        Ok(claims)
+    }
+}
@@ -1,8 +1,14 @@
 use actix_web::web;
 
+use crate::services::admin::routes::admin_scope;
+use crate::services::contacts::routes::{me_contacts_match_routes, me_contacts_salt_routes};
+use crate::services::currency::routes::{me_currency_routes, me_currency_transactions_routes};
 use crate::services::events::members::routes::{
     event_members_invitations_routes, event_members_routes, event_members_user_routes,
 };
+use crate::services::events::pools::routes::{
+    event_pool_bets_entity_routes, event_pool_bets_routes, event_pool_routes,
+};
 use crate::services::events::routes::{
     events_entity_routes, events_me_routes, events_routes, users_me_events_invites_routes,
     users_username_events_routes,
@@ -12,14 +18,40 @@ use crate::services::events::teams::routes::{
     event_teams_routes,
 };
 use crate::services::friends::routes::{
-    users_me_follow_username_routes, users_me_followers_routes, users_me_following_routes,
-    users_me_following_username_routes, users_username_followers_routes,
-    users_username_following_routes,
+    users_me_blocks_routes, users_me_blocks_username_routes, users_me_follow_requests_routes,
+    users_me_follow_requests_username_routes, users_me_follow_username_routes,
+    users_me_followers_routes, users_me_following_routes, users_me_following_username_routes,
+    users_me_suggestions_routes, users_username_followers_routes, users_username_following_routes,
+    users_username_mutuals_routes,
+};
+use crate::services::identities::routes::{
+    login_oidc_routes, me_identities_entity_routes, me_identities_routes,
+};
+use crate::services::keys::routes::well_known_jwks_routes;
+use crate::services::passwords::routes::{
+    me_password_routes, password_forgot_routes, password_reset_routes,
+};
+use crate::services::search::routes::{search_events_routes, search_users_routes};
+use crate::services::sessions::routes::{
+    logout_all_routes, logout_routes, me_sessions_entity_routes, me_sessions_routes,
+    token_refresh_routes,
+};
+use crate::services::tokens::routes::{me_tokens_entity_routes, me_tokens_routes};
+use crate::services::two_factor::routes::{
+    login_two_factor_routes, me_two_factor_confirm_routes, me_two_factor_routes,
 };
 use crate::services::users::routes::{
     invite_codes_entity_routes, invite_codes_routes, users_email_check_routes, users_login,
-    users_me_routes, users_signup, users_username_check_routes, users_username_routes,
-    users_username_search_routes,
+    users_me_email_routes, users_me_export_routes, users_me_invite_codes_routes, users_me_routes,
+    users_me_username_routes, users_signup, users_username_check_routes, users_username_routes,
+};
+use crate::services::verification::routes::{me_resend_verification_routes, verify_email_routes};
+use crate::services::wagers::routes::{
+    event_wagers_settlement_routes, me_wagers_invitations_routes, me_wagers_routes,
+    user_wagers_routes, wager_accept_routes, wager_entity_routes, wagers_routes,
+};
+use crate::services::waitlist::routes::{
+    waitlist_position_routes, waitlist_routes, waitlist_vouches_routes,
 };
 
 use crate::services::events::comments::routes::{event_comments, event_comments_entity};
@@ -28,18 +60,52 @@ pub fn define_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(invite_codes_routes())
         .service(invite_codes_entity_routes())
         .service(users_login())
+        .service(login_two_factor_routes())
+        .service(login_oidc_routes())
         .service(users_signup())
+        .service(waitlist_routes())
+        .service(waitlist_position_routes())
+        .service(waitlist_vouches_routes())
+        .service(token_refresh_routes())
+        .service(logout_routes())
+        .service(logout_all_routes())
+        .service(me_sessions_routes())
+        .service(me_sessions_entity_routes())
+        .service(me_tokens_routes())
+        .service(me_tokens_entity_routes())
+        .service(password_forgot_routes())
+        .service(password_reset_routes())
+        .service(me_password_routes())
+        .service(me_two_factor_routes())
+        .service(me_two_factor_confirm_routes())
+        .service(me_identities_routes())
+        .service(me_identities_entity_routes())
+        .service(verify_email_routes())
+        .service(me_resend_verification_routes())
         .service(users_username_check_routes())
         .service(users_email_check_routes())
         .service(users_me_routes())
+        .service(users_me_username_routes())
+        .service(users_me_email_routes())
+        .service(users_me_export_routes())
+        .service(users_me_invite_codes_routes())
         .service(users_username_routes())
-        .service(users_username_search_routes())
+        .service(search_users_routes())
+        .service(search_events_routes())
         .service(users_me_followers_routes())
         .service(users_me_follow_username_routes())
         .service(users_me_following_username_routes())
         .service(users_username_followers_routes())
         .service(users_username_following_routes())
         .service(users_me_following_routes())
+        .service(users_me_follow_requests_routes())
+        .service(users_me_follow_requests_username_routes())
+        .service(users_me_blocks_routes())
+        .service(users_me_blocks_username_routes())
+        .service(users_me_suggestions_routes())
+        .service(users_username_mutuals_routes())
+        .service(me_contacts_salt_routes())
+        .service(me_contacts_match_routes())
         .service(events_routes())
         .service(events_me_routes())
         .service(users_username_events_routes())
@@ -53,5 +119,23 @@ pub fn define_routes(cfg: &mut web::ServiceConfig) {
         .service(event_teams_routes())
         .service(event_teams_entity_routes())
         .service(event_teams_members_routes())
-        .service(event_teams_members_entity_routes());
+        .service(event_teams_members_entity_routes())
+        .service(wagers_routes())
+        .service(me_wagers_routes())
+        .service(me_wagers_invitations_routes())
+        .service(user_wagers_routes())
+        .service(wager_entity_routes())
+        .service(wager_accept_routes())
+        .service(event_wagers_settlement_routes())
+        .service(event_pool_routes())
+        .service(event_pool_bets_routes())
+        .service(event_pool_bets_entity_routes())
+        .service(me_currency_routes())
+        .service(me_currency_transactions_routes())
+        .service(admin_scope());
+}
+
+/// Routes served from the root instead of under the api version, i.e. for other services
+pub fn define_well_known_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(well_known_jwks_routes());
 }
@@ -0,0 +1,224 @@
+use sqlx::{PgPool, Transaction};
+
+use crate::errors::TalliiError;
+
+use super::models::{
+    AdminUserQuery, AdminUserView, AuditAction, AuditLogEntry, AuditLogQuery, InviteTreeNode,
+    SignupsQuery, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, MAX_USERS,
+};
+
+/// Selects the users as seen by moderators
+const SELECT_ADMIN_USER_VIEWS: &str = r#"
+    select
+        user_id,
+        username,
+        email,
+        emoji,
+        bg_color,
+        bio,
+        verified,
+        role,
+        currency,
+        invite_code,
+        invited_by,
+        suspended_at,
+        suspended_until,
+        suspension_reason,
+        deleted_at,
+        created_at
+    from
+        users
+"#;
+
+pub struct AdminUsersTable;
+
+impl AdminUsersTable {
+    /// Searches every user, including unverified, suspended and deleted users, by email or
+    /// username
+    pub async fn search(
+        pool: &PgPool,
+        params: &AdminUserQuery,
+    ) -> Result<Vec<AdminUserView>, TalliiError> {
+        let users = sqlx::query_as::<_, AdminUserView>(&format!(
+            r#"
+                {}
+                where
+                    username ilike $1
+                or
+                    email ilike $1
+                order by
+                    username
+                limit
+                    $2
+            "#,
+            SELECT_ADMIN_USER_VIEWS
+        ))
+        .bind(format!("%{}%", params.q))
+        .bind(MAX_USERS)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    /// Gets a single user by their id
+    pub async fn get_by_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Option<AdminUserView>, TalliiError> {
+        let user = sqlx::query_as::<_, AdminUserView>(&format!(
+            "{} where user_id = $1",
+            SELECT_ADMIN_USER_VIEWS
+        ))
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    /// Gets the most recent signups, optionally only the ones that used the invite code
+    pub async fn get_recent_signups(
+        pool: &PgPool,
+        params: &SignupsQuery,
+    ) -> Result<Vec<AdminUserView>, TalliiError> {
+        let limit = params.limit.unwrap_or(MAX_USERS).max(1).min(MAX_USERS);
+
+        let users = sqlx::query_as::<_, AdminUserView>(&format!(
+            r#"
+                {}
+                where
+                    ($1::text is null or invite_code = $1)
+                order by
+                    created_at desc
+                limit
+                    $2
+            "#,
+            SELECT_ADMIN_USER_VIEWS
+        ))
+        .bind(&params.invite_code)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    /// Gets every user the user brought in, directly or through the users they invited
+    pub async fn get_invite_tree(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<InviteTreeNode>, TalliiError> {
+        let nodes = sqlx::query_as::<_, InviteTreeNode>(
+            r#"
+                with recursive tree as (
+                    select
+                        user_id,
+                        username,
+                        invited_by,
+                        invite_code,
+                        0 as depth,
+                        created_at
+                    from
+                        users
+                    where
+                        user_id = $1
+                    union all
+                    select
+                        u.user_id,
+                        u.username,
+                        u.invited_by,
+                        u.invite_code,
+                        t.depth + 1,
+                        u.created_at
+                    from
+                        users u
+                    inner join
+                        tree t
+                    on
+                        u.invited_by = t.user_id
+                )
+                select
+                    *
+                from
+                    tree
+                order by
+                    depth,
+                    created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(nodes)
+    }
+}
+
+pub struct AuditLogTable;
+
+impl AuditLogTable {
+    /// Records an action the actor took, optionally against another user
+    pub async fn record(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        actor_user_id: &i32,
+        action: AuditAction,
+        target_user_id: Option<i32>,
+        details: Option<String>,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                insert into
+                    audit_log (actor_user_id, action, target_user_id, details)
+                values
+                    ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(actor_user_id)
+        .bind(action.as_str())
+        .bind(target_user_id)
+        .bind(details)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets a page of the audit log, newest first
+    pub async fn get_entries(
+        pool: &PgPool,
+        params: &AuditLogQuery,
+    ) -> Result<Vec<AuditLogEntry>, TalliiError> {
+        let limit = params
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .max(1)
+            .min(MAX_PAGE_SIZE);
+
+        let entries = sqlx::query_as::<_, AuditLogEntry>(
+            r#"
+                select
+                    audit_id,
+                    actor_user_id,
+                    action,
+                    target_user_id,
+                    details,
+                    created_at
+                from
+                    audit_log
+                where
+                    ($1::integer is null or audit_id < $1)
+                order by
+                    audit_id desc
+                limit
+                    $2
+            "#,
+        )
+        .bind(params.before)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+}
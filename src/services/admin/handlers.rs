@@ -0,0 +1,352 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+use crate::errors::TalliiError;
+use crate::services::auth::{AdminUser, ModeratorUser};
+use crate::services::currency::db::CurrencyTable;
+use crate::services::currency::models::TransactionReason;
+use crate::services::events::comments::db::EventCommentsTable;
+use crate::services::users::db::UsersTable;
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::{AdminUsersTable, AuditLogTable};
+use super::models::{
+    AdjustCurrencyRequest, AdminUserQuery, AuditAction, AuditLogQuery, Role, SignupsQuery,
+    SuspendUserRequest, UpdateRoleRequest,
+};
+
+/// Sets the global role of a user
+pub async fn grant_role(
+    pool: web::Data<PgPool>,
+    admin: AdminUser,
+    user_id: web::Path<i32>,
+    request: web::Json<UpdateRoleRequest>,
+) -> TalliiResponse {
+    let role = Role::parse(&request.role).ok_or_else(|| {
+        TalliiError::BAD_REQUEST.message(String::from("The provided role does not exist."))
+    })?;
+
+    set_role(&pool, &admin, &user_id, role, AuditAction::GrantRole).await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("GRANTED_ROLE"),
+        message: String::from("The provided role was granted to the user."),
+    }))
+}
+
+/// Revokes the global role of a user so they are a regular user again
+pub async fn revoke_role(
+    pool: web::Data<PgPool>,
+    admin: AdminUser,
+    user_id: web::Path<i32>,
+) -> TalliiResponse {
+    set_role(&pool, &admin, &user_id, Role::User, AuditAction::RevokeRole).await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("REVOKED_ROLE"),
+        message: String::from("The role of the user was revoked."),
+    }))
+}
+
+/// Sets the role of the user and records it in the audit log
+async fn set_role(
+    pool: &PgPool,
+    admin: &AdminUser,
+    user_id: &i32,
+    role: Role,
+    action: AuditAction,
+) -> Result<(), TalliiError> {
+    // admins cannot lock themselves out
+    if *user_id == admin.user_id {
+        return Err(TalliiError::BAD_REQUEST.message(String::from("Cannot change your own role.")));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    if !UsersTable::update_role(&mut tx, user_id, role.as_str()).await? {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    AuditLogTable::record(
+        &mut tx,
+        &admin.user_id,
+        action,
+        Some(*user_id),
+        Some(format!("role={}", role.as_str())),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Gets a page of the audit log
+pub async fn get_audit_log(
+    pool: web::Data<PgPool>,
+    _admin: AdminUser,
+    params: web::Query<AuditLogQuery>,
+) -> TalliiResponse {
+    let entries = AuditLogTable::get_entries(&pool, &params).await?;
+
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+/// Searches every user by email or username
+pub async fn search_users(
+    pool: web::Data<PgPool>,
+    _moderator: ModeratorUser,
+    params: web::Query<AdminUserQuery>,
+) -> TalliiResponse {
+    let users = AdminUsersTable::search(&pool, &params).await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+/// Gets the most recent signups, optionally filtered by the invite code they used
+pub async fn get_signups(
+    pool: web::Data<PgPool>,
+    _moderator: ModeratorUser,
+    params: web::Query<SignupsQuery>,
+) -> TalliiResponse {
+    let users = AdminUsersTable::get_recent_signups(&pool, &params).await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+/// Gets the invite tree that starts at the user
+pub async fn get_invite_tree(
+    pool: web::Data<PgPool>,
+    _moderator: ModeratorUser,
+    user_id: web::Path<i32>,
+) -> TalliiResponse {
+    let nodes = AdminUsersTable::get_invite_tree(&pool, &user_id).await?;
+
+    // the user itself is always the root of its tree
+    if nodes.is_empty() {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    Ok(HttpResponse::Ok().json(nodes))
+}
+
+/// Suspends a user, moderators can only suspend regular users
+pub async fn suspend_user(
+    pool: web::Data<PgPool>,
+    moderator: ModeratorUser,
+    user_id: web::Path<i32>,
+    request: web::Json<SuspendUserRequest>,
+) -> TalliiResponse {
+    if *user_id == moderator.user_id {
+        return Err(TalliiError::BAD_REQUEST.message(String::from("Cannot suspend yourself.")));
+    }
+
+    if request.reason.trim().is_empty() {
+        return Err(TalliiError::BAD_REQUEST
+            .message(String::from("A reason is required to suspend a user.")));
+    }
+
+    let target = AdminUsersTable::get_by_id(&pool, &user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if target.role != Role::User.as_str() && moderator.role != Role::Admin {
+        return Err(TalliiError::FORBIDDEN.message(String::from(
+            "Only admins can suspend moderators and admins.",
+        )));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    if !UsersTable::suspend(&mut tx, &user_id, request.reason.trim(), request.until).await? {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    AuditLogTable::record(
+        &mut tx,
+        &moderator.user_id,
+        AuditAction::SuspendUser,
+        Some(*user_id),
+        Some(match request.until {
+            Some(until) => format!("until={};reason={}", until, request.reason.trim()),
+            None => format!("reason={}", request.reason.trim()),
+        }),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("SUSPENDED_USER"),
+        message: String::from("The provided user was suspended."),
+    }))
+}
+
+/// Lifts the suspension of a user
+pub async fn unsuspend_user(
+    pool: web::Data<PgPool>,
+    moderator: ModeratorUser,
+    user_id: web::Path<i32>,
+) -> TalliiResponse {
+    let target = AdminUsersTable::get_by_id(&pool, &user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if target.role != Role::User.as_str() && moderator.role != Role::Admin {
+        return Err(TalliiError::FORBIDDEN.message(String::from(
+            "Only admins can unsuspend moderators and admins.",
+        )));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    if !UsersTable::unsuspend(&mut tx, &user_id).await? {
+        return Err(
+            TalliiError::NOT_FOUND.message(String::from("The provided user is not suspended."))
+        );
+    }
+
+    AuditLogTable::record(
+        &mut tx,
+        &moderator.user_id,
+        AuditAction::UnsuspendUser,
+        Some(*user_id),
+        None,
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("UNSUSPENDED_USER"),
+        message: String::from("The suspension of the provided user was lifted."),
+    }))
+}
+
+/// Marks the email of a user as verified without them following the verification email
+pub async fn verify_user_email(
+    pool: web::Data<PgPool>,
+    moderator: ModeratorUser,
+    user_id: web::Path<i32>,
+) -> TalliiResponse {
+    AdminUsersTable::get_by_id(&pool, &user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    UsersTable::set_verified(&mut tx, &user_id).await?;
+
+    AuditLogTable::record(
+        &mut tx,
+        &moderator.user_id,
+        AuditAction::VerifyEmail,
+        Some(*user_id),
+        None,
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("VERIFIED_EMAIL"),
+        message: String::from("The email of the provided user was verified."),
+    }))
+}
+
+/// Adds or removes currency from a user through the ledger
+pub async fn adjust_currency(
+    pool: web::Data<PgPool>,
+    admin: AdminUser,
+    user_id: web::Path<i32>,
+    request: web::Json<AdjustCurrencyRequest>,
+) -> TalliiResponse {
+    if request.amount == 0 {
+        return Err(TalliiError::BAD_REQUEST.message(String::from("The amount cannot be zero.")));
+    }
+
+    if request.note.trim().is_empty() {
+        return Err(TalliiError::BAD_REQUEST
+            .message(String::from("A note is required to adjust currency.")));
+    }
+
+    AdminUsersTable::get_by_id(&pool, &user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    CurrencyTable::post(
+        &mut tx,
+        &user_id,
+        request.amount,
+        TransactionReason::AdminAdjustment,
+        None,
+    )
+    .await?;
+
+    AuditLogTable::record(
+        &mut tx,
+        &admin.user_id,
+        AuditAction::AdjustCurrency,
+        Some(*user_id),
+        Some(format!(
+            "amount={};note={}",
+            request.amount,
+            request.note.trim()
+        )),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("ADJUSTED_CURRENCY"),
+        message: String::from("The currency of the provided user was adjusted."),
+    }))
+}
+
+/// Deletes a comment on any event
+pub async fn delete_comment(
+    pool: web::Data<PgPool>,
+    moderator: ModeratorUser,
+    comment_id: web::Path<i32>,
+) -> TalliiResponse {
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let comment = EventCommentsTable::delete_by_id(&mut tx, &comment_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // keep the deleted comment in the log so the decision can be reviewed
+    AuditLogTable::record(
+        &mut tx,
+        &moderator.user_id,
+        AuditAction::DeleteComment,
+        Some(comment.user_id),
+        Some(format!(
+            "event_id={};comment_id={};comment={}",
+            comment.event_id, comment.comment_id, comment.comment
+        )),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("DELETED_COMMENT"),
+        message: String::from("The provided comment was deleted."),
+    }))
+}
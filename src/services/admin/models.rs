@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+/// Default amount of audit log entries in a page
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Max amount of audit log entries in a page
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+/// Max amount of users returned by a search or the recent signups
+pub const MAX_USERS: i64 = 50;
+
+/// The global roles a user can have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    /// The value stored in the role column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Parses the value stored in the role column
+    pub fn parse(role: &str) -> Option<Role> {
+        match role {
+            "user" => Some(Role::User),
+            "moderator" => Some(Role::Moderator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// The actions that are written to the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    GrantRole,
+    RevokeRole,
+    CreateInviteCodes,
+    SuspendUser,
+    UnsuspendUser,
+    VerifyEmail,
+    AdjustCurrency,
+    DeleteComment,
+    ApproveWaitlist,
+}
+
+impl AuditAction {
+    /// The value stored in the action column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::GrantRole => "grant_role",
+            AuditAction::RevokeRole => "revoke_role",
+            AuditAction::CreateInviteCodes => "create_invite_codes",
+            AuditAction::SuspendUser => "suspend_user",
+            AuditAction::UnsuspendUser => "unsuspend_user",
+            AuditAction::VerifyEmail => "verify_email",
+            AuditAction::AdjustCurrency => "adjust_currency",
+            AuditAction::DeleteComment => "delete_comment",
+            AuditAction::ApproveWaitlist => "approve_waitlist",
+        }
+    }
+}
+
+/// Database representation of an Audit Log Entry
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct AuditLogEntry {
+    pub audit_id: i32,
+    pub actor_user_id: i32,
+    pub action: String,
+    pub target_user_id: Option<i32>,
+    pub details: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Query params for paging through the audit log
+#[derive(Deserialize, Debug)]
+pub struct AuditLogQuery {
+    pub before: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+/// Representation of the update role request
+#[derive(Deserialize, Debug)]
+pub struct UpdateRoleRequest {
+    pub role: String,
+}
+
+/// A user as seen by moderators, including the data hidden from other users
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct AdminUserView {
+    pub user_id: i32,
+    pub username: String,
+    pub email: String,
+    pub emoji: String,
+    pub bg_color: String,
+    pub bio: Option<String>,
+    pub verified: Option<bool>,
+    pub role: String,
+    pub currency: i32,
+    pub invite_code: String,
+    pub invited_by: Option<i32>,
+    pub suspended_at: Option<chrono::NaiveDateTime>,
+    pub suspended_until: Option<chrono::NaiveDateTime>,
+    pub suspension_reason: Option<String>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Query params for searching users by email or username
+#[derive(Deserialize, Debug)]
+pub struct AdminUserQuery {
+    pub q: String,
+}
+
+/// Query params for listing recent signups
+#[derive(Deserialize, Debug)]
+pub struct SignupsQuery {
+    pub invite_code: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Representation of the suspend user request
+#[derive(Deserialize, Debug)]
+pub struct SuspendUserRequest {
+    pub reason: String,
+    pub until: Option<chrono::NaiveDateTime>,
+}
+
+/// Representation of the adjust currency request
+#[derive(Deserialize, Debug)]
+pub struct AdjustCurrencyRequest {
+    pub amount: i32,
+    pub note: String,
+}
+
+/// A user in the invite tree, the depth is relative to the user the tree starts at
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct InviteTreeNode {
+    pub user_id: i32,
+    pub username: String,
+    pub invited_by: Option<i32>,
+    pub invite_code: String,
+    pub depth: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,70 @@
+use actix_web::{web, Resource, Scope};
+
+use crate::services::waitlist::routes::{admin_waitlist_approvals_routes, admin_waitlist_routes};
+
+use super::handlers;
+
+/// Scope of every moderator and admin route
+pub fn admin_scope() -> Scope {
+    web::scope("/admin")
+        .service(admin_users_routes())
+        .service(admin_users_role_routes())
+        .service(admin_users_suspension_routes())
+        .service(admin_users_verification_routes())
+        .service(admin_users_currency_routes())
+        .service(admin_users_invite_tree_routes())
+        .service(admin_comments_entity_routes())
+        .service(admin_signups_routes())
+        .service(admin_waitlist_routes())
+        .service(admin_waitlist_approvals_routes())
+        .service(admin_audit_log_routes())
+}
+
+/// Resource routes for searching users
+pub fn admin_users_routes() -> Resource {
+    web::resource("/users").route(web::get().to(handlers::search_users))
+}
+
+/// Resource routes for the global role of a user
+pub fn admin_users_role_routes() -> Resource {
+    web::resource("/users/{user_id}/role")
+        .route(web::put().to(handlers::grant_role))
+        .route(web::delete().to(handlers::revoke_role))
+}
+
+/// Resource routes for the suspension of a user
+pub fn admin_users_suspension_routes() -> Resource {
+    web::resource("/users/{user_id}/suspension")
+        .route(web::put().to(handlers::suspend_user))
+        .route(web::delete().to(handlers::unsuspend_user))
+}
+
+/// Resource routes for verifying the email of a user
+pub fn admin_users_verification_routes() -> Resource {
+    web::resource("/users/{user_id}/verification").route(web::put().to(handlers::verify_user_email))
+}
+
+/// Resource routes for adjusting the currency of a user
+pub fn admin_users_currency_routes() -> Resource {
+    web::resource("/users/{user_id}/currency").route(web::post().to(handlers::adjust_currency))
+}
+
+/// Resource routes for the users a user brought in
+pub fn admin_users_invite_tree_routes() -> Resource {
+    web::resource("/users/{user_id}/invite-tree").route(web::get().to(handlers::get_invite_tree))
+}
+
+/// Resource routes for a comment on any event
+pub fn admin_comments_entity_routes() -> Resource {
+    web::resource("/comments/{comment_id}").route(web::delete().to(handlers::delete_comment))
+}
+
+/// Resource routes for the recent signups
+pub fn admin_signups_routes() -> Resource {
+    web::resource("/signups").route(web::get().to(handlers::get_signups))
+}
+
+/// Resource routes for the audit log
+pub fn admin_audit_log_routes() -> Resource {
+    web::resource("/audit-log").route(web::get().to(handlers::get_audit_log))
+}
@@ -1,17 +1,27 @@
 use actix_web::dev::Payload;
 use actix_web::{web, FromRequest, HttpRequest};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
+use chrono::{Duration, Utc};
 use futures::future::{ready, BoxFuture};
 use sqlx::PgPool;
 
 use crate::crypto::Crypto;
 use crate::errors::TalliiError;
+use crate::services::admin::models::Role;
+use crate::services::sessions::db::SessionsTable;
+use crate::services::tokens::db::AccessTokensTable;
+use crate::services::tokens::models::ACCESS_TOKEN_PREFIX;
 use crate::services::users::db::UsersTable;
 
+/// Seconds between updates of the last used time of a session or an access token
+const SESSION_TOUCH_INTERVAL_SECONDS: i64 = 60;
+
 #[derive(Debug)]
 pub struct AuthenticatedUser {
     pub user_id: i32,
     pub username: String,
+    pub session_id: i32,
+    pub role: String,
 }
 
 impl FromRequest for AuthenticatedUser {
@@ -39,19 +49,38 @@ impl FromRequest for AuthenticatedUser {
                         .await
                         .map_err(|_err| TalliiError::UNAUTHORIZED.default())?;
 
-                    // check to make sure the provided username and user_id combo is valid
-                    UsersTable::get_by_username_and_id(
-                        &p.into_inner(),
-                        &token.claims.sub,
-                        &token.claims.username,
-                    )
-                    .await?
-                    .ok_or_else(|| TalliiError::UNAUTHORIZED.default())?;
+                    let pool = p.into_inner();
+
+                    // check to make sure the user still exists, the username is read from the
+                    // user so that a renamed user is not locked out by the claim in their token
+                    let user = UsersTable::get_by_id(&pool, &token.claims.sub)
+                        .await?
+                        .ok_or_else(|| TalliiError::UNAUTHORIZED.default())?;
+
+                    // suspended users cannot do anything until the suspension ends
+                    if user.is_suspended(Utc::now().naive_utc()) {
+                        return Err(TalliiError::ACCOUNT_SUSPENDED.default());
+                    }
+
+                    // check to make sure the session has not been logged out
+                    let session =
+                        SessionsTable::get_active(&pool, &token.claims.sid, &token.claims.sub)
+                            .await?
+                            .ok_or_else(|| TalliiError::UNAUTHORIZED.default())?;
+
+                    // only record the session as used once a minute so not every request writes
+                    if Utc::now().naive_utc() - session.last_used_at
+                        >= Duration::seconds(SESSION_TOUCH_INTERVAL_SECONDS)
+                    {
+                        SessionsTable::touch(&pool, &session.session_id).await?;
+                    }
 
                     // return the authenticated user
                     Ok(AuthenticatedUser {
-                        user_id: token.claims.sub,
-                        username: token.claims.username,
+                        user_id: user.user_id,
+                        username: user.username,
+                        session_id: token.claims.sid,
+                        role: user.role,
                     })
                 };
 
@@ -65,3 +94,216 @@ impl FromRequest for AuthenticatedUser {
         }
     }
 }
+
+/// An authenticated user that has verified their email
+#[derive(Debug)]
+pub struct VerifiedUser {
+    pub user_id: i32,
+    pub username: String,
+    pub session_id: i32,
+}
+
+impl FromRequest for VerifiedUser {
+    type Error = TalliiError;
+    type Future = BoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // authenticate the user first
+        let authenticated = AuthenticatedUser::from_request(req, payload);
+
+        // get the pool
+        let pool = web::Data::<PgPool>::from_request(req, payload).into_inner();
+
+        let future = async move {
+            let user = authenticated.await?;
+            let pool = pool.map_err(|_err| TalliiError::UNAUTHORIZED.default())?;
+
+            // check to make sure the user has verified their email
+            if !UsersTable::is_verified(&pool, &user.user_id).await? {
+                return Err(TalliiError::EMAIL_NOT_VERIFIED.default());
+            }
+
+            // return the verified user
+            Ok(VerifiedUser {
+                user_id: user.user_id,
+                username: user.username,
+                session_id: user.session_id,
+            })
+        };
+
+        Box::pin(future)
+    }
+}
+
+/// An authenticated user that has the global admin role
+#[derive(Debug)]
+pub struct AdminUser {
+    pub user_id: i32,
+    pub username: String,
+    pub session_id: i32,
+}
+
+impl FromRequest for AdminUser {
+    type Error = TalliiError;
+    type Future = BoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // authenticate the user first
+        let authenticated = AuthenticatedUser::from_request(req, payload);
+
+        let future = async move {
+            let user = authenticated.await?;
+
+            // check to make sure the user is an admin
+            if user.role != Role::Admin.as_str() {
+                return Err(TalliiError::FORBIDDEN.default());
+            }
+
+            // return the admin user
+            Ok(AdminUser {
+                user_id: user.user_id,
+                username: user.username,
+                session_id: user.session_id,
+            })
+        };
+
+        Box::pin(future)
+    }
+}
+
+/// An authenticated user that has the global moderator or admin role
+#[derive(Debug)]
+pub struct ModeratorUser {
+    pub user_id: i32,
+    pub username: String,
+    pub session_id: i32,
+    pub role: Role,
+}
+
+impl FromRequest for ModeratorUser {
+    type Error = TalliiError;
+    type Future = BoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // authenticate the user first
+        let authenticated = AuthenticatedUser::from_request(req, payload);
+
+        let future = async move {
+            let user = authenticated.await?;
+
+            // check to make sure the user is a moderator or an admin
+            match Role::parse(&user.role) {
+                Some(role @ Role::Moderator) | Some(role @ Role::Admin) => Ok(ModeratorUser {
+                    user_id: user.user_id,
+                    username: user.username,
+                    session_id: user.session_id,
+                    role,
+                }),
+                _ => Err(TalliiError::FORBIDDEN.default()),
+            }
+        };
+
+        Box::pin(future)
+    }
+}
+
+/// A user authenticated with either a session or an access token. An access token can only do
+/// what its scopes allow, so handlers that accept one check the scope they need.
+#[derive(Debug)]
+pub struct ApiUser {
+    pub user_id: i32,
+    pub username: String,
+    /// Scopes of the access token, none when the user authenticated with a session
+    pub scopes: Option<Vec<String>>,
+}
+
+impl ApiUser {
+    /// Fails with INSUFFICIENT_SCOPE when an access token without the scope was used, a session
+    /// has every scope
+    pub fn require_scope(&self, scope: &str) -> Result<(), TalliiError> {
+        match &self.scopes {
+            Some(scopes) if !scopes.iter().any(|granted| granted == scope) => {
+                Err(TalliiError::INSUFFICIENT_SCOPE
+                    .message(format!("The access token needs the {} scope.", scope)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromRequest for ApiUser {
+    type Error = TalliiError;
+    type Future = BoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // get the users bearer header from the bearer extractor
+        let bearer = BearerAuth::from_request(req, payload).into_inner();
+
+        match bearer {
+            Ok(b) if b.token().starts_with(ACCESS_TOKEN_PREFIX) => {
+                // get the pool
+                let pool = web::Data::<PgPool>::from_request(req, payload).into_inner();
+
+                // get the crypto service
+                let crypto = web::Data::<Crypto>::from_request(req, payload).into_inner();
+
+                let future = async move {
+                    let pool = pool.map_err(|_err| TalliiError::UNAUTHORIZED.default())?;
+                    let crypto = crypto.map_err(|_err| TalliiError::UNAUTHORIZED.default())?;
+
+                    // only the hash of the token is stored
+                    let access_token =
+                        AccessTokensTable::get_active(&pool, &crypto.hash_token(b.token()))
+                            .await?
+                            .ok_or_else(|| TalliiError::UNAUTHORIZED.default())?;
+
+                    let user = UsersTable::get_by_id(&pool, &access_token.user_id)
+                        .await?
+                        .ok_or_else(|| TalliiError::UNAUTHORIZED.default())?;
+
+                    // suspended users cannot do anything until the suspension ends
+                    if user.is_suspended(Utc::now().naive_utc()) {
+                        return Err(TalliiError::ACCOUNT_SUSPENDED.default());
+                    }
+
+                    // only record the token as used once a minute so not every request writes
+                    if access_token.last_used_at.map_or(true, |last_used_at| {
+                        Utc::now().naive_utc() - last_used_at
+                            >= Duration::seconds(SESSION_TOUCH_INTERVAL_SECONDS)
+                    }) {
+                        AccessTokensTable::touch(&pool, &access_token.token_id).await?;
+                    }
+
+                    // return the user limited to the scopes of the token
+                    Ok(ApiUser {
+                        user_id: user.user_id,
+                        username: user.username,
+                        scopes: Some(access_token.scopes),
+                    })
+                };
+
+                Box::pin(future)
+            }
+            _ => {
+                // anything else has to be a session
+                let authenticated = AuthenticatedUser::from_request(req, payload);
+
+                let future = async move {
+                    let user = authenticated.await?;
+
+                    Ok(ApiUser {
+                        user_id: user.user_id,
+                        username: user.username,
+                        scopes: None,
+                    })
+                };
+
+                Box::pin(future)
+            }
+        }
+    }
+}
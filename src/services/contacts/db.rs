@@ -0,0 +1,112 @@
+use sqlx::{PgPool, Transaction};
+
+use crate::errors::TalliiError;
+use crate::services::users::models::PublicUser;
+
+use super::models::ContactMatchUsage;
+
+pub struct ContactsTable;
+
+impl ContactsTable {
+    /// Gets the discoverable users with a verified email matching one of the hashes, leaving out
+    /// the user and users blocked by or blocking the user
+    pub async fn get_matching(
+        pool: &PgPool,
+        user_id: &i32,
+        hashes: &[String],
+    ) -> Result<Vec<PublicUser>, TalliiError> {
+        let users = sqlx::query_as::<_, PublicUser>(
+            r#"
+                select
+                    u.user_id,
+                    u.emoji,
+                    u.bg_color,
+                    u.username,
+                    u.bio
+                from
+                    users u
+                where
+                    u.contact_hash = any($2)
+                and
+                    u.discoverable = true
+                and
+                    u.verified = true
+                and
+                    u.deleted_at is null
+                and
+                    u.user_id <> $1
+                and not exists (
+                    select
+                        1
+                    from
+                        friends f
+                    where
+                        f.state = 'blocked'
+                    and (
+                        (f.user_id = $1 and f.friend_user_id = u.user_id)
+                        or (f.user_id = u.user_id and f.friend_user_id = $1)
+                    )
+                )
+                order by
+                    u.username
+            "#,
+        )
+        .bind(user_id)
+        .bind(hashes)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+}
+
+pub struct ContactMatchesTable;
+
+impl ContactMatchesTable {
+    /// Gets the matches the user made in the last day, locking the user so concurrent requests
+    /// can not go over the limits together
+    pub async fn get_usage_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<ContactMatchUsage, TalliiError> {
+        sqlx::query("select user_id from users where user_id = $1 for update")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let usage = sqlx::query_as::<_, ContactMatchUsage>(
+            r#"
+                select
+                    count(*) as requests,
+                    coalesce(sum(hashes), 0) as hashes,
+                    min(created_at) as oldest
+                from
+                    contact_matches
+                where
+                    user_id = $1
+                and
+                    created_at > current_timestamp - interval '1 day'
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Ok(usage)
+    }
+
+    /// Records how many hashes the user matched, the hashes themselves are not stored
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        hashes: i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query("insert into contact_matches (user_id, hashes) values ($1, $2)")
+            .bind(user_id)
+            .bind(hashes)
+            .execute(&mut *tx)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+
+use crate::crypto::Crypto;
+use crate::errors::TalliiError;
+use crate::services::auth::{AuthenticatedUser, VerifiedUser};
+use crate::services::TalliiResponse;
+
+use super::db::{ContactMatchesTable, ContactsTable};
+use super::models::{
+    ContactsSaltResponse, MatchContactsRequest, CONTACT_HASH_LENGTH, MAX_CONTACT_HASHES_PER_DAY,
+    MAX_CONTACT_HASHES_PER_REQUEST, MAX_CONTACT_MATCHES_PER_DAY,
+};
+
+/// Gets the salt to hash the emails in my address book with
+pub async fn get_contacts_salt(
+    crypto: web::Data<Crypto>,
+    _user: AuthenticatedUser,
+) -> TalliiResponse {
+    Ok(HttpResponse::Ok().json(ContactsSaltResponse {
+        salt: crypto.contacts_salt.to_string(),
+    }))
+}
+
+/// Finds the discoverable users matching the hashed emails of my address book
+pub async fn match_contacts(
+    pool: web::Data<PgPool>,
+    user: VerifiedUser,
+    request: web::Json<MatchContactsRequest>,
+) -> TalliiResponse {
+    let mut hashes: Vec<String> = request
+        .hashes
+        .iter()
+        .map(|hash| hash.trim().to_lowercase())
+        .collect();
+
+    if hashes.iter().any(|hash| {
+        hash.len() != CONTACT_HASH_LENGTH || !hash.chars().all(|c| c.is_ascii_hexdigit())
+    }) {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "The hashes must be hex encoded sha256 hashes.",
+        )));
+    }
+
+    hashes.sort();
+    hashes.dedup();
+
+    if hashes.len() > MAX_CONTACT_HASHES_PER_REQUEST {
+        return Err(TalliiError::BAD_REQUEST.message(format!(
+            "Cannot match more than {} contacts at once.",
+            MAX_CONTACT_HASHES_PER_REQUEST
+        )));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let usage = ContactMatchesTable::get_usage_for_update(&mut tx, &user.user_id).await?;
+
+    if usage.requests >= MAX_CONTACT_MATCHES_PER_DAY
+        || usage.hashes + hashes.len() as i64 > MAX_CONTACT_HASHES_PER_DAY
+    {
+        // the limits free up as the oldest match of the day expires
+        let retry_after = usage
+            .oldest
+            .map(|oldest| (oldest + Duration::days(1) - Utc::now().naive_utc()).num_seconds())
+            .unwrap_or(0);
+
+        return Err(TalliiError::TOO_MANY_REQUESTS
+            .message(String::from(
+                "Too many contacts were matched today. Please try again later.",
+            ))
+            .retry_after(retry_after));
+    }
+
+    ContactMatchesTable::create(&mut tx, &user.user_id, hashes.len() as i32).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    let users = ContactsTable::get_matching(&pool, &user.user_id, &hashes).await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// Max amount of hashes that can be matched in a single request
+pub const MAX_CONTACT_HASHES_PER_REQUEST: usize = 500;
+
+/// Max amount of hashes a user can match per day, so the endpoint can not be used to enumerate
+/// accounts
+pub const MAX_CONTACT_HASHES_PER_DAY: i64 = 2000;
+
+/// Max amount of match requests a user can make per day
+pub const MAX_CONTACT_MATCHES_PER_DAY: i64 = 10;
+
+/// Length of a hex encoded sha256 hash
+pub const CONTACT_HASH_LENGTH: usize = 64;
+
+/// Representation of a request to find the users in an address book
+#[derive(Deserialize, Debug)]
+pub struct MatchContactsRequest {
+    /// The salted sha256 hashes of the emails in the address book
+    pub hashes: Vec<String>,
+}
+
+/// The salt clients hash the emails in their address book with
+#[derive(Serialize, Debug)]
+pub struct ContactsSaltResponse {
+    pub salt: String,
+}
+
+/// The matches a user made in the last day
+#[derive(sqlx::FromRow, Debug)]
+pub struct ContactMatchUsage {
+    pub requests: i64,
+    pub hashes: i64,
+    pub oldest: Option<chrono::NaiveDateTime>,
+}
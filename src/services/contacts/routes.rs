@@ -0,0 +1,13 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for the salt the address book of the currently logged in user is hashed with
+pub fn me_contacts_salt_routes() -> Resource {
+    web::resource("/me/contacts/salt").route(web::get().to(handlers::get_contacts_salt))
+}
+
+/// Resource routes finding the users in the address book of the currently logged in user
+pub fn me_contacts_match_routes() -> Resource {
+    web::resource("/me/contacts/match").route(web::post().to(handlers::match_contacts))
+}
@@ -0,0 +1,164 @@
+use sqlx::{PgPool, Transaction};
+
+use crate::errors::TalliiError;
+
+use super::models::{
+    CurrencyBalance, CurrencyTransaction, CurrencyTransactionsQuery, TransactionReason,
+};
+
+/// Default amount of transactions returned in a single page
+const DEFAULT_PAGE_SIZE: i64 = 25;
+
+/// Maximum amount of transactions returned in a single page
+const MAX_PAGE_SIZE: i64 = 100;
+
+pub struct CurrencyTable;
+
+impl CurrencyTable {
+    /// Applies the amount to the balance of the user and records it in the ledger.
+    ///
+    /// The balance update and the ledger entry are written in the provided transaction so they
+    /// either both succeed or both fail. The balance row is locked by the update, so concurrent
+    /// transactions against the same user are serialized and can never overdraw the balance.
+    /// Only reasons that can overdraw, i.e. reversing a payout that was already spent, can take
+    /// the balance below zero.
+    pub async fn post(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        amount: i32,
+        reason: TransactionReason,
+        reference_id: Option<i32>,
+    ) -> Result<i32, TalliiError> {
+        let balance = sqlx::query_as::<_, CurrencyBalance>(
+            r#"
+                update
+                    users
+                set
+                    currency = currency + $2
+                where
+                    user_id = $1
+                and
+                    (currency + $2 >= 0 or $3)
+                returning
+                    currency
+            "#,
+        )
+        .bind(user_id)
+        .bind(amount)
+        .bind(reason.can_overdraw())
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| TalliiError::INSUFFICIENT_CURRENCY.default())?;
+
+        sqlx::query(
+            r#"
+                insert into
+                    currency_transactions (user_id, amount, reason, counter_account, reference_id)
+                values
+                    ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(user_id)
+        .bind(amount)
+        .bind(reason.as_str())
+        .bind(reason.counter_account())
+        .bind(reference_id)
+        .execute(tx)
+        .await?;
+
+        Ok(balance.currency)
+    }
+
+    /// Gets the current balance of the user
+    pub async fn get_balance(pool: &PgPool, user_id: &i32) -> Result<CurrencyBalance, TalliiError> {
+        let balance = sqlx::query_as::<_, CurrencyBalance>(
+            r#"
+                select
+                    currency
+                from
+                    users
+                where
+                    user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+        Ok(balance)
+    }
+
+    /// Gets every transaction of the user, oldest first
+    pub async fn get_all_transactions(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<CurrencyTransaction>, TalliiError> {
+        let transactions = sqlx::query_as::<_, CurrencyTransaction>(
+            r#"
+                select
+                    transaction_id,
+                    user_id,
+                    amount,
+                    reason,
+                    counter_account,
+                    reference_id,
+                    created_at
+                from
+                    currency_transactions
+                where
+                    user_id = $1
+                order by
+                    transaction_id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(transactions)
+    }
+
+    /// Gets a page of the transactions of the user, newest first
+    pub async fn get_transactions(
+        pool: &PgPool,
+        user_id: &i32,
+        params: &CurrencyTransactionsQuery,
+    ) -> Result<Vec<CurrencyTransaction>, TalliiError> {
+        let limit = params
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .max(1)
+            .min(MAX_PAGE_SIZE);
+
+        let transactions = sqlx::query_as::<_, CurrencyTransaction>(
+            r#"
+                select
+                    transaction_id,
+                    user_id,
+                    amount,
+                    reason,
+                    counter_account,
+                    reference_id,
+                    created_at
+                from
+                    currency_transactions
+                where
+                    user_id = $1
+                and
+                    ($2::integer is null or transaction_id < $2)
+                order by
+                    transaction_id desc
+                limit
+                    $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(params.before)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(transactions)
+    }
+}
@@ -0,0 +1,26 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+use crate::services::auth::AuthenticatedUser;
+use crate::services::TalliiResponse;
+
+use super::db::CurrencyTable;
+use super::models::CurrencyTransactionsQuery;
+
+/// Gets the currency balance of me
+pub async fn get_me_currency(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    let balance = CurrencyTable::get_balance(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(balance))
+}
+
+/// Gets a page of the currency transactions of me
+pub async fn get_me_currency_transactions(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    params: web::Query<CurrencyTransactionsQuery>,
+) -> TalliiResponse {
+    let transactions = CurrencyTable::get_transactions(&pool, &user.user_id, &params).await?;
+
+    Ok(HttpResponse::Ok().json(transactions))
+}
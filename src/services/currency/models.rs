@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+/// Amount of currency every new user starts with
+pub const SIGNUP_GRANT: i32 = 1000;
+
+/// The reasons currency can move in or out of a users balance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionReason {
+    SignupGrant,
+    WagerEscrow,
+    WagerPayout,
+    Refund,
+    SettlementReversal,
+    PoolBet,
+    PoolPayout,
+    PoolRefund,
+    PoolHouseTake,
+    AdminAdjustment,
+}
+
+impl TransactionReason {
+    /// The value stored in the reason column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TransactionReason::SignupGrant => "signup_grant",
+            TransactionReason::WagerEscrow => "wager_escrow",
+            TransactionReason::WagerPayout => "wager_payout",
+            TransactionReason::Refund => "refund",
+            TransactionReason::SettlementReversal => "settlement_reversal",
+            TransactionReason::PoolBet => "pool_bet",
+            TransactionReason::PoolPayout => "pool_payout",
+            TransactionReason::PoolRefund => "pool_refund",
+            TransactionReason::PoolHouseTake => "pool_house_take",
+            TransactionReason::AdminAdjustment => "admin_adjustment",
+        }
+    }
+
+    /// Whether the transaction can take the balance below zero, a settlement has to be reversible
+    /// even when the payout was already spent so the user is left owing the difference
+    pub fn can_overdraw(self) -> bool {
+        self == TransactionReason::SettlementReversal
+    }
+
+    /// The system account on the other side of the transaction
+    pub fn counter_account(self) -> &'static str {
+        match self {
+            TransactionReason::SignupGrant | TransactionReason::AdminAdjustment => "treasury",
+            TransactionReason::WagerEscrow
+            | TransactionReason::WagerPayout
+            | TransactionReason::Refund
+            | TransactionReason::SettlementReversal
+            | TransactionReason::PoolBet
+            | TransactionReason::PoolPayout
+            | TransactionReason::PoolRefund
+            | TransactionReason::PoolHouseTake => "escrow",
+        }
+    }
+}
+
+/// Database representation of a Currency Transaction
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct CurrencyTransaction {
+    pub transaction_id: i32,
+    pub user_id: i32,
+    pub amount: i32,
+    pub reason: String,
+    pub counter_account: String,
+    pub reference_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Representation of the balance of a user
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct CurrencyBalance {
+    pub currency: i32,
+}
+
+/// Query params for paging through currency transactions
+#[derive(Deserialize, Debug)]
+pub struct CurrencyTransactionsQuery {
+    pub before: Option<i32>,
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,14 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for the currency balance of the currently logged in user
+pub fn me_currency_routes() -> Resource {
+    web::resource("/me/currency").route(web::get().to(handlers::get_me_currency))
+}
+
+/// Resource routes for the currency transactions of the currently logged in user
+pub fn me_currency_transactions_routes() -> Resource {
+    web::resource("/me/currency/transactions")
+        .route(web::get().to(handlers::get_me_currency_transactions))
+}
@@ -1,9 +1,10 @@
-use sqlx::PgPool;
+use sqlx::{PgPool, Transaction};
 
 use crate::errors::TalliiError;
 
-use super::models::{CreateEventCommentRequest, EventCommentResponse, EventCommentRow};
-use crate::services::auth::AuthenticatedUser;
+use super::models::{
+    CreateEventCommentRequest, EventComment, EventCommentResponse, EventCommentRow,
+};
 use crate::services::users::models::PublicUser;
 
 pub struct EventCommentsTable;
@@ -13,6 +14,7 @@ impl EventCommentsTable {
     pub async fn get_comments_by_event_id(
         pool: &PgPool,
         event_id: &i32,
+        viewer_user_id: &i32,
     ) -> Result<Vec<EventCommentResponse>, TalliiError> {
         let comments = sqlx::query_as::<_, EventCommentRow>(
             r#"
@@ -34,28 +36,69 @@ impl EventCommentsTable {
                     ec.user_id = u.user_id
                 where
                     ec.event_id = $1
+                and not exists (
+                    select
+                        1
+                    from
+                        friends f
+                    where
+                        f.user_id = $2
+                    and
+                        f.friend_user_id = ec.user_id
+                    and
+                        f.state = 'blocked'
+                )
             "#,
         )
         .bind(event_id)
+        .bind(viewer_user_id)
         .fetch_all(pool)
         .await?
-            .into_iter()
-            .map(| row | {
-                return EventCommentResponse {
-                    comment_id: row.comment_id,
-                    event_id: row.event_id,
-                    user: PublicUser {
-                        user_id: row.user_id,
-                        username: row.username,
-                        emoji: row.emoji,
-                        bg_color: row.bg_color,
-                        bio: row.bio
-                    },
-                    comment: row.comment,
-                    created_at: row.created_at,
-                }
-            })
-            .collect();
+        .into_iter()
+        .map(|row| {
+            return EventCommentResponse {
+                comment_id: row.comment_id,
+                event_id: row.event_id,
+                user: PublicUser {
+                    user_id: row.user_id,
+                    username: row.username,
+                    emoji: row.emoji,
+                    bg_color: row.bg_color,
+                    bio: row.bio,
+                },
+                comment: row.comment,
+                created_at: row.created_at,
+            };
+        })
+        .collect();
+
+        Ok(comments)
+    }
+
+    /// Gets all comments the user made on any event
+    pub async fn get_comments_by_user_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<EventComment>, TalliiError> {
+        let comments = sqlx::query_as::<_, EventComment>(
+            r#"
+                select
+                    comment_id,
+                    event_id,
+                    user_id,
+                    comment,
+                    created_at
+                from
+                    events_comments
+                where
+                    user_id = $1
+                order by
+                    created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
 
         Ok(comments)
     }
@@ -63,7 +106,7 @@ impl EventCommentsTable {
     /// Creates a single event comment in the database
     pub async fn create_one(
         pool: &PgPool,
-        user: &AuthenticatedUser,
+        user_id: &i32,
         event_id: &i32,
         request: &CreateEventCommentRequest,
     ) -> Result<(), TalliiError> {
@@ -76,7 +119,7 @@ impl EventCommentsTable {
             "#,
         )
         .bind(event_id)
-        .bind(user.user_id)
+        .bind(user_id)
         .bind(&request.comment)
         .execute(pool)
         .await?;
@@ -85,11 +128,7 @@ impl EventCommentsTable {
     }
 
     /// Deletes a single comment
-    pub async fn delete(
-        pool: &PgPool,
-        user: &AuthenticatedUser,
-        comment_id: &i32,
-    ) -> Result<(), TalliiError> {
+    pub async fn delete(pool: &PgPool, user_id: &i32, comment_id: &i32) -> Result<(), TalliiError> {
         sqlx::query(
             r#"
                 delete from
@@ -101,10 +140,36 @@ impl EventCommentsTable {
             "#,
         )
         .bind(comment_id)
-        .bind(user.user_id)
+        .bind(user_id)
         .execute(pool)
         .await?;
 
         Ok(())
     }
+
+    /// Deletes a single comment regardless of who made it and returns it, used by moderators
+    pub async fn delete_by_id(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        comment_id: &i32,
+    ) -> Result<Option<EventComment>, TalliiError> {
+        let comment = sqlx::query_as::<_, EventComment>(
+            r#"
+                delete from
+                    events_comments
+                where
+                    comment_id = $1
+                returning
+                    comment_id,
+                    event_id,
+                    user_id,
+                    comment,
+                    created_at
+            "#,
+        )
+        .bind(comment_id)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(comment)
+    }
 }
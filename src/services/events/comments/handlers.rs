@@ -2,7 +2,8 @@ use actix_web::{web, HttpResponse};
 
 use sqlx::PgPool;
 
-use crate::services::auth::AuthenticatedUser;
+use crate::services::auth::ApiUser;
+use crate::services::tokens::models::{COMMENTS_WRITE, EVENTS_READ};
 use crate::services::{SuccessResponse, TalliiResponse};
 
 use super::db::EventCommentsTable;
@@ -11,11 +12,14 @@ use super::models::CreateEventCommentRequest;
 /// Gets all comments in a single event
 pub async fn get_comments(
     pool: web::Data<PgPool>,
-    _user: AuthenticatedUser,
+    user: ApiUser,
     event_id: web::Path<i32>,
 ) -> TalliiResponse {
-    // get the members of the event
-    let comments = EventCommentsTable::get_comments_by_event_id(&pool, &event_id).await?;
+    user.require_scope(EVENTS_READ)?;
+
+    // get the comments of the event, without the ones from users the user blocked
+    let comments =
+        EventCommentsTable::get_comments_by_event_id(&pool, &event_id, &user.user_id).await?;
 
     // return the members of a single event
     Ok(HttpResponse::Ok().json(comments))
@@ -24,12 +28,14 @@ pub async fn get_comments(
 /// Creates a single comment
 pub async fn create_comment(
     pool: web::Data<PgPool>,
-    user: AuthenticatedUser,
+    user: ApiUser,
     event_id: web::Path<i32>,
     request: web::Json<CreateEventCommentRequest>,
 ) -> TalliiResponse {
+    user.require_scope(COMMENTS_WRITE)?;
+
     // create the comment in the database
-    EventCommentsTable::create_one(&pool, &user, &event_id, &request).await?;
+    EventCommentsTable::create_one(&pool, &user.user_id, &event_id, &request).await?;
 
     // response with success
     Ok(HttpResponse::Ok().json(SuccessResponse {
@@ -41,13 +47,15 @@ pub async fn create_comment(
 /// deletes an event comment
 pub async fn delete_comment(
     pool: web::Data<PgPool>,
-    user: AuthenticatedUser,
+    user: ApiUser,
     path_params: web::Path<(i32, i32)>,
 ) -> TalliiResponse {
+    user.require_scope(COMMENTS_WRITE)?;
+
     let (_event_id, comment_id) = path_params.into_inner();
 
     // delete the comment
-    EventCommentsTable::delete(&pool, &user, &comment_id).await?;
+    EventCommentsTable::delete(&pool, &user.user_id, &comment_id).await?;
 
     // response with success
     Ok(HttpResponse::Ok().json(SuccessResponse {
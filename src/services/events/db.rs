@@ -55,6 +55,7 @@ impl EventsTable {
                     u.bio,
                     u.emoji,
                     u.bg_color,
+                    events.finalized_at,
                     events.created_at
                 from
                     events
@@ -86,6 +87,7 @@ impl EventsTable {
                 username: event.username,
                 bio: event.bio,
             },
+            finalized_at: event.finalized_at,
             created_at: event.created_at,
         })
         .collect();
@@ -109,6 +111,7 @@ impl EventsTable {
                     u.bio,
                     u.emoji,
                     u.bg_color,
+                    events.finalized_at,
                     events.created_at
                 from
                     events
@@ -141,6 +144,7 @@ impl EventsTable {
                         username: event.username,
                         bio: event.bio,
                     },
+                    finalized_at: event.finalized_at,
                     created_at: event.created_at,
                 };
 
@@ -176,8 +180,35 @@ impl EventsTable {
         Ok(())
     }
 
-    /// Updates an event with the provided event_id
-    pub async fn delete_event_by_id(pool: &PgPool, event_id: &i32) -> Result<(), TalliiError> {
+    /// Marks the event with the provided event_id as finalised or not
+    pub async fn update_finalized_by_id(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+        finalized: bool,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    events
+                set
+                    finalized_at = case when $1 then current_timestamp else null end
+                where
+                    event_id = $2
+            "#,
+        )
+        .bind(finalized)
+        .bind(event_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deletes an event with the provided event_id
+    pub async fn delete_event_by_id(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<(), TalliiError> {
         sqlx::query(
             r#"
                 delete from
@@ -187,7 +218,7 @@ impl EventsTable {
             "#,
         )
         .bind(event_id)
-        .execute(pool)
+        .execute(tx)
         .await?;
 
         Ok(())
@@ -1,25 +1,39 @@
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 
-use crate::services::auth::AuthenticatedUser;
+use crate::services::auth::{ApiUser, AuthenticatedUser, VerifiedUser};
 
 use super::db::EventsTable;
 
 use super::models::{CreateEventRequest, CreatedEventResponse};
 
 use super::members::db::EventMembersTable;
+use super::pools::db::EventPoolsTable;
+use super::pools::settlement::PoolSettlement;
 
 use crate::errors::TalliiError;
 use crate::services::events::models::UpdateEventRequest;
+use crate::services::friends::db::FriendsTable;
+use crate::services::tokens::models::EVENTS_READ;
 use crate::services::users::db::UsersTable;
+use crate::services::wagers::settlement::WagerSettlement;
 use crate::services::{SuccessResponse, TalliiResponse};
 
 /// Creates a new Event
 pub async fn create_event(
     pool: web::Data<PgPool>,
     new_event: web::Json<CreateEventRequest>,
-    user: AuthenticatedUser,
+    user: VerifiedUser,
 ) -> TalliiResponse {
+    // members blocked by or blocking the user cannot be invited
+    for member_user_id in &new_event.members {
+        if FriendsTable::is_blocked(&pool, &user.user_id, member_user_id).await? {
+            return Err(
+                TalliiError::FORBIDDEN.message(String::from("You cannot invite this user."))
+            );
+        }
+    }
+
     // start the transaction
     let mut tx = pool.begin().await?;
 
@@ -35,6 +49,9 @@ pub async fn create_event(
     )
     .await?;
 
+    // create the betting pool of the event in the transaction
+    EventPoolsTable::create(&mut tx, &created_event.event_id).await?;
+
     // commit the transaction
     tx.commit().await?;
 
@@ -45,17 +62,18 @@ pub async fn create_event(
 }
 
 /// Gets all Events for me
-pub async fn get_me_events(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+pub async fn get_me_events(pool: web::Data<PgPool>, user: ApiUser) -> TalliiResponse {
+    user.require_scope(EVENTS_READ)?;
+
     let events = EventsTable::get_events_for_user_id(&pool, &user.user_id, "active").await?;
 
     Ok(HttpResponse::Ok().json(events))
 }
 
 /// Gets all Event Invitations for me
-pub async fn get_me_event_invitations(
-    pool: web::Data<PgPool>,
-    user: AuthenticatedUser,
-) -> TalliiResponse {
+pub async fn get_me_event_invitations(pool: web::Data<PgPool>, user: ApiUser) -> TalliiResponse {
+    user.require_scope(EVENTS_READ)?;
+
     let events = EventsTable::get_events_for_user_id(&pool, &user.user_id, "pending").await?;
 
     Ok(HttpResponse::Ok().json(events))
@@ -65,9 +83,15 @@ pub async fn get_me_event_invitations(
 pub async fn get_users_events(
     pool: web::Data<PgPool>,
     username: web::Path<String>,
-    _user: AuthenticatedUser,
+    api_user: ApiUser,
 ) -> TalliiResponse {
+    api_user.require_scope(EVENTS_READ)?;
+
     if let Some(user) = UsersTable::get_by_username(&pool, &username).await? {
+        if !FriendsTable::can_view(&pool, &api_user.user_id, &user.user_id).await? {
+            return Err(TalliiError::PRIVATE_ACCOUNT.default());
+        }
+
         let events = EventsTable::get_events_for_user_id(&pool, &user.user_id, "active").await?;
         Ok(HttpResponse::Ok().json(events))
     } else {
@@ -78,9 +102,11 @@ pub async fn get_users_events(
 /// Gets a single event for the user
 pub async fn get_event(
     pool: web::Data<PgPool>,
-    _user: AuthenticatedUser,
+    user: ApiUser,
     event_id: web::Path<i32>,
 ) -> TalliiResponse {
+    user.require_scope(EVENTS_READ)?;
+
     let event = EventsTable::get_event_by_id(&pool, &event_id).await?;
 
     Ok(HttpResponse::Ok().json(event))
@@ -116,7 +142,17 @@ pub async fn delete_event(
         EventMembersTable::get_member_by_user_id(&pool, &event_id, &user.user_id).await?
     {
         if member.role == String::from("admin") {
-            EventsTable::delete_event_by_id(&pool, &event_id).await?;
+            // start the transaction
+            let mut tx = pool.begin().await?;
+
+            // the stakes in escrow are given back before the wagers and bets are deleted
+            WagerSettlement::cancel_event(&mut tx, &event_id).await?;
+            PoolSettlement::refund_event(&mut tx, &event_id).await?;
+
+            EventsTable::delete_event_by_id(&mut tx, &event_id).await?;
+
+            // commit the transaction
+            tx.commit().await?;
 
             Ok(HttpResponse::Ok().json(SuccessResponse {
                 code: String::from("EVENT_DELETED"),
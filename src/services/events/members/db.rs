@@ -84,6 +84,37 @@ impl EventMembersTable {
         Ok(exists.exists)
     }
 
+    /// Checks if the user is an admin of the event
+    pub async fn is_admin(
+        pool: &PgPool,
+        event_id: &i32,
+        user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let exists = sqlx::query_as::<_, MemberExists>(
+            r#"
+                select
+                    exists (
+                        select
+                            1
+                        from
+                            events_members
+                        where
+                            event_id = $1
+                        and
+                            user_id = $2
+                        and
+                            role = 'admin'
+                    )
+            "#,
+        )
+        .bind(event_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(exists.exists)
+    }
+
     /// Gets a member by an event_id and user_id
     pub async fn get_member_by_user_id(
         pool: &PgPool,
@@ -145,6 +176,35 @@ impl EventMembersTable {
         Ok(members)
     }
 
+    /// Gets every membership of the user across all events
+    pub async fn get_by_user_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<EventMember>, TalliiError> {
+        let members = sqlx::query_as::<_, EventMember>(
+            r#"
+                select
+                    member_id,
+                    event_id,
+                    user_id,
+                    state,
+                    role,
+                    created_at
+                from
+                    events_members
+                where
+                    user_id = $1
+                order by
+                    created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(members)
+    }
+
     /// Updates a single member
     pub async fn update(
         pool: &PgPool,
@@ -3,6 +3,7 @@ use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 
 use crate::services::auth::AuthenticatedUser;
+use crate::services::friends::db::FriendsTable;
 use crate::services::{SuccessResponse, TalliiResponse};
 
 use super::db::EventMembersTable;
@@ -33,6 +34,12 @@ pub async fn invite_member(
     if let Some(_member) =
         EventMembersTable::get_member_by_user_id(&pool, &event_id, &user.user_id).await?
     {
+        if FriendsTable::is_blocked(&pool, &user.user_id, &invite_member_request.user_id).await? {
+            return Err(
+                TalliiError::FORBIDDEN.message(String::from("You cannot invite this user."))
+            );
+        }
+
         EventMembersTable::create_one(&pool, &event_id, &invite_member_request).await?;
 
         Ok(HttpResponse::Ok().json(SuccessResponse {
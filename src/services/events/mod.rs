@@ -1,5 +1,6 @@
 pub mod comments;
 pub mod members;
+pub mod pools;
 pub mod teams;
 
 pub mod db;
@@ -9,6 +9,7 @@ pub struct Event {
     pub name: String,
     pub description: Option<String>,
     pub creator_user_id: i32,
+    pub finalized_at: Option<chrono::NaiveDateTime>,
     pub created_at: chrono::NaiveDateTime,
 }
 
@@ -23,6 +24,7 @@ pub struct EventRow {
     pub bg_color: String,
     pub username: String,
     pub bio: Option<String>,
+    pub finalized_at: Option<chrono::NaiveDateTime>,
     pub created_at: chrono::NaiveDateTime,
 }
 
@@ -33,6 +35,7 @@ pub struct EventResponse {
     pub name: String,
     pub description: Option<String>,
     pub creator: PublicUser,
+    pub finalized_at: Option<chrono::NaiveDateTime>,
     pub created_at: chrono::NaiveDateTime,
 }
 
@@ -0,0 +1,529 @@
+use sqlx::{PgPool, Transaction};
+
+use crate::errors::TalliiError;
+
+use super::models::{
+    EventPool, EventPoolBet, EventPoolBetExists, EventPoolHouse, EventPoolTeamStake,
+    PlaceBetRequest, UpdateEventPoolRequest,
+};
+
+pub struct EventPoolsTable;
+
+impl EventPoolsTable {
+    /// Creates the pool of an event
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                insert into
+                    events_pools (event_id)
+                values
+                    ($1)
+            "#,
+        )
+        .bind(event_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the pool of an event
+    pub async fn get(pool: &PgPool, event_id: &i32) -> Result<Option<EventPool>, TalliiError> {
+        let event_pool = sqlx::query_as::<_, EventPool>(
+            r#"
+                select
+                    event_id,
+                    house_cut,
+                    locks_at,
+                    locked_at,
+                    settled_at,
+                    house_take,
+                    created_at
+                from
+                    events_pools
+                where
+                    event_id = $1
+            "#,
+        )
+        .bind(event_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(event_pool)
+    }
+
+    /// Gets the pool of an event and locks it until the transaction finishes
+    pub async fn get_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<Option<EventPool>, TalliiError> {
+        let event_pool = sqlx::query_as::<_, EventPool>(
+            r#"
+                select
+                    event_id,
+                    house_cut,
+                    locks_at,
+                    locked_at,
+                    settled_at,
+                    house_take,
+                    created_at
+                from
+                    events_pools
+                where
+                    event_id = $1
+                for update
+            "#,
+        )
+        .bind(event_id)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(event_pool)
+    }
+
+    /// Gets the total active stake on each team of an event
+    pub async fn get_team_stakes(
+        pool: &PgPool,
+        event_id: &i32,
+    ) -> Result<Vec<EventPoolTeamStake>, TalliiError> {
+        let stakes = sqlx::query_as::<_, EventPoolTeamStake>(
+            r#"
+                select
+                    t.team_id,
+                    t.name,
+                    coalesce(sum(b.currency), 0)::bigint as stake,
+                    count(b.bet_id) as bets
+                from
+                    events_teams t
+                left join
+                    events_pools_bets b
+                on
+                    b.team_id = t.team_id
+                and
+                    b.state = 'active'
+                where
+                    t.event_id = $1
+                group by
+                    t.team_id,
+                    t.name
+                order by
+                    t.team_id
+            "#,
+        )
+        .bind(event_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(stakes)
+    }
+
+    /// Gets the user the house take of the pool of an event is credited to
+    pub async fn get_house(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<EventPoolHouse, TalliiError> {
+        let house = sqlx::query_as::<_, EventPoolHouse>(
+            r#"
+                select
+                    creator_user_id
+                from
+                    events
+                where
+                    event_id = $1
+            "#,
+        )
+        .bind(event_id)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(house)
+    }
+
+    /// Updates the configuration of the pool of an event
+    pub async fn update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+        request: &UpdateEventPoolRequest,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    events_pools
+                set
+                    house_cut = $1,
+                    locks_at = $2,
+                    locked_at = case when $3 then coalesce(locked_at, current_timestamp) else null end
+                where
+                    event_id = $4
+            "#,
+        )
+        .bind(&request.house_cut)
+        .bind(&request.locks_at)
+        .bind(&request.locked)
+        .bind(event_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Marks the pool of an event as settled
+    pub async fn settle(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+        house_take: i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    events_pools
+                set
+                    settled_at = current_timestamp,
+                    house_take = $1
+                where
+                    event_id = $2
+            "#,
+        )
+        .bind(house_take)
+        .bind(event_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Marks the pool of an event as not settled, so it can be settled again
+    pub async fn unsettle(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    events_pools
+                set
+                    settled_at = null,
+                    house_take = null
+                where
+                    event_id = $1
+            "#,
+        )
+        .bind(event_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub struct EventPoolBetsTable;
+
+impl EventPoolBetsTable {
+    /// Creates a bet in the pool of an event
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+        user_id: &i32,
+        request: &PlaceBetRequest,
+    ) -> Result<EventPoolBet, TalliiError> {
+        let bet = sqlx::query_as::<_, EventPoolBet>(
+            r#"
+                insert into
+                    events_pools_bets (event_id, user_id, team_id, currency)
+                values
+                    ($1, $2, $3, $4)
+                returning
+                    *
+            "#,
+        )
+        .bind(event_id)
+        .bind(user_id)
+        .bind(&request.team_id)
+        .bind(&request.currency)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(bet)
+    }
+
+    /// Gets all bets in the pool of an event
+    pub async fn get_by_event_id(
+        pool: &PgPool,
+        event_id: &i32,
+    ) -> Result<Vec<EventPoolBet>, TalliiError> {
+        let bets = sqlx::query_as::<_, EventPoolBet>(
+            r#"
+                select
+                    bet_id,
+                    event_id,
+                    user_id,
+                    team_id,
+                    currency,
+                    state,
+                    payout,
+                    created_at
+                from
+                    events_pools_bets
+                where
+                    event_id = $1
+                order by
+                    created_at desc
+            "#,
+        )
+        .bind(event_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(bets)
+    }
+
+    /// Gets all bets the user placed in any pool
+    pub async fn get_by_user_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<EventPoolBet>, TalliiError> {
+        let bets = sqlx::query_as::<_, EventPoolBet>(
+            r#"
+                select
+                    bet_id,
+                    event_id,
+                    user_id,
+                    team_id,
+                    currency,
+                    state,
+                    payout,
+                    created_at
+                from
+                    events_pools_bets
+                where
+                    user_id = $1
+                order by
+                    created_at desc
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(bets)
+    }
+
+    /// Checks if the user has any bets that are still active
+    pub async fn has_active_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let exists = sqlx::query_as::<_, EventPoolBetExists>(
+            r#"
+                select exists (
+                    select
+                        1
+                    from
+                        events_pools_bets
+                    where
+                        user_id = $1
+                    and
+                        state = 'active'
+                )
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(exists.exists)
+    }
+
+    /// Checks if the pool of an event has any bets that are still active
+    pub async fn has_active_for_event(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let exists = sqlx::query_as::<_, EventPoolBetExists>(
+            r#"
+                select exists (
+                    select
+                        1
+                    from
+                        events_pools_bets
+                    where
+                        event_id = $1
+                    and
+                        state = 'active'
+                )
+            "#,
+        )
+        .bind(event_id)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(exists.exists)
+    }
+
+    /// Gets a single bet and locks it until the transaction finishes
+    pub async fn get_by_id_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+        bet_id: &i32,
+    ) -> Result<Option<EventPoolBet>, TalliiError> {
+        let bet = sqlx::query_as::<_, EventPoolBet>(
+            r#"
+                select
+                    bet_id,
+                    event_id,
+                    user_id,
+                    team_id,
+                    currency,
+                    state,
+                    payout,
+                    created_at
+                from
+                    events_pools_bets
+                where
+                    event_id = $1
+                and
+                    bet_id = $2
+                for update
+            "#,
+        )
+        .bind(event_id)
+        .bind(bet_id)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(bet)
+    }
+
+    /// Gets the active bets in the pool of an event and locks them until the transaction finishes
+    pub async fn get_active_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<Vec<EventPoolBet>, TalliiError> {
+        let bets = sqlx::query_as::<_, EventPoolBet>(
+            r#"
+                select
+                    bet_id,
+                    event_id,
+                    user_id,
+                    team_id,
+                    currency,
+                    state,
+                    payout,
+                    created_at
+                from
+                    events_pools_bets
+                where
+                    event_id = $1
+                and
+                    state = 'active'
+                order by
+                    bet_id
+                for update
+            "#,
+        )
+        .bind(event_id)
+        .fetch_all(tx)
+        .await?;
+
+        Ok(bets)
+    }
+
+    /// Gets the settled bets in the pool of an event and locks them until the transaction
+    /// finishes
+    pub async fn get_settled_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<Vec<EventPoolBet>, TalliiError> {
+        let bets = sqlx::query_as::<_, EventPoolBet>(
+            r#"
+                select
+                    bet_id,
+                    event_id,
+                    user_id,
+                    team_id,
+                    currency,
+                    state,
+                    payout,
+                    created_at
+                from
+                    events_pools_bets
+                where
+                    event_id = $1
+                and
+                    state = 'settled'
+                order by
+                    bet_id
+                for update
+            "#,
+        )
+        .bind(event_id)
+        .fetch_all(tx)
+        .await?;
+
+        Ok(bets)
+    }
+
+    /// Gets the active bets on a team and locks them until the transaction finishes
+    pub async fn get_active_for_team_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        team_id: &i32,
+    ) -> Result<Vec<EventPoolBet>, TalliiError> {
+        let bets = sqlx::query_as::<_, EventPoolBet>(
+            r#"
+                select
+                    bet_id,
+                    event_id,
+                    user_id,
+                    team_id,
+                    currency,
+                    state,
+                    payout,
+                    created_at
+                from
+                    events_pools_bets
+                where
+                    team_id = $1
+                and
+                    state = 'active'
+                order by
+                    bet_id
+                for update
+            "#,
+        )
+        .bind(team_id)
+        .fetch_all(tx)
+        .await?;
+
+        Ok(bets)
+    }
+
+    /// Updates the state and payout of a single bet
+    pub async fn update_state(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        bet_id: &i32,
+        state: &str,
+        payout: Option<i32>,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    events_pools_bets
+                set
+                    state = $1,
+                    payout = $2
+                where
+                    bet_id = $3
+            "#,
+        )
+        .bind(state)
+        .bind(payout)
+        .bind(bet_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+}
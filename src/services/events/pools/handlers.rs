@@ -0,0 +1,259 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::errors::TalliiError;
+use crate::services::auth::{AuthenticatedUser, VerifiedUser};
+use crate::services::currency::db::CurrencyTable;
+use crate::services::currency::models::TransactionReason;
+use crate::services::events::members::db::EventMembersTable;
+use crate::services::events::teams::db::EventsTeamsTable;
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::{EventPoolBetsTable, EventPoolsTable};
+use super::models::{
+    EventPoolResponse, EventPoolTeamOdds, PlaceBetRequest, PlacedBetResponse,
+    UpdateEventPoolRequest,
+};
+
+/// Gets the pool of an event with the live implied odds of each team
+pub async fn get_pool(
+    pool: web::Data<PgPool>,
+    _user: AuthenticatedUser,
+    event_id: web::Path<i32>,
+) -> TalliiResponse {
+    let event_pool = EventPoolsTable::get(&pool, &event_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    let stakes = EventPoolsTable::get_team_stakes(&pool, &event_id).await?;
+
+    let pot: i64 = stakes.iter().map(|team| team.stake).sum();
+
+    // the amount that is paid out to the winners once the house takes its cut
+    let distributable = (pot - pot * i64::from(event_pool.house_cut) / 100) as f64;
+
+    let teams = stakes
+        .into_iter()
+        .map(|team| EventPoolTeamOdds {
+            odds: if team.stake > 0 {
+                Some(distributable / team.stake as f64)
+            } else {
+                None
+            },
+            probability: if pot > 0 {
+                team.stake as f64 / pot as f64
+            } else {
+                0.0
+            },
+            team_id: team.team_id,
+            name: team.name,
+            stake: team.stake,
+            bets: team.bets,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(EventPoolResponse {
+        event_id: event_pool.event_id,
+        house_cut: event_pool.house_cut,
+        locks_at: event_pool.locks_at,
+        locked: event_pool.is_locked(Utc::now().naive_utc()),
+        settled_at: event_pool.settled_at,
+        pot,
+        teams,
+    }))
+}
+
+/// Updates the configuration of the pool of an event
+pub async fn update_pool(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    event_id: web::Path<i32>,
+    request: web::Json<UpdateEventPoolRequest>,
+) -> TalliiResponse {
+    // only admins can configure the pool
+    match EventMembersTable::get_member_by_user_id(&pool, &event_id, &user.user_id).await? {
+        Some(member) if member.role == "admin" => (),
+        _ => return Err(TalliiError::FORBIDDEN.default()),
+    }
+
+    if request.house_cut < 0 || request.house_cut > 100 {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "The house cut must be between 0 and 100 percent.",
+        )));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    // lock the pool so no bet can be placed while it is configured
+    let event_pool = EventPoolsTable::get_for_update(&mut tx, &event_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if event_pool.settled_at.is_some() {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("The pool has already been settled."))
+        );
+    }
+
+    // the odds the bets were placed at depend on the house cut, so it is frozen by the first bet
+    if request.house_cut != event_pool.house_cut
+        && EventPoolBetsTable::has_active_for_event(&mut tx, &event_id).await?
+    {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "The house cut cannot be changed once bets have been placed.",
+        )));
+    }
+
+    EventPoolsTable::update(&mut tx, &event_id, &request).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("UPDATED_EVENT_POOL"),
+        message: String::from("The provided event pool was updated."),
+    }))
+}
+
+/// Gets all bets in the pool of an event
+pub async fn get_bets(
+    pool: web::Data<PgPool>,
+    _user: AuthenticatedUser,
+    event_id: web::Path<i32>,
+) -> TalliiResponse {
+    let bets = EventPoolBetsTable::get_by_event_id(&pool, &event_id).await?;
+
+    Ok(HttpResponse::Ok().json(bets))
+}
+
+/// Places a bet on a team in the pool of an event
+pub async fn place_bet(
+    pool: web::Data<PgPool>,
+    user: VerifiedUser,
+    event_id: web::Path<i32>,
+    request: web::Json<PlaceBetRequest>,
+) -> TalliiResponse {
+    if request.currency <= 0 {
+        return Err(TalliiError::BAD_REQUEST
+            .message(String::from("The bet currency must be greater than zero.")));
+    }
+
+    // only active members can bet in the pool
+    match EventMembersTable::get_member_by_user_id(&pool, &event_id, &user.user_id).await? {
+        Some(member) if member.state == "active" => (),
+        _ => return Err(TalliiError::FORBIDDEN.default()),
+    }
+
+    // make sure the team is a part of the event
+    match EventsTeamsTable::get_by_id(&pool, &request.team_id).await? {
+        Some(team) if team.event_id == *event_id => (),
+        _ => {
+            return Err(TalliiError::BAD_REQUEST.message(String::from(
+                "The provided team is not a part of this event.",
+            )))
+        }
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    // lock the pool so it cannot be locked or settled while the bet is placed
+    let event_pool = EventPoolsTable::get_for_update(&mut tx, &event_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if event_pool.is_locked(Utc::now().naive_utc()) {
+        return Err(TalliiError::BAD_REQUEST.message(String::from("The pool is locked.")));
+    }
+
+    // the pool is closed once a winner has been marked, even before it is settled
+    if !EventsTeamsTable::get_winners(&mut tx, &event_id)
+        .await?
+        .is_empty()
+    {
+        return Err(TalliiError::BAD_REQUEST.message(String::from("The pool is locked.")));
+    }
+
+    let bet = EventPoolBetsTable::create(&mut tx, &event_id, &user.user_id, &request).await?;
+
+    // hold the stake in escrow until the pool is settled
+    CurrencyTable::post(
+        &mut tx,
+        &user.user_id,
+        -bet.currency,
+        TransactionReason::PoolBet,
+        Some(bet.bet_id),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(PlacedBetResponse { bet_id: bet.bet_id }))
+}
+
+/// Cancels a bet in the pool of an event
+pub async fn cancel_bet(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    path_params: web::Path<(i32, i32)>,
+) -> TalliiResponse {
+    let (event_id, bet_id) = path_params.into_inner();
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    // lock the pool so it cannot be locked or settled while the bet is cancelled
+    let event_pool = EventPoolsTable::get_for_update(&mut tx, &event_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if event_pool.is_locked(Utc::now().naive_utc()) {
+        return Err(TalliiError::BAD_REQUEST.message(String::from("The pool is locked.")));
+    }
+
+    // the pool is closed once a winner has been marked, even before it is settled
+    if !EventsTeamsTable::get_winners(&mut tx, &event_id)
+        .await?
+        .is_empty()
+    {
+        return Err(TalliiError::BAD_REQUEST.message(String::from("The pool is locked.")));
+    }
+
+    let bet = EventPoolBetsTable::get_by_id_for_update(&mut tx, &event_id, &bet_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // only the user that placed the bet can cancel it
+    if bet.user_id != user.user_id {
+        return Err(TalliiError::FORBIDDEN.default());
+    }
+
+    if bet.state != "active" {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("Only active bets can be cancelled."))
+        );
+    }
+
+    // give the user their stake back
+    CurrencyTable::post(
+        &mut tx,
+        &user.user_id,
+        bet.currency,
+        TransactionReason::PoolRefund,
+        Some(bet.bet_id),
+    )
+    .await?;
+
+    EventPoolBetsTable::update_state(&mut tx, &bet_id, "cancelled", None).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("CANCELLED_EVENT_POOL_BET"),
+        message: String::from("The provided bet was cancelled."),
+    }))
+}
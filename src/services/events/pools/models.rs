@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+/// Database representation of an Event Pool
+#[derive(sqlx::FromRow, Deserialize, Serialize, Debug)]
+pub struct EventPool {
+    pub event_id: i32,
+    pub house_cut: i32,
+    pub locks_at: Option<chrono::NaiveDateTime>,
+    pub locked_at: Option<chrono::NaiveDateTime>,
+    pub settled_at: Option<chrono::NaiveDateTime>,
+    pub house_take: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl EventPool {
+    /// Checks if bets can no longer be placed or cancelled in the pool
+    pub fn is_locked(&self, now: chrono::NaiveDateTime) -> bool {
+        self.locked_at.is_some()
+            || self.settled_at.is_some()
+            || self.locks_at.map_or(false, |locks_at| locks_at <= now)
+    }
+}
+
+/// The user the house take of a pool is credited to, which is the creator of the event
+#[derive(sqlx::FromRow, Debug)]
+pub struct EventPoolHouse {
+    pub creator_user_id: i32,
+}
+
+/// Database representation of a bet in an Event Pool
+#[derive(sqlx::FromRow, Deserialize, Serialize, Debug)]
+pub struct EventPoolBet {
+    pub bet_id: i32,
+    pub event_id: i32,
+    pub user_id: i32,
+    pub team_id: i32,
+    pub currency: i32,
+    pub state: String,
+    pub payout: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Represents a bet existing
+#[derive(sqlx::FromRow, Debug)]
+pub struct EventPoolBetExists {
+    pub exists: bool,
+}
+
+/// The total stake on a single team of a pool
+#[derive(sqlx::FromRow, Debug)]
+pub struct EventPoolTeamStake {
+    pub team_id: i32,
+    pub name: String,
+    pub stake: i64,
+    pub bets: i64,
+}
+
+/// The live implied odds of a single team of a pool
+#[derive(Serialize, Debug)]
+pub struct EventPoolTeamOdds {
+    pub team_id: i32,
+    pub name: String,
+    pub stake: i64,
+    pub bets: i64,
+    /// Amount returned for every unit staked if the team wins, none when nobody backed the team
+    pub odds: Option<f64>,
+    /// Share of the pot staked on the team
+    pub probability: f64,
+}
+
+/// Event pool response payload
+#[derive(Serialize, Debug)]
+pub struct EventPoolResponse {
+    pub event_id: i32,
+    pub house_cut: i32,
+    pub locks_at: Option<chrono::NaiveDateTime>,
+    pub locked: bool,
+    pub settled_at: Option<chrono::NaiveDateTime>,
+    pub pot: i64,
+    pub teams: Vec<EventPoolTeamOdds>,
+}
+
+/// Request to configure an event pool
+#[derive(Deserialize, Debug)]
+pub struct UpdateEventPoolRequest {
+    pub house_cut: i32,
+    pub locks_at: Option<chrono::NaiveDateTime>,
+    pub locked: bool,
+}
+
+/// Request to place a bet in an event pool
+#[derive(Deserialize, Debug)]
+pub struct PlaceBetRequest {
+    pub team_id: i32,
+    pub currency: i32,
+}
+
+/// Response of a successfully placed bet
+#[derive(Serialize, Debug)]
+pub struct PlacedBetResponse {
+    pub bet_id: i32,
+}
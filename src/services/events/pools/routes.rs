@@ -0,0 +1,23 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for the pool of an event
+pub fn event_pool_routes() -> Resource {
+    web::resource("/events/{event_id}/pool")
+        .route(web::get().to(handlers::get_pool))
+        .route(web::put().to(handlers::update_pool))
+}
+
+/// Resource routes for the bets in the pool of an event
+pub fn event_pool_bets_routes() -> Resource {
+    web::resource("/events/{event_id}/pool/bets")
+        .route(web::get().to(handlers::get_bets))
+        .route(web::post().to(handlers::place_bet))
+}
+
+/// Resource routes for a specific bet in the pool of an event
+pub fn event_pool_bets_entity_routes() -> Resource {
+    web::resource("/events/{event_id}/pool/bets/{bet_id}")
+        .route(web::delete().to(handlers::cancel_bet))
+}
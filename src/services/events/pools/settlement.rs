@@ -0,0 +1,311 @@
+use sqlx::Transaction;
+
+use crate::errors::TalliiError;
+use crate::services::currency::db::CurrencyTable;
+use crate::services::currency::models::TransactionReason;
+use crate::services::events::teams::db::EventsTeamsTable;
+
+use super::db::{EventPoolBetsTable, EventPoolsTable};
+use super::models::EventPoolBet;
+
+/// How a settled pool is paid out
+#[derive(Debug, PartialEq, Eq)]
+pub struct PoolPayouts {
+    /// The amount paid out to each bet, in the same order as the bets
+    pub payouts: Vec<i32>,
+    /// The amount kept by the house, including whatever is left over from rounding
+    pub house_take: i32,
+    /// Whether every bet was refunded instead of paid out
+    pub refunded: bool,
+}
+
+impl PoolPayouts {
+    /// Splits the pot between the bets that backed a winning team in proportion to their stake.
+    ///
+    /// The house cut is a percent of the whole pot. When there is no winner, or nobody backed a
+    /// winner, every bet is refunded in full and the house keeps nothing.
+    pub fn calculate(
+        bets: &[EventPoolBet],
+        winning_team_ids: &[i32],
+        house_cut: i32,
+    ) -> PoolPayouts {
+        let pot: i64 = bets.iter().map(|bet| i64::from(bet.currency)).sum();
+
+        let winning_stake: i64 = bets
+            .iter()
+            .filter(|bet| winning_team_ids.contains(&bet.team_id))
+            .map(|bet| i64::from(bet.currency))
+            .sum();
+
+        if winning_stake == 0 {
+            return PoolPayouts {
+                payouts: bets.iter().map(|bet| bet.currency).collect(),
+                house_take: 0,
+                refunded: true,
+            };
+        }
+
+        let distributable = pot - pot * i64::from(house_cut) / 100;
+
+        let payouts: Vec<i32> = bets
+            .iter()
+            .map(|bet| {
+                if winning_team_ids.contains(&bet.team_id) {
+                    (distributable * i64::from(bet.currency) / winning_stake) as i32
+                } else {
+                    0
+                }
+            })
+            .collect();
+
+        let paid: i64 = payouts.iter().map(|payout| i64::from(*payout)).sum();
+
+        PoolPayouts {
+            payouts,
+            house_take: (pot - paid) as i32,
+            refunded: false,
+        }
+    }
+}
+
+pub struct PoolSettlement;
+
+impl PoolSettlement {
+    /// Settles the pool of the event in the provided transaction.
+    ///
+    /// The pool is paid out to the bets on the teams marked as winners, without a winner it is
+    /// refunded. The house take is credited to the creator of the event. A pool is only ever
+    /// settled once until the settlement is reversed.
+    pub async fn settle_event(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<(), TalliiError> {
+        let event_pool = match EventPoolsTable::get_for_update(tx, event_id).await? {
+            Some(event_pool) if event_pool.settled_at.is_none() => event_pool,
+            _ => return Ok(()),
+        };
+
+        let winning_team_ids: Vec<i32> = EventsTeamsTable::get_winners(tx, event_id)
+            .await?
+            .into_iter()
+            .map(|team| team.team_id)
+            .collect();
+
+        let bets = EventPoolBetsTable::get_active_for_update(tx, event_id).await?;
+
+        let settlement = PoolPayouts::calculate(&bets, &winning_team_ids, event_pool.house_cut);
+
+        for (bet, payout) in bets.iter().zip(settlement.payouts) {
+            if payout > 0 {
+                let reason = if settlement.refunded {
+                    TransactionReason::PoolRefund
+                } else {
+                    TransactionReason::PoolPayout
+                };
+
+                CurrencyTable::post(tx, &bet.user_id, payout, reason, Some(bet.bet_id)).await?;
+            }
+
+            EventPoolBetsTable::update_state(tx, &bet.bet_id, "settled", Some(payout)).await?;
+        }
+
+        if settlement.house_take > 0 {
+            let house = EventPoolsTable::get_house(tx, event_id).await?;
+
+            CurrencyTable::post(
+                tx,
+                &house.creator_user_id,
+                settlement.house_take,
+                TransactionReason::PoolHouseTake,
+                Some(*event_id),
+            )
+            .await?;
+        }
+
+        EventPoolsTable::settle(tx, event_id, settlement.house_take).await?;
+
+        Ok(())
+    }
+
+    /// Reverses the settlement of the pool of the event in the provided transaction.
+    ///
+    /// Payouts, refunds and the house take are moved back into escrow with a reversal entry in
+    /// the ledger and the bets are active again, so they will be paid out the next time the
+    /// event is settled. A user that already spent their payout is left with a negative balance.
+    pub async fn reverse_event(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<(), TalliiError> {
+        let event_pool = match EventPoolsTable::get_for_update(tx, event_id).await? {
+            Some(event_pool) if event_pool.settled_at.is_some() => event_pool,
+            _ => return Ok(()),
+        };
+
+        let bets = EventPoolBetsTable::get_settled_for_update(tx, event_id).await?;
+
+        for bet in bets {
+            if let Some(payout) = bet.payout.filter(|payout| *payout > 0) {
+                CurrencyTable::post(
+                    tx,
+                    &bet.user_id,
+                    -payout,
+                    TransactionReason::SettlementReversal,
+                    Some(bet.bet_id),
+                )
+                .await?;
+            }
+
+            EventPoolBetsTable::update_state(tx, &bet.bet_id, "active", None).await?;
+        }
+
+        if let Some(house_take) = event_pool.house_take.filter(|house_take| *house_take > 0) {
+            let house = EventPoolsTable::get_house(tx, event_id).await?;
+
+            CurrencyTable::post(
+                tx,
+                &house.creator_user_id,
+                -house_take,
+                TransactionReason::SettlementReversal,
+                Some(*event_id),
+            )
+            .await?;
+        }
+
+        EventPoolsTable::unsettle(tx, event_id).await?;
+
+        Ok(())
+    }
+
+    /// Refunds the active bets on a team that is about to be deleted. Returns whether there were
+    /// any.
+    pub async fn refund_team(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        team_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let bets = EventPoolBetsTable::get_active_for_team_for_update(tx, team_id).await?;
+
+        PoolSettlement::refund(tx, bets).await
+    }
+
+    /// Refunds the active bets in the pool of an event that is about to be deleted. Returns
+    /// whether there were any.
+    pub async fn refund_event(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let bets = EventPoolBetsTable::get_active_for_update(tx, event_id).await?;
+
+        PoolSettlement::refund(tx, bets).await
+    }
+
+    async fn refund(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        bets: Vec<EventPoolBet>,
+    ) -> Result<bool, TalliiError> {
+        let any = !bets.is_empty();
+
+        for bet in bets {
+            CurrencyTable::post(
+                tx,
+                &bet.user_id,
+                bet.currency,
+                TransactionReason::PoolRefund,
+                Some(bet.bet_id),
+            )
+            .await?;
+
+            EventPoolBetsTable::update_state(tx, &bet.bet_id, "cancelled", None).await?;
+        }
+
+        Ok(any)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an active bet of the user on the team
+    fn bet(user_id: i32, team_id: i32, currency: i32) -> EventPoolBet {
+        EventPoolBet {
+            bet_id: user_id,
+            event_id: 1,
+            user_id,
+            team_id,
+            currency,
+            state: String::from("active"),
+            payout: None,
+            created_at: chrono::NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    #[test]
+    fn calculate_takes_the_house_cut_from_the_whole_pot() {
+        let bets = vec![bet(1, 1, 100), bet(2, 2, 100)];
+
+        assert_eq!(
+            PoolPayouts::calculate(&bets, &[1], 10),
+            PoolPayouts {
+                payouts: vec![180, 0],
+                house_take: 20,
+                refunded: false,
+            }
+        );
+    }
+
+    #[test]
+    fn calculate_splits_the_pot_by_stake() {
+        let bets = vec![bet(1, 1, 100), bet(2, 1, 300), bet(3, 2, 400)];
+
+        assert_eq!(
+            PoolPayouts::calculate(&bets, &[1], 0),
+            PoolPayouts {
+                payouts: vec![200, 600, 0],
+                house_take: 0,
+                refunded: false,
+            }
+        );
+    }
+
+    #[test]
+    fn calculate_gives_the_rounding_remainder_to_the_house() {
+        let bets = vec![bet(1, 1, 1), bet(2, 1, 2), bet(3, 2, 7)];
+
+        assert_eq!(
+            PoolPayouts::calculate(&bets, &[1], 0),
+            PoolPayouts {
+                payouts: vec![3, 6, 0],
+                house_take: 1,
+                refunded: false,
+            }
+        );
+    }
+
+    #[test]
+    fn calculate_refunds_when_nobody_backed_the_winner() {
+        let bets = vec![bet(1, 1, 100), bet(2, 2, 50)];
+
+        assert_eq!(
+            PoolPayouts::calculate(&bets, &[3], 10),
+            PoolPayouts {
+                payouts: vec![100, 50],
+                house_take: 0,
+                refunded: true,
+            }
+        );
+    }
+
+    #[test]
+    fn calculate_refunds_without_a_winner() {
+        let bets = vec![bet(1, 1, 100), bet(2, 2, 50)];
+
+        assert_eq!(
+            PoolPayouts::calculate(&bets, &[], 10),
+            PoolPayouts {
+                payouts: vec![100, 50],
+                house_take: 0,
+                refunded: true,
+            }
+        );
+    }
+}
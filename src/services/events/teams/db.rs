@@ -2,7 +2,7 @@ use sqlx::{PgPool, Transaction};
 
 use crate::errors::TalliiError;
 
-use super::models::{NewTeam, Team};
+use super::models::{NewTeam, Team, TeamAssignment};
 use crate::services::events::members::models::EventMember;
 use crate::services::events::teams::models::UpdateTeamRequest;
 use crate::services::users::models::PublicUser;
@@ -34,9 +34,12 @@ impl EventsTeamsTable {
         Ok(created_team)
     }
 
-
     // Gets a single team for an event
-    pub async fn get_one(pool: &PgPool, event_id: &i32, team_id: &i32) -> Result<Team, TalliiError> {
+    pub async fn get_one(
+        pool: &PgPool,
+        event_id: &i32,
+        team_id: &i32,
+    ) -> Result<Team, TalliiError> {
         let teams = sqlx::query_as::<_, Team>(
             r#"
                 select
@@ -56,14 +59,38 @@ impl EventsTeamsTable {
                     score desc
             "#,
         )
-            .bind(event_id)
-            .bind(team_id)
-            .fetch_one(pool)
-            .await?;
+        .bind(event_id)
+        .bind(team_id)
+        .fetch_one(pool)
+        .await?;
 
         Ok(teams)
     }
 
+    /// Gets a single team by its id
+    pub async fn get_by_id(pool: &PgPool, team_id: &i32) -> Result<Option<Team>, TalliiError> {
+        let team = sqlx::query_as::<_, Team>(
+            r#"
+                select
+                    team_id,
+                    event_id,
+                    name,
+                    score,
+                    winner,
+                    created_at
+                from
+                    events_teams
+                where
+                    team_id = $1
+            "#,
+        )
+        .bind(team_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(team)
+    }
+
     // Gets all teams for a single event
     pub async fn get_many(pool: &PgPool, event_id: &i32) -> Result<Vec<Team>, TalliiError> {
         let teams = sqlx::query_as::<_, Team>(
@@ -90,9 +117,38 @@ impl EventsTeamsTable {
         Ok(teams)
     }
 
+    /// Gets the teams of an event that have been marked as a winner
+    pub async fn get_winners(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<Vec<Team>, TalliiError> {
+        let teams = sqlx::query_as::<_, Team>(
+            r#"
+                select
+                    team_id,
+                    event_id,
+                    name,
+                    score,
+                    winner,
+                    created_at
+                from
+                    events_teams
+                where
+                    event_id = $1
+                and
+                    winner = true
+            "#,
+        )
+        .bind(event_id)
+        .fetch_all(tx)
+        .await?;
+
+        Ok(teams)
+    }
+
     /// Update a specific team
     pub async fn update(
-        pool: &PgPool,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
         team_id: &i32,
         team: &UpdateTeamRequest,
     ) -> Result<(), TalliiError> {
@@ -112,14 +168,17 @@ impl EventsTeamsTable {
         .bind(&team.score)
         .bind(&team.winner)
         .bind(team_id)
-        .execute(pool)
+        .execute(tx)
         .await?;
 
         Ok(())
     }
 
     /// Delete a specific team
-    pub async fn delete(pool: &PgPool, team_id: &i32) -> Result<(), TalliiError> {
+    pub async fn delete(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        team_id: &i32,
+    ) -> Result<(), TalliiError> {
         sqlx::query(
             r#"
                 delete from
@@ -129,7 +188,7 @@ impl EventsTeamsTable {
             "#,
         )
         .bind(team_id)
-        .execute(pool)
+        .execute(tx)
         .await?;
 
         Ok(())
@@ -212,10 +271,10 @@ impl EventTeamMembersTable {
                    ($1, $2)
             "#,
         )
-            .bind(team_id)
-            .bind(member.member_id)
-            .execute(tx)
-            .await?;
+        .bind(team_id)
+        .bind(member.member_id)
+        .execute(tx)
+        .await?;
 
         Ok(())
     }
@@ -251,6 +310,41 @@ impl EventTeamMembersTable {
         Ok(members)
     }
 
+    /// Gets the teams the user is assigned to across all events
+    pub async fn get_by_user_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<TeamAssignment>, TalliiError> {
+        let teams = sqlx::query_as::<_, TeamAssignment>(
+            r#"
+                select
+                    t.team_id,
+                    t.event_id,
+                    t.name,
+                    etm.created_at
+                from
+                    events_teams_members etm
+                inner join
+                    events_members em
+                on
+                    em.member_id = etm.member_id
+                inner join
+                    events_teams t
+                on
+                    t.team_id = etm.team_id
+                where
+                    em.user_id = $1
+                order by
+                    etm.created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(teams)
+    }
+
     /// Deletes a team member from a team
     pub async fn delete(pool: &PgPool, team_id: &i32, user_id: &i32) -> Result<(), TalliiError> {
         sqlx::query(
@@ -275,9 +369,12 @@ impl EventTeamMembersTable {
         Ok(())
     }
 
-
     /// Deletes a team member from a team
-    pub async fn delete_by_event_id(tx: &mut Transaction<'_, sqlx::Postgres>, event_id: &i32, user_id: &i32) -> Result<(), TalliiError> {
+    pub async fn delete_by_event_id(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
         sqlx::query(
             r#"
                 delete from
@@ -292,10 +389,10 @@ impl EventTeamMembersTable {
                     em.event_id = $2
             "#,
         )
-            .bind(user_id)
-            .bind(event_id)
-            .execute(tx)
-            .await?;
+        .bind(user_id)
+        .bind(event_id)
+        .execute(tx)
+        .await?;
 
         Ok(())
     }
@@ -2,21 +2,27 @@ use actix_web::{web, HttpResponse};
 
 use sqlx::PgPool;
 
-use crate::services::auth::AuthenticatedUser;
+use crate::services::auth::{ApiUser, AuthenticatedUser};
+use crate::services::tokens::models::{EVENTS_READ, SCORES_WRITE};
 use crate::services::{SuccessResponse, TalliiResponse};
 
 use super::db::{EventTeamMembersTable, EventsTeamsTable};
 use super::models::{NewTeam, UpdateTeamRequest};
 use crate::errors::TalliiError;
+use crate::services::events::db::EventsTable;
 use crate::services::events::members::db::EventMembersTable;
 use crate::services::events::members::models::EventMember;
+use crate::services::events::pools::settlement::PoolSettlement;
+use crate::services::wagers::settlement::WagerSettlement;
 
 /// Gets all Teams for an event
 pub async fn get_teams(
     pool: web::Data<PgPool>,
-    _user: AuthenticatedUser,
+    user: ApiUser,
     event_id: web::Path<i32>,
 ) -> TalliiResponse {
+    user.require_scope(EVENTS_READ)?;
+
     let teams = EventsTeamsTable::get_many(&pool, &event_id).await?;
 
     Ok(HttpResponse::Ok().json(teams))
@@ -25,9 +31,11 @@ pub async fn get_teams(
 /// Gets a single Team for an event
 pub async fn get_team(
     pool: web::Data<PgPool>,
-    _user: AuthenticatedUser,
+    user: ApiUser,
     params: web::Path<(i32, i32)>,
 ) -> TalliiResponse {
+    user.require_scope(EVENTS_READ)?;
+
     let (event_id, team_id) = params.into_inner();
 
     let teams = EventsTeamsTable::get_one(&pool, &event_id, &team_id).await?;
@@ -89,10 +97,12 @@ pub async fn create_team(
 /// Updates a specific team
 pub async fn update_team(
     pool: web::Data<PgPool>,
-    user: AuthenticatedUser,
+    user: ApiUser,
     path_params: web::Path<(i32, i32)>,
     team: web::Json<UpdateTeamRequest>,
 ) -> TalliiResponse {
+    user.require_scope(SCORES_WRITE)?;
+
     let (event_id, team_id) = path_params.into_inner();
 
     // check if the user is a member
@@ -103,7 +113,45 @@ pub async fn update_team(
         return Err(TalliiError::FORBIDDEN.default());
     }
 
-    EventsTeamsTable::update(&pool, &team_id, &team).await?;
+    // make sure the team is a part of the event
+    let existing = match EventsTeamsTable::get_by_id(&pool, &team_id).await? {
+        Some(existing) if existing.event_id == event_id => existing,
+        _ => return Err(TalliiError::NOT_FOUND.default()),
+    };
+
+    // the winner decides the wagers and the pool, so only an admin can mark one and only while
+    // the event has not been settled
+    if existing.winner != team.winner {
+        if !EventMembersTable::is_admin(&pool, &event_id, &user.user_id).await? {
+            return Err(TalliiError::FORBIDDEN.message(String::from(
+                "Only an admin can change the winner of an event.",
+            )));
+        }
+
+        if EventsTable::get_event_by_id(&pool, &event_id)
+            .await?
+            .finalized_at
+            .is_some()
+        {
+            return Err(TalliiError::BAD_REQUEST.message(String::from(
+                "The event has been settled, reverse the settlement to change the winner.",
+            )));
+        }
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    EventsTeamsTable::update(&mut tx, &team_id, &team).await?;
+
+    // marking a winner decides the event, so its wagers and pool are settled along with the update
+    if team.winner && !existing.winner {
+        WagerSettlement::settle_event(&mut tx, &event_id).await?;
+        PoolSettlement::settle_event(&mut tx, &event_id).await?;
+    }
+
+    // commit the transaction
+    tx.commit().await?;
 
     Ok(HttpResponse::Ok().json(SuccessResponse {
         code: String::from("UPDATED_EVENT_TEAM"),
@@ -127,7 +175,32 @@ pub async fn delete_team(
         return Err(TalliiError::FORBIDDEN.default());
     }
 
-    EventsTeamsTable::delete(&pool, &team_id).await?;
+    // make sure the team is a part of the event
+    match EventsTeamsTable::get_by_id(&pool, &team_id).await? {
+        Some(existing) if existing.event_id == event_id => (),
+        _ => return Err(TalliiError::NOT_FOUND.default()),
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    // the stakes in escrow are given back before the wagers and bets are deleted
+    let had_wagers = WagerSettlement::cancel_team(&mut tx, &team_id).await?;
+    let had_bets = PoolSettlement::refund_team(&mut tx, &team_id).await?;
+
+    // only an admin can void the wagers and bets on a team, the transaction is rolled back
+    if (had_wagers || had_bets)
+        && !EventMembersTable::is_admin(&pool, &event_id, &user.user_id).await?
+    {
+        return Err(TalliiError::FORBIDDEN.message(String::from(
+            "Only an admin can delete a team with open wagers or bets.",
+        )));
+    }
+
+    EventsTeamsTable::delete(&mut tx, &team_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
 
     Ok(HttpResponse::Ok().json(SuccessResponse {
         code: String::from("DELETED_EVENT_TEAM"),
@@ -138,9 +211,11 @@ pub async fn delete_team(
 /// Gets all Teams and Members for an Event
 pub async fn get_team_members(
     pool: web::Data<PgPool>,
-    _user: AuthenticatedUser,
+    user: ApiUser,
     path_params: web::Path<(i32, i32)>,
 ) -> TalliiResponse {
+    user.require_scope(EVENTS_READ)?;
+
     let (_event_id, team_id) = path_params.into_inner();
 
     let members = EventTeamMembersTable::get_many(&pool, &team_id).await?;
@@ -173,7 +248,6 @@ pub async fn delete_team_member(
     }))
 }
 
-
 /// Changes team of the provided user
 pub async fn change_team(
     pool: web::Data<PgPool>,
@@ -34,6 +34,15 @@ pub struct TeamPlayer {
     pub created_at: chrono::NaiveDateTime,
 }
 
+/// Representation of a team a user is assigned to
+#[derive(sqlx::FromRow, Deserialize, Serialize, Debug)]
+pub struct TeamAssignment {
+    pub team_id: i32,
+    pub event_id: i32,
+    pub name: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
 /// Query params for getting players on a specific team
 #[derive(sqlx::FromRow, Deserialize, Serialize, Debug)]
 pub struct TeamPlayerQueryParams {
@@ -1,8 +1,10 @@
-use sqlx::PgPool;
+use sqlx::{PgPool, Transaction};
 
 use crate::errors::TalliiError;
 use crate::services::users::models::PublicUser;
 
+use super::models::{FollowRequest, Friend, FriendExists, SuggestedUser};
+
 pub struct FriendsTable;
 
 impl FriendsTable {
@@ -15,9 +17,10 @@ impl FriendsTable {
             r#"
                 select
                     u.user_id,
-                    u.avatar,
+                    u.emoji,
+                    u.bg_color,
                     u.username,
-                    u.taunt
+                    u.bio
                 from
                     friends f
                 inner join
@@ -26,6 +29,8 @@ impl FriendsTable {
                     f.user_id = u.user_id
                 where
                     f.friend_user_id = $1
+                and
+                    f.state = 'active'
             "#,
         )
         .bind(user_id)
@@ -44,9 +49,10 @@ impl FriendsTable {
             r#"
                 select
                     u.user_id,
-                    u.avatar,
+                    u.emoji,
+                    u.bg_color,
                     u.username,
-                    u.taunt
+                    u.bio
                 from
                     friends f
                 inner join
@@ -55,6 +61,8 @@ impl FriendsTable {
                     f.friend_user_id = u.user_id
                 where
                     f.user_id = $1
+                and
+                    f.state = 'active'
             "#,
         )
         .bind(user_id)
@@ -64,28 +72,230 @@ impl FriendsTable {
         Ok(followers)
     }
 
-    /// Follows a user based on the provided username
+    /// Checks if the user is actively following the friend
+    pub async fn is_following(
+        pool: &PgPool,
+        user_id: &i32,
+        friend_user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let following = sqlx::query_as::<_, FriendExists>(
+            r#"
+                select
+                    exists (
+                        select
+                            1
+                        from
+                            friends
+                        where
+                            user_id = $1
+                        and
+                            friend_user_id = $2
+                        and
+                            state = 'active'
+                    )
+            "#,
+        )
+        .bind(user_id)
+        .bind(friend_user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(following.exists)
+    }
+
+    /// Checks if the viewer can see the followers, following and events of the user, which only
+    /// approved followers can when the user is private and nobody can when either blocked the other
+    pub async fn can_view(
+        pool: &PgPool,
+        viewer_user_id: &i32,
+        user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let visible = sqlx::query_as::<_, FriendExists>(
+            r#"
+                select
+                    exists (
+                        select
+                            1
+                        from
+                            users u
+                        where
+                            u.user_id = $2
+                        and not exists (
+                            select
+                                1
+                            from
+                                friends b
+                            where
+                                b.state = 'blocked'
+                            and (
+                                (b.user_id = $1 and b.friend_user_id = $2)
+                                or (b.user_id = $2 and b.friend_user_id = $1)
+                            )
+                        )
+                        and (
+                            u.private = false
+                            or u.user_id = $1
+                            or exists (
+                                select
+                                    1
+                                from
+                                    friends f
+                                where
+                                    f.user_id = $1
+                                and
+                                    f.friend_user_id = u.user_id
+                                and
+                                    f.state = 'active'
+                            )
+                        )
+                    )
+            "#,
+        )
+        .bind(viewer_user_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(visible.exists)
+    }
+
+    /// Follows a user based on the provided username, following a private user only requests
+    /// to follow them. Following a user twice keeps the existing follow or request.
     pub async fn follow_user_by_id(
         pool: &PgPool,
         user_id: &i32,
         friend_user_id: &i32,
-    ) -> Result<(), TalliiError> {
-        sqlx::query(
+    ) -> Result<String, TalliiError> {
+        let friend = sqlx::query_as::<_, Friend>(
             r#"
                 insert into
                     friends (user_id, friend_user_id, state)
-                values
-                    ($1, $2, 'active')
+                select
+                    $1, u.user_id, case when u.private then 'pending' else 'active' end
+                from
+                    users u
+                where
+                    u.user_id = $2
+                on conflict (user_id, friend_user_id) do update set
+                    state = friends.state
+                returning
+                    user_id,
+                    friend_user_id,
+                    state,
+                    created_at
             "#,
         )
         .bind(user_id)
         .bind(friend_user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(friend.state)
+    }
+
+    /// Gets the users that requested to follow the user, oldest first
+    pub async fn get_requests_by_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<FollowRequest>, TalliiError> {
+        let requests = sqlx::query_as::<_, FollowRequest>(
+            r#"
+                select
+                    u.user_id,
+                    u.emoji,
+                    u.bg_color,
+                    u.username,
+                    u.bio,
+                    f.created_at as requested_at
+                from
+                    friends f
+                inner join
+                    users u
+                on
+                    f.user_id = u.user_id
+                where
+                    f.friend_user_id = $1
+                and
+                    f.state = 'pending'
+                order by
+                    f.created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Approves the request of the follower to follow the user, returns false when there is no
+    /// such request
+    pub async fn approve_request(
+        pool: &PgPool,
+        user_id: &i32,
+        follower_user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                update
+                    friends
+                set
+                    state = 'active'
+                where
+                    user_id = $1
+                and
+                    friend_user_id = $2
+                and
+                    state = 'pending'
+            "#,
+        )
+        .bind(follower_user_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Approves every request to follow the user, used when the user stops being private
+    pub async fn approve_all_requests(pool: &PgPool, user_id: &i32) -> Result<(), TalliiError> {
+        sqlx::query(
+            "update friends set state = 'active' where friend_user_id = $1 and state = 'pending'",
+        )
+        .bind(user_id)
         .execute(pool)
         .await?;
 
         Ok(())
     }
 
+    /// Rejects the request of the follower to follow the user, returns false when there is no
+    /// such request
+    pub async fn reject_request(
+        pool: &PgPool,
+        user_id: &i32,
+        follower_user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                delete from
+                    friends
+                where
+                    user_id = $1
+                and
+                    friend_user_id = $2
+                and
+                    state = 'pending'
+            "#,
+        )
+        .bind(follower_user_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
     /// Unfollows a user based on the provided username
     pub async fn unfollow_user_by_id(
         pool: &PgPool,
@@ -99,6 +309,7 @@ impl FriendsTable {
                 where
                     f.user_id = $1
                     and f.friend_user_id = $2
+                    and f.state <> 'blocked'
             "#,
         )
         .bind(user_id)
@@ -108,4 +319,309 @@ impl FriendsTable {
 
         Ok(())
     }
+
+    /// Checks if either user blocked the other
+    pub async fn is_blocked(
+        pool: &PgPool,
+        user_id: &i32,
+        other_user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let blocked = sqlx::query_as::<_, FriendExists>(
+            r#"
+                select
+                    exists (
+                        select
+                            1
+                        from
+                            friends
+                        where
+                            state = 'blocked'
+                        and (
+                            (user_id = $1 and friend_user_id = $2)
+                            or (user_id = $2 and friend_user_id = $1)
+                        )
+                    )
+            "#,
+        )
+        .bind(user_id)
+        .bind(other_user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(blocked.exists)
+    }
+
+    /// Gets the users the user blocked
+    pub async fn get_blocked_by_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<PublicUser>, TalliiError> {
+        let blocked = sqlx::query_as::<_, PublicUser>(
+            r#"
+                select
+                    u.user_id,
+                    u.emoji,
+                    u.bg_color,
+                    u.username,
+                    u.bio
+                from
+                    friends f
+                inner join
+                    users u
+                on
+                    f.friend_user_id = u.user_id
+                where
+                    f.user_id = $1
+                and
+                    f.state = 'blocked'
+                order by
+                    f.created_at desc
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(blocked)
+    }
+
+    /// Blocks a user, which removes the follows and follow requests between the two users
+    pub async fn block_user_by_id(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        blocked_user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                delete from
+                    friends
+                where
+                    state <> 'blocked'
+                and (
+                    (user_id = $1 and friend_user_id = $2)
+                    or (user_id = $2 and friend_user_id = $1)
+                )
+            "#,
+        )
+        .bind(user_id)
+        .bind(blocked_user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+                insert into
+                    friends (user_id, friend_user_id, state)
+                values
+                    ($1, $2, 'blocked')
+                on conflict (user_id, friend_user_id) do nothing
+            "#,
+        )
+        .bind(user_id)
+        .bind(blocked_user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Unblocks a user, returns false when the user was not blocked
+    pub async fn unblock_user_by_id(
+        pool: &PgPool,
+        user_id: &i32,
+        blocked_user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                delete from
+                    friends
+                where
+                    user_id = $1
+                and
+                    friend_user_id = $2
+                and
+                    state = 'blocked'
+            "#,
+        )
+        .bind(user_id)
+        .bind(blocked_user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Gets the users both users follow
+    pub async fn get_mutuals_by_id(
+        pool: &PgPool,
+        user_id: &i32,
+        other_user_id: &i32,
+    ) -> Result<Vec<PublicUser>, TalliiError> {
+        let mutuals = sqlx::query_as::<_, PublicUser>(
+            r#"
+                select
+                    u.user_id,
+                    u.emoji,
+                    u.bg_color,
+                    u.username,
+                    u.bio
+                from
+                    friends a
+                inner join
+                    friends b
+                on
+                    b.friend_user_id = a.friend_user_id
+                inner join
+                    users u
+                on
+                    u.user_id = a.friend_user_id
+                where
+                    a.user_id = $1
+                and
+                    a.state = 'active'
+                and
+                    b.user_id = $2
+                and
+                    b.state = 'active'
+                order by
+                    u.username
+            "#,
+        )
+        .bind(user_id)
+        .bind(other_user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(mutuals)
+    }
+
+    /// Suggests users to follow, ranked by the follows, events and teams they share with the user.
+    /// Users already followed or requested, blocked either way and deleted are left out.
+    pub async fn get_suggestions_by_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<SuggestedUser>, TalliiError> {
+        let suggestions = sqlx::query_as::<_, SuggestedUser>(
+            r#"
+                with candidates as (
+                    -- users followed by the users the user follows
+                    select
+                        f2.friend_user_id as user_id,
+                        1 as mutual_follows,
+                        0 as shared_events,
+                        0 as shared_teams
+                    from
+                        friends f1
+                    inner join
+                        friends f2
+                    on
+                        f2.user_id = f1.friend_user_id
+                    and
+                        f2.state = 'active'
+                    where
+                        f1.user_id = $1
+                    and
+                        f1.state = 'active'
+
+                    union all
+
+                    -- users in the same events
+                    select
+                        em2.user_id,
+                        0,
+                        1,
+                        0
+                    from
+                        events_members em1
+                    inner join
+                        events_members em2
+                    on
+                        em2.event_id = em1.event_id
+                    and
+                        em2.state = 'active'
+                    where
+                        em1.user_id = $1
+                    and
+                        em1.state = 'active'
+
+                    union all
+
+                    -- users on the same teams
+                    select
+                        em2.user_id,
+                        0,
+                        0,
+                        1
+                    from
+                        events_members em1
+                    inner join
+                        events_teams_members etm1
+                    on
+                        etm1.member_id = em1.member_id
+                    inner join
+                        events_teams_members etm2
+                    on
+                        etm2.team_id = etm1.team_id
+                    inner join
+                        events_members em2
+                    on
+                        em2.member_id = etm2.member_id
+                    where
+                        em1.user_id = $1
+                )
+                select
+                    u.user_id,
+                    u.emoji,
+                    u.bg_color,
+                    u.username,
+                    u.bio,
+                    sum(c.mutual_follows) as mutual_follows,
+                    sum(c.shared_events) as shared_events,
+                    sum(c.shared_teams) as shared_teams
+                from
+                    candidates c
+                inner join
+                    users u
+                on
+                    u.user_id = c.user_id
+                where
+                    c.user_id <> $1
+                and
+                    u.deleted_at is null
+                and not exists (
+                    select
+                        1
+                    from
+                        friends f
+                    where
+                        (f.user_id = $1 and f.friend_user_id = c.user_id)
+                        or (f.user_id = c.user_id and f.friend_user_id = $1 and f.state = 'blocked')
+                )
+                group by
+                    u.user_id
+                order by
+                    3 * sum(c.mutual_follows) + 2 * sum(c.shared_events) + sum(c.shared_teams) desc,
+                    u.user_id
+                limit 20
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(suggestions)
+    }
+
+    /// Deletes every follow from or to the user
+    pub async fn delete_all_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query("delete from friends where user_id = $1 or friend_user_id = $1")
+            .bind(user_id)
+            .execute(tx)
+            .await?;
+
+        Ok(())
+    }
 }
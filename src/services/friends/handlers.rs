@@ -31,7 +31,29 @@ pub async fn follow_user(
     user: AuthenticatedUser,
 ) -> TalliiResponse {
     if let Some(friend) = UsersTable::get_by_username(&pool, &username).await? {
-        FriendsTable::follow_user_by_id(&pool, &user.user_id, &friend.user_id).await?;
+        if friend.user_id == user.user_id {
+            return Err(
+                TalliiError::BAD_REQUEST.message(String::from("You cannot follow yourself."))
+            );
+        }
+
+        if FriendsTable::is_blocked(&pool, &user.user_id, &friend.user_id).await? {
+            return Err(
+                TalliiError::FORBIDDEN.message(String::from("You cannot follow this user."))
+            );
+        }
+
+        // a private user has to approve the follow first
+        if FriendsTable::follow_user_by_id(&pool, &user.user_id, &friend.user_id).await?
+            == "pending"
+        {
+            return Ok(HttpResponse::Ok().json(SuccessResponse {
+                code: String::from("REQUESTED_FOLLOW"),
+                message: String::from(
+                    "The provided user is private and has been requested to be followed.",
+                ),
+            }));
+        }
 
         Ok(HttpResponse::Ok().json(SuccessResponse {
             code: String::from("FOLLOWED_USER"),
@@ -64,9 +86,13 @@ pub async fn unfollow_user(
 pub async fn get_user_followers(
     pool: web::Data<PgPool>,
     username: web::Path<String>,
-    _user: AuthenticatedUser,
+    viewer: AuthenticatedUser,
 ) -> TalliiResponse {
     if let Some(user) = UsersTable::get_by_username(&pool, &username).await? {
+        if !FriendsTable::can_view(&pool, &viewer.user_id, &user.user_id).await? {
+            return Err(TalliiError::PRIVATE_ACCOUNT.default());
+        }
+
         let followers = FriendsTable::get_followers_by_id(&pool, &user.user_id).await?;
 
         Ok(HttpResponse::Ok().json(followers))
@@ -79,9 +105,13 @@ pub async fn get_user_followers(
 pub async fn get_user_following(
     pool: web::Data<PgPool>,
     username: web::Path<String>,
-    _user: AuthenticatedUser,
+    viewer: AuthenticatedUser,
 ) -> TalliiResponse {
     if let Some(user) = UsersTable::get_by_username(&pool, &username).await? {
+        if !FriendsTable::can_view(&pool, &viewer.user_id, &user.user_id).await? {
+            return Err(TalliiError::PRIVATE_ACCOUNT.default());
+        }
+
         let following = FriendsTable::get_following_by_id(&pool, &user.user_id).await?;
 
         Ok(HttpResponse::Ok().json(following))
@@ -89,3 +119,138 @@ pub async fn get_user_following(
         Err(TalliiError::NOT_FOUND.default())
     }
 }
+
+/// Gets the users both me and the user matching the username follow
+pub async fn get_user_mutuals(
+    pool: web::Data<PgPool>,
+    username: web::Path<String>,
+    viewer: AuthenticatedUser,
+) -> TalliiResponse {
+    let user = UsersTable::get_by_username(&pool, &username)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // the mutuals give away who the user follows
+    if !FriendsTable::can_view(&pool, &viewer.user_id, &user.user_id).await? {
+        return Err(TalliiError::PRIVATE_ACCOUNT.default());
+    }
+
+    let mutuals = FriendsTable::get_mutuals_by_id(&pool, &viewer.user_id, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(mutuals))
+}
+
+/// Gets the users suggested for me to follow
+pub async fn get_me_suggestions(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> TalliiResponse {
+    let suggestions = FriendsTable::get_suggestions_by_id(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(suggestions))
+}
+
+/// Gets the users that requested to follow me
+pub async fn get_me_follow_requests(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> TalliiResponse {
+    let requests = FriendsTable::get_requests_by_id(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(requests))
+}
+
+/// Approves the request of the user matching the username to follow me
+pub async fn approve_follow_request(
+    pool: web::Data<PgPool>,
+    username: web::Path<String>,
+    user: AuthenticatedUser,
+) -> TalliiResponse {
+    let follower = UsersTable::get_by_username(&pool, &username)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if !FriendsTable::approve_request(&pool, &user.user_id, &follower.user_id).await? {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("APPROVED_FOLLOW_REQUEST"),
+        message: String::from("The provided user now follows you."),
+    }))
+}
+
+/// Rejects the request of the user matching the username to follow me
+pub async fn reject_follow_request(
+    pool: web::Data<PgPool>,
+    username: web::Path<String>,
+    user: AuthenticatedUser,
+) -> TalliiResponse {
+    let follower = UsersTable::get_by_username(&pool, &username)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if !FriendsTable::reject_request(&pool, &user.user_id, &follower.user_id).await? {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("REJECTED_FOLLOW_REQUEST"),
+        message: String::from("The request of the provided user was rejected."),
+    }))
+}
+
+/// Gets the users me blocked
+pub async fn get_me_blocks(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    let blocked = FriendsTable::get_blocked_by_id(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(blocked))
+}
+
+/// Blocks the user matching the username, which also removes the follows between us
+pub async fn block_user(
+    pool: web::Data<PgPool>,
+    username: web::Path<String>,
+    user: AuthenticatedUser,
+) -> TalliiResponse {
+    let blocked = UsersTable::get_by_username(&pool, &username)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if blocked.user_id == user.user_id {
+        return Err(TalliiError::BAD_REQUEST.message(String::from("You cannot block yourself.")));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    FriendsTable::block_user_by_id(&mut tx, &user.user_id, &blocked.user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("BLOCKED_USER"),
+        message: String::from("The provided user has been blocked."),
+    }))
+}
+
+/// Unblocks the user matching the username
+pub async fn unblock_user(
+    pool: web::Data<PgPool>,
+    username: web::Path<String>,
+    user: AuthenticatedUser,
+) -> TalliiResponse {
+    let blocked = UsersTable::get_by_username(&pool, &username)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if !FriendsTable::unblock_user_by_id(&pool, &user.user_id, &blocked.user_id).await? {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("UNBLOCKED_USER"),
+        message: String::from("The provided user has been unblocked."),
+    }))
+}
@@ -3,8 +3,39 @@ use serde::Serialize;
 /// Database representation of a Friend
 #[derive(sqlx::FromRow, Debug, Serialize)]
 pub struct Friend {
-    pub user_id: String,
-    pub friend_user_id: String,
+    pub user_id: i32,
+    pub friend_user_id: i32,
     pub state: String,
     pub created_at: chrono::NaiveDateTime,
 }
+
+/// Represents a Friend existing
+#[derive(sqlx::FromRow, Debug)]
+pub struct FriendExists {
+    pub exists: bool,
+}
+
+/// A user that requested to follow a private user
+#[derive(sqlx::FromRow, Debug, Serialize)]
+pub struct FollowRequest {
+    pub user_id: i32,
+    pub emoji: String,
+    pub bg_color: String,
+    pub username: String,
+    pub bio: Option<String>,
+    pub requested_at: chrono::NaiveDateTime,
+}
+
+/// A user suggested to follow along with what the users have in common
+#[derive(sqlx::FromRow, Debug, Serialize)]
+pub struct SuggestedUser {
+    pub user_id: i32,
+    pub emoji: String,
+    pub bg_color: String,
+    pub username: String,
+    pub bio: Option<String>,
+    /// Number of users the user follows that follow the suggested user
+    pub mutual_follows: i64,
+    pub shared_events: i64,
+    pub shared_teams: i64,
+}
@@ -31,3 +31,37 @@ pub fn users_username_followers_routes() -> Resource {
 pub fn users_username_following_routes() -> Resource {
     web::resource("/users/{username}/following").route(web::get().to(handlers::get_user_following))
 }
+
+/// Resource routes for the users both the currently logged in user and user username follow
+pub fn users_username_mutuals_routes() -> Resource {
+    web::resource("/users/{username}/mutuals").route(web::get().to(handlers::get_user_mutuals))
+}
+
+/// Resource routes for the users suggested for the currently logged in user to follow
+pub fn users_me_suggestions_routes() -> Resource {
+    web::resource("/me/suggestions").route(web::get().to(handlers::get_me_suggestions))
+}
+
+/// Resource routes for the requests to follow the currently logged in user
+pub fn users_me_follow_requests_routes() -> Resource {
+    web::resource("/me/follow-requests").route(web::get().to(handlers::get_me_follow_requests))
+}
+
+/// Resource routes for the request of a user to follow the currently logged in user
+pub fn users_me_follow_requests_username_routes() -> Resource {
+    web::resource("/me/follow-requests/{username}")
+        .route(web::put().to(handlers::approve_follow_request))
+        .route(web::delete().to(handlers::reject_follow_request))
+}
+
+/// Resource routes for the users the currently logged in user blocked
+pub fn users_me_blocks_routes() -> Resource {
+    web::resource("/me/blocks").route(web::get().to(handlers::get_me_blocks))
+}
+
+/// Resource routes blocking and unblocking a user as the currently logged in user
+pub fn users_me_blocks_username_routes() -> Resource {
+    web::resource("/me/blocks/{username}")
+        .route(web::put().to(handlers::block_user))
+        .route(web::delete().to(handlers::unblock_user))
+}
@@ -0,0 +1,147 @@
+use sqlx::{PgPool, Transaction};
+
+use crate::errors::TalliiError;
+
+use super::models::{IdentityCount, UserIdentity};
+
+pub struct IdentitiesTable;
+
+impl IdentitiesTable {
+    /// Gets the identity with the subject at the provider
+    pub async fn get_by_subject(
+        pool: &PgPool,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<UserIdentity>, TalliiError> {
+        let identity = sqlx::query_as::<_, UserIdentity>(
+            r#"
+                select
+                    identity_id,
+                    user_id,
+                    provider,
+                    subject,
+                    email,
+                    created_at
+                from
+                    user_identities
+                where
+                    provider = $1
+                and
+                    subject = $2
+            "#,
+        )
+        .bind(provider)
+        .bind(subject)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(identity)
+    }
+
+    /// Gets all identities the user linked
+    pub async fn get_by_user_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<UserIdentity>, TalliiError> {
+        let identities = sqlx::query_as::<_, UserIdentity>(
+            r#"
+                select
+                    identity_id,
+                    user_id,
+                    provider,
+                    subject,
+                    email,
+                    created_at
+                from
+                    user_identities
+                where
+                    user_id = $1
+                order by
+                    created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(identities)
+    }
+
+    /// Links the identity at the provider to the user
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        provider: &str,
+        subject: &str,
+        email: Option<&str>,
+    ) -> Result<UserIdentity, TalliiError> {
+        let identity = sqlx::query_as::<_, UserIdentity>(
+            r#"
+                insert into
+                    user_identities (user_id, provider, subject, email)
+                values
+                    ($1, $2, $3, $4)
+                returning
+                    *
+            "#,
+        )
+        .bind(user_id)
+        .bind(provider)
+        .bind(subject)
+        .bind(email)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(identity)
+    }
+
+    /// Counts the identities of the user and locks them until the transaction finishes
+    pub async fn count_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<i64, TalliiError> {
+        // lock the user so two identities can not be unlinked at the same time
+        sqlx::query("select user_id from users where user_id = $1 for update")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let count = sqlx::query_as::<_, IdentityCount>(
+            "select count(*) as count from user_identities where user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Ok(count.count)
+    }
+
+    /// Unlinks the identity at the provider from the user
+    pub async fn delete(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        provider: &str,
+    ) -> Result<bool, TalliiError> {
+        let result =
+            sqlx::query("delete from user_identities where user_id = $1 and provider = $2")
+                .bind(user_id)
+                .bind(provider)
+                .execute(tx)
+                .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Unlinks every identity of the user
+    pub async fn delete_all_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query("delete from user_identities where user_id = $1")
+            .bind(user_id)
+            .execute(tx)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::crypto::{Crypto, TokenResponse};
+use crate::errors::TalliiError;
+use crate::mailer::Mailer;
+use crate::oidc::OidcVerifier;
+use crate::services::auth::AuthenticatedUser;
+use crate::services::currency::db::CurrencyTable;
+use crate::services::currency::models::{TransactionReason, SIGNUP_GRANT};
+use crate::services::sessions::db::SessionsTable;
+use crate::services::sessions::models::NewSession;
+use crate::services::two_factor::db::{LoginChallengesTable, TwoFactorTable};
+use crate::services::two_factor::models::TwoFactorChallengeResponse;
+use crate::services::users::db::{InviteCodesTable, UsersTable, MAX_USERNAME_LENGTH};
+use crate::services::users::models::User;
+use crate::services::verification::db::VerificationTokensTable;
+use crate::services::verification::emails::verification_email;
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::IdentitiesTable;
+use super::models::{LinkIdentityRequest, OidcLoginRequest};
+
+/// Creates an account for an identity that is not linked to a user yet
+async fn create_account(
+    pool: &PgPool,
+    crypto: &Crypto,
+    mailer: &Mailer,
+    request: &OidcLoginRequest,
+    subject: &str,
+    email: &str,
+    email_verified: bool,
+) -> Result<User, TalliiError> {
+    let invite_code = request
+        .invite_code
+        .as_deref()
+        .ok_or_else(|| TalliiError::INVALID_INVITE_CODE.default())?;
+
+    let username = request.username.as_deref().map(str::trim).unwrap_or("");
+
+    if username.is_empty()
+        || username.chars().count() > MAX_USERNAME_LENGTH
+        || username.contains(|c: char| c.is_whitespace() || c == '/')
+    {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "A username without spaces or slashes is required to create an account.",
+        )));
+    }
+
+    // a username is taken while it or a recent rename from it resolves to a user, like at signup
+    if UsersTable::get_by_username(pool, &username.to_string())
+        .await?
+        .is_some()
+    {
+        return Err(TalliiError::USERNAME_TAKEN.default());
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    // use the invite code, this fails when it has no uses left or has expired
+    let invite_code = InviteCodesTable::redeem(&mut tx, invite_code)
+        .await?
+        .ok_or_else(|| TalliiError::INVALID_INVITE_CODE.default())?;
+
+    let created_user = UsersTable::create_without_password(
+        &mut tx,
+        email,
+        username,
+        &invite_code.id,
+        invite_code.created_by,
+        email_verified,
+    )
+    .await?;
+
+    // give the new user their starting currency
+    CurrencyTable::post(
+        &mut tx,
+        &created_user.user_id,
+        SIGNUP_GRANT,
+        TransactionReason::SignupGrant,
+        None,
+    )
+    .await?;
+
+    IdentitiesTable::create(
+        &mut tx,
+        &created_user.user_id,
+        &request.provider,
+        subject,
+        Some(email),
+    )
+    .await?;
+
+    // the email still has to be verified when the provider did not verify it
+    let verification_token = if email_verified {
+        None
+    } else {
+        Some(VerificationTokensTable::issue(&mut tx, crypto, &created_user.user_id, None).await?)
+    };
+
+    // commit the transaction
+    tx.commit().await?;
+
+    if let Some(verification_token) = verification_token {
+        if let Err(err) = mailer
+            .send(verification_email(mailer, email, &verification_token))
+            .await
+        {
+            error!("Failed to send verification email: {:?}", err);
+        }
+    }
+
+    UsersTable::get_by_id(pool, &created_user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::INTERNAL_SERVER_ERROR.default())
+}
+
+/// Logs a user in with an id token from an oidc provider. The identity is linked to the user with
+/// the same email when both the provider and the user verified it, otherwise a new account is
+/// created with the provided invite code.
+pub async fn login_oidc(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    mailer: web::Data<Mailer>,
+    verifier: web::Data<OidcVerifier>,
+    request: web::Json<OidcLoginRequest>,
+) -> TalliiResponse {
+    let claims = verifier
+        .verify(&request.provider, &request.id_token)
+        .await?;
+
+    let user = match IdentitiesTable::get_by_subject(&pool, &request.provider, &claims.sub).await? {
+        Some(identity) => UsersTable::get_by_id(&pool, &identity.user_id)
+            .await?
+            .ok_or_else(|| TalliiError::INVALID_LOGIN.default())?,
+        None => {
+            let email = claims.email.as_deref().ok_or_else(|| {
+                TalliiError::BAD_REQUEST
+                    .message(String::from("The provider did not share an email."))
+            })?;
+
+            match UsersTable::get_by_email(&pool, &email.to_string()).await? {
+                // the identity only owns the account when both the provider and the account
+                // verified the email, an unverified account may have been registered by someone
+                // else in advance to take over the identity
+                Some(existing) if claims.is_email_verified() && existing.verified == Some(true) => {
+                    // start the transaction
+                    let mut tx = pool.begin().await?;
+
+                    IdentitiesTable::create(
+                        &mut tx,
+                        &existing.user_id,
+                        &request.provider,
+                        &claims.sub,
+                        Some(email),
+                    )
+                    .await?;
+
+                    // commit the transaction
+                    tx.commit().await?;
+
+                    existing
+                }
+                Some(_) => {
+                    return Err(TalliiError::EMAIL_TAKEN.message(String::from(
+                        "An account with the provided email exists, log in to it or reset its password and link the identity instead.",
+                    )))
+                }
+                None => {
+                    create_account(
+                        &pool,
+                        &crypto,
+                        &mailer,
+                        &request,
+                        &claims.sub,
+                        email,
+                        claims.is_email_verified(),
+                    )
+                    .await?
+                }
+            }
+        }
+    };
+
+    if user.is_suspended(Utc::now().naive_utc()) {
+        return Err(TalliiError::ACCOUNT_SUSPENDED.default());
+    }
+
+    // the session is only started once the 2fa code is provided to /login/2fa
+    if TwoFactorTable::is_enabled(&pool, &user.user_id).await? {
+        let challenge_token =
+            LoginChallengesTable::issue(&pool, &crypto, &user.user_id, request.device_name.clone())
+                .await?;
+
+        return Ok(HttpResponse::Ok().json(TwoFactorChallengeResponse {
+            two_factor_required: true,
+            challenge_token,
+        }));
+    }
+
+    // start a new session for the user on their device
+    let new_session = NewSession::from_request(&req, request.device_name.clone());
+    let (session, refresh_token) =
+        SessionsTable::start(&pool, &crypto, &user.user_id, &new_session).await?;
+
+    // create a new jwt for the newly authorized user
+    let token = crypto
+        .generate_jwt(user.user_id, user.username, session.session_id)
+        .await?;
+
+    // respond with the token
+    Ok(HttpResponse::Ok().json(TokenResponse {
+        token,
+        refresh_token,
+    }))
+}
+
+/// Gets the identities I linked
+pub async fn get_identities(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    let identities = IdentitiesTable::get_by_user_id(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(identities))
+}
+
+/// Links an identity at an oidc provider to me
+pub async fn link_identity(
+    pool: web::Data<PgPool>,
+    verifier: web::Data<OidcVerifier>,
+    user: AuthenticatedUser,
+    request: web::Json<LinkIdentityRequest>,
+) -> TalliiResponse {
+    let claims = verifier
+        .verify(&request.provider, &request.id_token)
+        .await?;
+
+    if let Some(identity) =
+        IdentitiesTable::get_by_subject(&pool, &request.provider, &claims.sub).await?
+    {
+        return Err(
+            TalliiError::BAD_REQUEST.message(if identity.user_id == user.user_id {
+                String::from("The provided identity is already linked to your account.")
+            } else {
+                String::from("The provided identity is linked to another account.")
+            }),
+        );
+    }
+
+    if IdentitiesTable::get_by_user_id(&pool, &user.user_id)
+        .await?
+        .iter()
+        .any(|identity| identity.provider == request.provider)
+    {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "An identity at the provided provider is already linked to your account.",
+        )));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let identity = IdentitiesTable::create(
+        &mut tx,
+        &user.user_id,
+        &request.provider,
+        &claims.sub,
+        claims.email.as_deref(),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(identity))
+}
+
+/// Unlinks the identity at the provider from me, unless it is the only way left to log in
+pub async fn unlink_identity(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    provider: web::Path<String>,
+) -> TalliiResponse {
+    let me = UsersTable::get_by_id(&pool, &user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let identities = IdentitiesTable::count_for_update(&mut tx, &user.user_id).await?;
+
+    if me.password.is_empty() && identities <= 1 {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "Cannot unlink your last way to log in. Set a password first.",
+        )));
+    }
+
+    if !IdentitiesTable::delete(&mut tx, &user.user_id, &provider).await? {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("UNLINKED_IDENTITY"),
+        message: String::from("The identity was unlinked."),
+    }))
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+/// Database representation of a User Identity
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct UserIdentity {
+    pub identity_id: i32,
+    pub user_id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// The amount of identities a user has linked
+#[derive(sqlx::FromRow, Debug)]
+pub struct IdentityCount {
+    pub count: i64,
+}
+
+/// Representation of the oidc login request, the invite code and username are only needed when
+/// the identity does not belong to an account yet
+#[derive(Deserialize, Debug)]
+pub struct OidcLoginRequest {
+    pub provider: String,
+    pub id_token: String,
+    pub invite_code: Option<String>,
+    pub username: Option<String>,
+    pub device_name: Option<String>,
+}
+
+/// Proof that the user is present, their password or an id token of an identity linked to them
+#[derive(Deserialize, Debug)]
+pub struct Reauthentication {
+    pub password: Option<String>,
+    pub provider: Option<String>,
+    pub id_token: Option<String>,
+}
+
+/// Representation of the link identity request
+#[derive(Deserialize, Debug)]
+pub struct LinkIdentityRequest {
+    pub provider: String,
+    pub id_token: String,
+}
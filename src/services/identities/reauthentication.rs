@@ -0,0 +1,47 @@
+use sqlx::PgPool;
+
+use crate::crypto::Crypto;
+use crate::errors::TalliiError;
+use crate::oidc::OidcVerifier;
+use crate::services::users::models::User;
+
+use super::db::IdentitiesTable;
+use super::models::Reauthentication;
+
+/// Checks that the user is present before something irreversible. Users with a password provide
+/// it, users that only log in through a provider provide a fresh id token of a linked identity.
+pub async fn reauthenticate(
+    pool: &PgPool,
+    crypto: &Crypto,
+    verifier: &OidcVerifier,
+    me: &User,
+    proof: &Reauthentication,
+) -> Result<(), TalliiError> {
+    match (&proof.password, &proof.provider, &proof.id_token) {
+        (Some(password), _, _) => {
+            if me.password.is_empty() || !crypto.verify_password(password, &me.password).await? {
+                return Err(TalliiError::INVALID_LOGIN
+                    .message(String::from("The provided password is incorrect.")));
+            }
+        }
+        (None, Some(provider), Some(id_token)) => {
+            let claims = verifier.verify(provider, id_token).await?;
+
+            match IdentitiesTable::get_by_subject(pool, provider, &claims.sub).await? {
+                Some(identity) if identity.user_id == me.user_id => (),
+                _ => {
+                    return Err(TalliiError::INVALID_LOGIN.message(String::from(
+                        "The provided identity is not linked to your account.",
+                    )))
+                }
+            }
+        }
+        _ => {
+            return Err(TalliiError::BAD_REQUEST.message(String::from(
+                "Provide your password or an id token of an identity linked to your account.",
+            )))
+        }
+    }
+
+    Ok(())
+}
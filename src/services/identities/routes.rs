@@ -0,0 +1,20 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for logging in with an oidc provider
+pub fn login_oidc_routes() -> Resource {
+    web::resource("/login/oidc").route(web::post().to(handlers::login_oidc))
+}
+
+/// Resource routes for the identities of the currently logged in user
+pub fn me_identities_routes() -> Resource {
+    web::resource("/me/identities")
+        .route(web::get().to(handlers::get_identities))
+        .route(web::post().to(handlers::link_identity))
+}
+
+/// Resource routes for a single identity of the currently logged in user
+pub fn me_identities_entity_routes() -> Resource {
+    web::resource("/me/identities/{provider}").route(web::delete().to(handlers::unlink_identity))
+}
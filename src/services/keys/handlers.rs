@@ -0,0 +1,18 @@
+use actix_web::http::header::CACHE_CONTROL;
+use actix_web::{web, HttpResponse};
+
+use crate::crypto::Crypto;
+use crate::services::TalliiResponse;
+
+/// Seconds other services may cache the public keys for
+const JWKS_MAX_AGE_SECONDS: i64 = 300;
+
+/// Gets the public keys our jwts are signed with so other services can verify them
+pub async fn get_jwks(crypto: web::Data<Crypto>) -> TalliiResponse {
+    Ok(HttpResponse::Ok()
+        .header(
+            CACHE_CONTROL,
+            format!("public, max-age={}", JWKS_MAX_AGE_SECONDS),
+        )
+        .json(crypto.public_jwks()))
+}
@@ -0,0 +1,8 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for the public keys jwts are signed with
+pub fn well_known_jwks_routes() -> Resource {
+    web::resource("/.well-known/jwks.json").route(web::get().to(handlers::get_jwks))
+}
@@ -3,10 +3,23 @@ use serde::{Deserialize, Serialize};
 
 use crate::errors::TalliiError;
 
+pub mod admin;
 pub mod auth;
+pub mod contacts;
+pub mod currency;
 pub mod events;
 pub mod friends;
+pub mod identities;
+pub mod keys;
+pub mod passwords;
+pub mod search;
+pub mod sessions;
+pub mod tokens;
+pub mod two_factor;
 pub mod users;
+pub mod verification;
+pub mod wagers;
+pub mod waitlist;
 
 type TalliiResponse = Result<HttpResponse, TalliiError>;
 
@@ -0,0 +1,115 @@
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Transaction};
+
+use crate::crypto::Crypto;
+use crate::errors::TalliiError;
+
+use super::models::{LastPasswordResetToken, PasswordResetToken, RESET_TOKEN_MINUTES};
+
+pub struct PasswordResetTokensTable;
+
+impl PasswordResetTokensTable {
+    /// Issues a new password reset token for the user and returns the token to email to them
+    pub async fn issue(
+        pool: &PgPool,
+        crypto: &Crypto,
+        user_id: &i32,
+    ) -> Result<String, TalliiError> {
+        let token = crypto.generate_opaque_token();
+        let expires_at = (Utc::now() + Duration::minutes(RESET_TOKEN_MINUTES)).naive_utc();
+
+        sqlx::query(
+            r#"
+                insert into
+                    users_password_reset_tokens (token_hash, user_id, expires_at)
+                values
+                    ($1, $2, $3)
+            "#,
+        )
+        .bind(crypto.hash_token(&token))
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Gets when the last password reset token of the user was created
+    pub async fn get_last_created_at(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Option<chrono::NaiveDateTime>, TalliiError> {
+        let last = sqlx::query_as::<_, LastPasswordResetToken>(
+            r#"
+                select
+                    max(created_at) as last_created_at
+                from
+                    users_password_reset_tokens
+                where
+                    user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(last.last_created_at)
+    }
+
+    /// Gets an unused and unexpired password reset token by its hash and locks it until the
+    /// transaction finishes
+    pub async fn get_valid_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<PasswordResetToken>, TalliiError> {
+        let token = sqlx::query_as::<_, PasswordResetToken>(
+            r#"
+                select
+                    token_hash,
+                    user_id,
+                    expires_at,
+                    used_at,
+                    created_at
+                from
+                    users_password_reset_tokens
+                where
+                    token_hash = $1
+                and
+                    used_at is null
+                and
+                    expires_at > current_timestamp
+                for update
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Marks every unused password reset token of the user as used
+    pub async fn use_all_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    users_password_reset_tokens
+                set
+                    used_at = current_timestamp
+                where
+                    user_id = $1
+                and
+                    used_at is null
+            "#,
+        )
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+use crate::mailer::{Email, Mailer};
+
+/// Creates the email that lets a user reset their password with the provided token
+pub fn password_reset_email(mailer: &Mailer, to: &str, token: &str) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: String::from("Reset your Tallii password"),
+        body: format!(
+            "Reset the password of your Tallii account by following the link below:\n\n{}/reset-password?token={}\n\nThe link expires in an hour. If you did not ask to reset your password you can ignore this email.",
+            mailer.app_url, token
+        ),
+    }
+}
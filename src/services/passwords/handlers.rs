@@ -0,0 +1,168 @@
+use actix_web::{rt, web, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::crypto::Crypto;
+use crate::errors::TalliiError;
+use crate::mailer::Mailer;
+use crate::services::auth::AuthenticatedUser;
+use crate::services::sessions::db::SessionsTable;
+use crate::services::tokens::db::AccessTokensTable;
+use crate::services::users::db::UsersTable;
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::PasswordResetTokensTable;
+use super::emails::password_reset_email;
+use super::models::{
+    ChangePasswordRequest, ForgotPasswordRequest, ResetPasswordRequest, MIN_PASSWORD_LENGTH,
+    RESET_INTERVAL_SECONDS,
+};
+
+/// Checks that a new password is long enough
+fn validate_password(password: &str) -> Result<(), TalliiError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(TalliiError::BAD_REQUEST.message(format!(
+            "The password must be at least {} characters long.",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+/// Issues a password reset token and emails it if there is a user with the provided email
+async fn send_password_reset(
+    pool: &PgPool,
+    crypto: &Crypto,
+    mailer: &Mailer,
+    email: String,
+) -> Result<(), TalliiError> {
+    if let Some(user) = UsersTable::get_by_email(pool, &email).await? {
+        // quietly skip sending when an email was just sent to the user
+        let sent_recently = PasswordResetTokensTable::get_last_created_at(pool, &user.user_id)
+            .await?
+            .map_or(false, |created_at| {
+                Utc::now().naive_utc() - created_at < Duration::seconds(RESET_INTERVAL_SECONDS)
+            });
+
+        if !sent_recently {
+            let token = PasswordResetTokensTable::issue(pool, crypto, &user.user_id).await?;
+
+            mailer
+                .send(password_reset_email(mailer, &user.email, &token))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends a password reset email if there is a user with the provided email. Always succeeds so
+/// that it does not reveal which emails have an account.
+pub async fn forgot_password(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    mailer: web::Data<Mailer>,
+    request: web::Json<ForgotPasswordRequest>,
+) -> TalliiResponse {
+    // the email is sent off the request path, so the response takes the same time and has the
+    // same outcome whether or not there is an account for the email
+    let email = request.into_inner().email;
+
+    rt::spawn(async move {
+        if let Err(err) = send_password_reset(&pool, &crypto, &mailer, email).await {
+            error!("Failed to send password reset email: {:?}", err);
+        }
+    });
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("SENT_PASSWORD_RESET"),
+        message: String::from(
+            "If an account exists for the provided email a password reset email was sent.",
+        ),
+    }))
+}
+
+/// Resets the password of the user the provided token was sent to and logs out all of their
+/// sessions
+pub async fn reset_password(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    request: web::Json<ResetPasswordRequest>,
+) -> TalliiResponse {
+    validate_password(&request.password)?;
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let token =
+        PasswordResetTokensTable::get_valid_for_update(&mut tx, &crypto.hash_token(&request.token))
+            .await?
+            .ok_or_else(|| TalliiError::INVALID_TOKEN.default())?;
+
+    // every outstanding token of the user is spent once one of them is used
+    PasswordResetTokensTable::use_all_for_user(&mut tx, &token.user_id).await?;
+
+    let hashed_password = crypto.hash_password(&request.password).await?;
+
+    UsersTable::update_password(&mut tx, &token.user_id, &hashed_password).await?;
+
+    SessionsTable::revoke_all_for_user(&mut tx, &token.user_id).await?;
+
+    // a reset usually means the account was at risk, so the access tokens stop working too
+    AccessTokensTable::revoke_all_for_user(&mut tx, &token.user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("RESET_PASSWORD"),
+        message: String::from("The password was reset."),
+    }))
+}
+
+/// Changes my password and logs out all of my other sessions
+pub async fn change_password(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    user: AuthenticatedUser,
+    request: web::Json<ChangePasswordRequest>,
+) -> TalliiResponse {
+    let me = UsersTable::get_by_id(&pool, &user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // verify the current password
+    if !crypto
+        .verify_password(&request.current_password, &me.password)
+        .await?
+    {
+        return Err(TalliiError::INVALID_LOGIN
+            .message(String::from("The provided current password is incorrect.")));
+    }
+
+    validate_password(&request.new_password)?;
+
+    let hashed_password = crypto.hash_password(&request.new_password).await?;
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    UsersTable::update_password(&mut tx, &user.user_id, &hashed_password).await?;
+
+    SessionsTable::revoke_others_for_user(&mut tx, &user.user_id, &user.session_id).await?;
+
+    // the access tokens were created with the old password, so they stop working like the sessions
+    AccessTokensTable::revoke_all_for_user(&mut tx, &user.user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("CHANGED_PASSWORD"),
+        message: String::from(
+            "The password was changed and all other sessions and access tokens were revoked.",
+        ),
+    }))
+}
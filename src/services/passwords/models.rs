@@ -0,0 +1,46 @@
+use serde::Deserialize;
+
+/// Minutes a password reset token is valid for
+pub const RESET_TOKEN_MINUTES: i64 = 60;
+
+/// Seconds a user has to wait before another password reset email is sent
+pub const RESET_INTERVAL_SECONDS: i64 = 60;
+
+/// Minimum length of a new password
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Database representation of a PasswordResetToken
+#[derive(sqlx::FromRow, Debug)]
+pub struct PasswordResetToken {
+    pub token_hash: String,
+    pub user_id: i32,
+    pub expires_at: chrono::NaiveDateTime,
+    pub used_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// The time the last password reset token of a user was created
+#[derive(sqlx::FromRow, Debug)]
+pub struct LastPasswordResetToken {
+    pub last_created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Representation of the forgot password request
+#[derive(Deserialize, Debug)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+/// Representation of the reset password request
+#[derive(Deserialize, Debug)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+}
+
+/// Representation of the change password request
+#[derive(Deserialize, Debug)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
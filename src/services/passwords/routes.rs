@@ -0,0 +1,18 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for requesting a password reset email
+pub fn password_forgot_routes() -> Resource {
+    web::resource("/password/forgot").route(web::post().to(handlers::forgot_password))
+}
+
+/// Resource routes for resetting a password
+pub fn password_reset_routes() -> Resource {
+    web::resource("/password/reset").route(web::post().to(handlers::reset_password))
+}
+
+/// Resource routes for changing the password of the currently logged in user
+pub fn me_password_routes() -> Resource {
+    web::resource("/me/password").route(web::put().to(handlers::change_password))
+}
@@ -0,0 +1,252 @@
+use sqlx::PgPool;
+
+use crate::errors::TalliiError;
+
+use super::models::{EventSearchRow, SearchCursor, UserSearchRow};
+
+pub struct SearchTable;
+
+impl SearchTable {
+    /// Searches the usernames and bios of the users, leaving out deleted users and users blocked
+    /// by or blocking the user. Fetches one more result than the limit.
+    pub async fn search_users(
+        pool: &PgPool,
+        user_id: &i32,
+        q: &str,
+        like_pattern: &str,
+        cursor: Option<SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<UserSearchRow>, TalliiError> {
+        let users = sqlx::query_as::<_, UserSearchRow>(
+            r#"
+                with search as (
+                    select
+                        f_unaccent(lower($1)) as q,
+                        f_unaccent(lower($2)) as pattern
+                ),
+                matches as (
+                    select
+                        u.user_id,
+                        u.emoji,
+                        u.bg_color,
+                        u.username,
+                        u.bio,
+                        f_unaccent(lower(u.username)) like s.pattern || '%' as prefix,
+                        exists (
+                            select
+                                1
+                            from
+                                friends f
+                            where
+                                f.user_id = $3
+                            and
+                                f.friend_user_id = u.user_id
+                            and
+                                f.state = 'active'
+                        ) as followed,
+                        greatest(
+                            similarity(f_unaccent(lower(u.username)), s.q),
+                            coalesce(word_similarity(s.q, f_unaccent(lower(u.bio))), 0)
+                        ) as similarity
+                    from
+                        users u
+                    cross join
+                        search s
+                    where
+                        (
+                            f_unaccent(lower(u.username)) like '%' || s.pattern || '%'
+                            or f_unaccent(lower(u.username)) % s.q
+                            or f_unaccent(lower(u.bio)) like '%' || s.pattern || '%'
+                            or s.q <% f_unaccent(lower(u.bio))
+                        )
+                    and
+                        u.deleted_at is null
+                    and not exists (
+                        select
+                            1
+                        from
+                            friends b
+                        where
+                            b.state = 'blocked'
+                        and (
+                            (b.user_id = $3 and b.friend_user_id = u.user_id)
+                            or (b.user_id = u.user_id and b.friend_user_id = $3)
+                        )
+                    )
+                )
+                select
+                    *
+                from
+                    matches
+                where
+                    $4::boolean is null
+                    or (prefix, followed, similarity, user_id) < ($4, $5, $6, $7)
+                order by
+                    prefix desc,
+                    followed desc,
+                    similarity desc,
+                    user_id desc
+                limit $8
+            "#,
+        )
+        .bind(q)
+        .bind(like_pattern)
+        .bind(user_id)
+        .bind(cursor.map(|c| c.prefix))
+        .bind(cursor.map(|c| c.followed))
+        .bind(cursor.map(|c| c.similarity))
+        .bind(cursor.map(|c| c.id))
+        .bind(limit + 1)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    /// Searches the names and descriptions of the events the user can see, which are the events
+    /// the user is in and the events with an active member whose events the user can view.
+    /// Events the user or people the user follows are active members of are ranked as followed.
+    /// Fetches one more result than the limit.
+    pub async fn search_events(
+        pool: &PgPool,
+        user_id: &i32,
+        q: &str,
+        like_pattern: &str,
+        cursor: Option<SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<EventSearchRow>, TalliiError> {
+        let events = sqlx::query_as::<_, EventSearchRow>(
+            r#"
+                with search as (
+                    select
+                        f_unaccent(lower($1)) as q,
+                        f_unaccent(lower($2)) as pattern
+                ),
+                matches as (
+                    select
+                        e.event_id,
+                        e.name,
+                        e.description,
+                        u.user_id,
+                        u.emoji,
+                        u.bg_color,
+                        u.username,
+                        u.bio,
+                        e.finalized_at,
+                        e.created_at,
+                        f_unaccent(lower(e.name)) like s.pattern || '%' as prefix,
+                        exists (
+                            select
+                                1
+                            from
+                                events_members em
+                            left join
+                                friends f
+                            on
+                                f.user_id = $3
+                            and
+                                f.friend_user_id = em.user_id
+                            and
+                                f.state = 'active'
+                            where
+                                em.event_id = e.event_id
+                            and
+                                em.state = 'active'
+                            and
+                                (em.user_id = $3 or f.user_id is not null)
+                        ) as followed,
+                        greatest(
+                            similarity(f_unaccent(lower(e.name)), s.q),
+                            coalesce(word_similarity(s.q, f_unaccent(lower(e.description))), 0)
+                        ) as similarity
+                    from
+                        events e
+                    inner join
+                        users u
+                    on
+                        e.creator_user_id = u.user_id
+                    cross join
+                        search s
+                    where
+                        (
+                            f_unaccent(lower(e.name)) like '%' || s.pattern || '%'
+                            or f_unaccent(lower(e.name)) % s.q
+                            or f_unaccent(lower(e.description)) like '%' || s.pattern || '%'
+                            or s.q <% f_unaccent(lower(e.description))
+                        )
+                    and exists (
+                        select
+                            1
+                        from
+                            events_members em
+                        inner join
+                            users mu
+                        on
+                            mu.user_id = em.user_id
+                        where
+                            em.event_id = e.event_id
+                        and (
+                            em.user_id = $3
+                            or (
+                                em.state = 'active'
+                                and mu.deleted_at is null
+                                and (
+                                    mu.private = false
+                                    or exists (
+                                        select
+                                            1
+                                        from
+                                            friends f
+                                        where
+                                            f.user_id = $3
+                                        and
+                                            f.friend_user_id = mu.user_id
+                                        and
+                                            f.state = 'active'
+                                    )
+                                )
+                                and not exists (
+                                    select
+                                        1
+                                    from
+                                        friends b
+                                    where
+                                        b.state = 'blocked'
+                                    and (
+                                        (b.user_id = $3 and b.friend_user_id = mu.user_id)
+                                        or (b.user_id = mu.user_id and b.friend_user_id = $3)
+                                    )
+                                )
+                            )
+                        )
+                    )
+                )
+                select
+                    *
+                from
+                    matches
+                where
+                    $4::boolean is null
+                    or (prefix, followed, similarity, event_id) < ($4, $5, $6, $7)
+                order by
+                    prefix desc,
+                    followed desc,
+                    similarity desc,
+                    event_id desc
+                limit $8
+            "#,
+        )
+        .bind(q)
+        .bind(like_pattern)
+        .bind(user_id)
+        .bind(cursor.map(|c| c.prefix))
+        .bind(cursor.map(|c| c.followed))
+        .bind(cursor.map(|c| c.similarity))
+        .bind(cursor.map(|c| c.id))
+        .bind(limit + 1)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(events)
+    }
+}
@@ -0,0 +1,100 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+use crate::services::auth::{ApiUser, AuthenticatedUser};
+use crate::services::events::models::EventResponse;
+use crate::services::tokens::models::EVENTS_READ;
+use crate::services::users::models::PublicUser;
+use crate::services::TalliiResponse;
+
+use super::db::SearchTable;
+use super::models::{SearchCursor, SearchPage, SearchQuery};
+
+/// Searches users by username and bio, ranking prefix matches first, then users I follow and
+/// then by similarity
+pub async fn search_users(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    params: web::Query<SearchQuery>,
+) -> TalliiResponse {
+    let limit = params.limit();
+
+    let rows = SearchTable::search_users(
+        &pool,
+        &user.user_id,
+        params.text()?,
+        &params.like_pattern()?,
+        params.cursor()?,
+        limit,
+    )
+    .await?;
+
+    let page = SearchPage::new(
+        rows,
+        limit,
+        |row| SearchCursor {
+            prefix: row.prefix,
+            followed: row.followed,
+            similarity: row.similarity,
+            id: row.user_id,
+        },
+        |row| PublicUser {
+            user_id: row.user_id,
+            emoji: row.emoji,
+            bg_color: row.bg_color,
+            username: row.username,
+            bio: row.bio,
+        },
+    );
+
+    Ok(HttpResponse::Ok().json(page))
+}
+
+/// Searches the events I can see by name and description, ranking prefix matches first, then
+/// events I or the people I follow are in and then by similarity
+pub async fn search_events(
+    pool: web::Data<PgPool>,
+    user: ApiUser,
+    params: web::Query<SearchQuery>,
+) -> TalliiResponse {
+    user.require_scope(EVENTS_READ)?;
+
+    let limit = params.limit();
+
+    let rows = SearchTable::search_events(
+        &pool,
+        &user.user_id,
+        params.text()?,
+        &params.like_pattern()?,
+        params.cursor()?,
+        limit,
+    )
+    .await?;
+
+    let page = SearchPage::new(
+        rows,
+        limit,
+        |row| SearchCursor {
+            prefix: row.prefix,
+            followed: row.followed,
+            similarity: row.similarity,
+            id: row.event_id,
+        },
+        |row| EventResponse {
+            event_id: row.event_id,
+            name: row.name,
+            description: row.description,
+            creator: PublicUser {
+                user_id: row.user_id,
+                emoji: row.emoji,
+                bg_color: row.bg_color,
+                username: row.username,
+                bio: row.bio,
+            },
+            finalized_at: row.finalized_at,
+            created_at: row.created_at,
+        },
+    );
+
+    Ok(HttpResponse::Ok().json(page))
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::TalliiError;
+
+/// Amount of results on a page when no limit is provided
+pub const DEFAULT_SEARCH_LIMIT: i64 = 10;
+
+/// Max amount of results on a page
+pub const MAX_SEARCH_LIMIT: i64 = 50;
+
+/// Max length of a search query
+pub const MAX_SEARCH_QUERY_LENGTH: usize = 100;
+
+/// Query params of a search
+#[derive(Deserialize, Debug)]
+pub struct SearchQuery {
+    pub q: String,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl SearchQuery {
+    /// The trimmed query, fails when it is empty or too long
+    pub fn text(&self) -> Result<&str, TalliiError> {
+        let q = self.q.trim();
+
+        if q.is_empty() || q.chars().count() > MAX_SEARCH_QUERY_LENGTH {
+            return Err(TalliiError::BAD_REQUEST.message(format!(
+                "The search query must be between 1 and {} characters.",
+                MAX_SEARCH_QUERY_LENGTH
+            )));
+        }
+
+        Ok(q)
+    }
+
+    /// The query with the like wildcards escaped, so it can be used in a like pattern
+    pub fn like_pattern(&self) -> Result<String, TalliiError> {
+        Ok(self
+            .text()?
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_"))
+    }
+
+    /// The amount of results on the page, between 1 and MAX_SEARCH_LIMIT
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT)
+    }
+
+    /// The cursor of the page, the first page has none
+    pub fn cursor(&self) -> Result<Option<SearchCursor>, TalliiError> {
+        self.cursor.as_deref().map(SearchCursor::decode).transpose()
+    }
+}
+
+/// Position of a result in the ranking, the next page starts after it. Results are ranked by
+/// prefix matches, then by whether they involve people the user follows, then by similarity and
+/// last by id.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchCursor {
+    pub prefix: bool,
+    pub followed: bool,
+    pub similarity: f32,
+    pub id: i32,
+}
+
+impl SearchCursor {
+    /// Encodes the cursor into an opaque string
+    pub fn encode(&self) -> String {
+        let cursor = format!(
+            "{}:{}:{}:{}",
+            self.prefix as u8, self.followed as u8, self.similarity, self.id
+        );
+
+        base64::encode_config(cursor.as_bytes(), base64::URL_SAFE_NO_PAD)
+    }
+
+    /// Decodes a cursor made by encode
+    pub fn decode(cursor: &str) -> Result<Self, TalliiError> {
+        let invalid = || TalliiError::BAD_REQUEST.message(String::from("The cursor is invalid."));
+
+        let decoded = base64::decode_config(cursor, base64::URL_SAFE_NO_PAD)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+
+        let parts: Vec<&str> = decoded.split(':').collect();
+
+        match parts.as_slice() {
+            [prefix, followed, similarity, id] => Ok(SearchCursor {
+                prefix: *prefix == "1",
+                followed: *followed == "1",
+                similarity: similarity.parse().map_err(|_err| invalid())?,
+                id: id.parse().map_err(|_err| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// A page of search results
+#[derive(Serialize, Debug)]
+pub struct SearchPage<T: Serialize> {
+    pub results: Vec<T>,
+    /// Cursor of the next page, there are no more results when it is missing
+    pub next_cursor: Option<String>,
+}
+
+impl<T: Serialize> SearchPage<T> {
+    /// Builds the page out of one result more than the limit, the extra result only tells that
+    /// there is a next page
+    pub fn new<R>(
+        mut rows: Vec<R>,
+        limit: i64,
+        cursor: impl Fn(&R) -> SearchCursor,
+        result: impl Fn(R) -> T,
+    ) -> Self {
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            rows.last().map(|row| cursor(row).encode())
+        } else {
+            None
+        };
+
+        SearchPage {
+            results: rows.into_iter().map(result).collect(),
+            next_cursor,
+        }
+    }
+}
+
+/// A user matching a search along with its ranking
+#[derive(sqlx::FromRow, Debug)]
+pub struct UserSearchRow {
+    pub user_id: i32,
+    pub emoji: String,
+    pub bg_color: String,
+    pub username: String,
+    pub bio: Option<String>,
+    pub prefix: bool,
+    pub followed: bool,
+    pub similarity: f32,
+}
+
+/// An event matching a search along with its creator and ranking
+#[derive(sqlx::FromRow, Debug)]
+pub struct EventSearchRow {
+    pub event_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub user_id: i32,
+    pub emoji: String,
+    pub bg_color: String,
+    pub username: String,
+    pub bio: Option<String>,
+    pub finalized_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub prefix: bool,
+    pub followed: bool,
+    pub similarity: f32,
+}
@@ -0,0 +1,13 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for user search
+pub fn search_users_routes() -> Resource {
+    web::resource("/search/users").route(web::get().to(handlers::search_users))
+}
+
+/// Resource routes for event search
+pub fn search_events_routes() -> Resource {
+    web::resource("/search/events").route(web::get().to(handlers::search_events))
+}
@@ -0,0 +1,341 @@
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Transaction};
+
+use crate::crypto::{Crypto, REFRESH_TOKEN_DAYS};
+use crate::errors::TalliiError;
+
+use super::models::{ActiveSession, NewSession, RefreshTokenRow, Session};
+
+pub struct SessionsTable;
+
+impl SessionsTable {
+    /// Starts a new session for the user and returns it with its first refresh token
+    pub async fn start(
+        pool: &PgPool,
+        crypto: &Crypto,
+        user_id: &i32,
+        new_session: &NewSession,
+    ) -> Result<(Session, String), TalliiError> {
+        // start the transaction
+        let mut tx = pool.begin().await?;
+
+        let session = SessionsTable::create(&mut tx, user_id, new_session).await?;
+
+        let refresh_token = crypto.generate_opaque_token();
+
+        RefreshTokensTable::create(
+            &mut tx,
+            &session.session_id,
+            &crypto.hash_token(&refresh_token),
+        )
+        .await?;
+
+        // commit the transaction
+        tx.commit().await?;
+
+        Ok((session, refresh_token))
+    }
+
+    /// Creates a session for the user on the provided device
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        new_session: &NewSession,
+    ) -> Result<Session, TalliiError> {
+        let session = sqlx::query_as::<_, Session>(
+            r#"
+                insert into
+                    sessions (user_id, device_name, user_agent, ip_address)
+                values
+                    ($1, $2, $3, $4)
+                returning
+                    session_id,
+                    user_id,
+                    device_name,
+                    user_agent,
+                    ip_address,
+                    last_used_at,
+                    revoked_at,
+                    created_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(&new_session.device_name)
+        .bind(&new_session.user_agent)
+        .bind(&new_session.ip_address)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// Gets the session of the user if it has not been revoked
+    pub async fn get_active(
+        pool: &PgPool,
+        session_id: &i32,
+        user_id: &i32,
+    ) -> Result<Option<ActiveSession>, TalliiError> {
+        let session = sqlx::query_as::<_, ActiveSession>(
+            r#"
+                select
+                    session_id,
+                    last_used_at
+                from
+                    sessions
+                where
+                    session_id = $1
+                and
+                    user_id = $2
+                and
+                    revoked_at is null
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// Gets all sessions of the user that have not been revoked
+    pub async fn get_active_for_user(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<Session>, TalliiError> {
+        let sessions = sqlx::query_as::<_, Session>(
+            r#"
+                select
+                    session_id,
+                    user_id,
+                    device_name,
+                    user_agent,
+                    ip_address,
+                    last_used_at,
+                    revoked_at,
+                    created_at
+                from
+                    sessions
+                where
+                    user_id = $1
+                and
+                    revoked_at is null
+                order by
+                    last_used_at desc
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// Records that the session was just used
+    pub async fn touch(pool: &PgPool, session_id: &i32) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    sessions
+                set
+                    last_used_at = current_timestamp
+                where
+                    session_id = $1
+            "#,
+        )
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes a single session of the user. Returns false if the user has no such session.
+    pub async fn revoke_for_user(
+        pool: &PgPool,
+        session_id: &i32,
+        user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                update
+                    sessions
+                set
+                    revoked_at = current_timestamp
+                where
+                    session_id = $1
+                and
+                    user_id = $2
+                and
+                    revoked_at is null
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Revokes a single session
+    pub async fn revoke(pool: &PgPool, session_id: &i32) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    sessions
+                set
+                    revoked_at = current_timestamp
+                where
+                    session_id = $1
+                and
+                    revoked_at is null
+            "#,
+        )
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes every session of the user
+    pub async fn revoke_all_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    sessions
+                set
+                    revoked_at = current_timestamp
+                where
+                    user_id = $1
+                and
+                    revoked_at is null
+            "#,
+        )
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes every session of the user except the provided one
+    pub async fn revoke_others_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        session_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    sessions
+                set
+                    revoked_at = current_timestamp
+                where
+                    user_id = $1
+                and
+                    session_id != $2
+                and
+                    revoked_at is null
+            "#,
+        )
+        .bind(user_id)
+        .bind(session_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub struct RefreshTokensTable;
+
+impl RefreshTokensTable {
+    /// Creates a refresh token for the session
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        session_id: &i32,
+        token_hash: &str,
+    ) -> Result<(), TalliiError> {
+        let expires_at = (Utc::now() + Duration::days(REFRESH_TOKEN_DAYS)).naive_utc();
+
+        sqlx::query(
+            r#"
+                insert into
+                    sessions_refresh_tokens (token_hash, session_id, expires_at)
+                values
+                    ($1, $2, $3)
+            "#,
+        )
+        .bind(token_hash)
+        .bind(session_id)
+        .bind(expires_at)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets a refresh token by its hash and locks it until the transaction finishes
+    pub async fn get_by_hash_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<RefreshTokenRow>, TalliiError> {
+        let token = sqlx::query_as::<_, RefreshTokenRow>(
+            r#"
+                select
+                    rt.token_hash,
+                    rt.session_id,
+                    s.user_id,
+                    u.username,
+                    rt.expires_at,
+                    rt.rotated_at,
+                    s.revoked_at
+                from
+                    sessions_refresh_tokens rt
+                inner join
+                    sessions s
+                on
+                    rt.session_id = s.session_id
+                inner join
+                    users u
+                on
+                    s.user_id = u.user_id
+                where
+                    rt.token_hash = $1
+                for update of rt
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Marks a refresh token as exchanged so it can never be used again
+    pub async fn rotate(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        token_hash: &str,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    sessions_refresh_tokens
+                set
+                    rotated_at = current_timestamp
+                where
+                    token_hash = $1
+            "#,
+        )
+        .bind(token_hash)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::crypto::{Crypto, TokenResponse};
+use crate::errors::TalliiError;
+use crate::services::auth::AuthenticatedUser;
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::{RefreshTokensTable, SessionsTable};
+use super::models::{RefreshTokenRequest, SessionResponse};
+
+/// Exchanges a refresh token for a new access token and refresh token
+pub async fn refresh_token(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    request: web::Json<RefreshTokenRequest>,
+) -> TalliiResponse {
+    let token_hash = crypto.hash_token(&request.refresh_token);
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let token = RefreshTokensTable::get_by_hash_for_update(&mut tx, &token_hash)
+        .await?
+        .ok_or_else(|| TalliiError::UNAUTHORIZED.default())?;
+
+    // a refresh token that was already exchanged has been stolen or replayed,
+    // so the whole session is revoked
+    if token.rotated_at.is_some() {
+        tx.rollback().await?;
+
+        warn!(
+            "refresh token reused, revoking session {} of user {}",
+            token.session_id, token.user_id
+        );
+
+        SessionsTable::revoke(&pool, &token.session_id).await?;
+
+        return Err(TalliiError::UNAUTHORIZED.default());
+    }
+
+    if token.revoked_at.is_some() || token.expires_at <= Utc::now().naive_utc() {
+        return Err(TalliiError::UNAUTHORIZED.default());
+    }
+
+    // rotate the refresh token
+    RefreshTokensTable::rotate(&mut tx, &token.token_hash).await?;
+
+    let refresh_token = crypto.generate_opaque_token();
+
+    RefreshTokensTable::create(
+        &mut tx,
+        &token.session_id,
+        &crypto.hash_token(&refresh_token),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    // create a new jwt for the session
+    let token = crypto
+        .generate_jwt(token.user_id, token.username, token.session_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(TokenResponse {
+        token,
+        refresh_token,
+    }))
+}
+
+/// Revokes the session of me
+pub async fn logout(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    SessionsTable::revoke(&pool, &user.session_id).await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("LOGGED_OUT"),
+        message: String::from("The current session was logged out."),
+    }))
+}
+
+/// Revokes every session of me
+pub async fn logout_all(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    SessionsTable::revoke_all_for_user(&mut tx, &user.user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("LOGGED_OUT_ALL"),
+        message: String::from("All sessions were logged out."),
+    }))
+}
+
+/// Gets the sessions of me that have not been logged out
+pub async fn get_me_sessions(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    let sessions: Vec<SessionResponse> = SessionsTable::get_active_for_user(&pool, &user.user_id)
+        .await?
+        .into_iter()
+        .map(|session| SessionResponse {
+            current: session.session_id == user.session_id,
+            session_id: session.session_id,
+            device_name: session.device_name,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            last_used_at: session.last_used_at,
+            created_at: session.created_at,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+/// Logs out a single session of me
+pub async fn delete_me_session(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    session_id: web::Path<i32>,
+) -> TalliiResponse {
+    if !SessionsTable::revoke_for_user(&pool, &session_id, &user.user_id).await? {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("DELETED_SESSION"),
+        message: String::from("The provided session was logged out."),
+    }))
+}
@@ -0,0 +1,77 @@
+use actix_web::http::header::USER_AGENT;
+use actix_web::HttpRequest;
+use serde::{Deserialize, Serialize};
+
+/// Database representation of a Session
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct Session {
+    pub session_id: i32,
+    pub user_id: i32,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub last_used_at: chrono::NaiveDateTime,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Representation of the device a new Session is started on
+#[derive(Debug)]
+pub struct NewSession {
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl NewSession {
+    /// Reads the user agent and ip address of the device from the request. The ip address is the
+    /// one of the connection, forwarded headers are set by the client and cannot be trusted.
+    pub fn from_request(req: &HttpRequest, device_name: Option<String>) -> NewSession {
+        NewSession {
+            device_name,
+            user_agent: req
+                .headers()
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
+            ip_address: req.peer_addr().map(|addr| addr.ip().to_string()),
+        }
+    }
+}
+
+/// Session response payload
+#[derive(Serialize, Debug)]
+pub struct SessionResponse {
+    pub session_id: i32,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub current: bool,
+    pub last_used_at: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Refresh token row joined with its session and user
+#[derive(sqlx::FromRow, Debug)]
+pub struct RefreshTokenRow {
+    pub token_hash: String,
+    pub session_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub expires_at: chrono::NaiveDateTime,
+    pub rotated_at: Option<chrono::NaiveDateTime>,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Representation of a request to refresh an access token
+#[derive(Deserialize, Debug)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+/// Representation of a Session that has not been revoked
+#[derive(sqlx::FromRow, Debug)]
+pub struct ActiveSession {
+    pub session_id: i32,
+    pub last_used_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,28 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for refreshing an access token
+pub fn token_refresh_routes() -> Resource {
+    web::resource("/token/refresh").route(web::post().to(handlers::refresh_token))
+}
+
+/// Resource routes for logging out of the current session
+pub fn logout_routes() -> Resource {
+    web::resource("/logout").route(web::post().to(handlers::logout))
+}
+
+/// Resource routes for logging out of every session
+pub fn logout_all_routes() -> Resource {
+    web::resource("/logout-all").route(web::post().to(handlers::logout_all))
+}
+
+/// Resource routes for the sessions of the currently logged in user
+pub fn me_sessions_routes() -> Resource {
+    web::resource("/me/sessions").route(web::get().to(handlers::get_me_sessions))
+}
+
+/// Resource routes for a specific session of the currently logged in user
+pub fn me_sessions_entity_routes() -> Resource {
+    web::resource("/me/sessions/{session_id}").route(web::delete().to(handlers::delete_me_session))
+}
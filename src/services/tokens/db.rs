@@ -0,0 +1,211 @@
+use chrono::NaiveDateTime;
+use sqlx::{PgPool, Transaction};
+
+use crate::errors::TalliiError;
+
+use super::models::{AccessToken, AccessTokenCount};
+
+pub struct AccessTokensTable;
+
+impl AccessTokensTable {
+    /// Creates an access token for the user from the hash of the token
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        token_hash: &str,
+        name: &str,
+        scopes: &[String],
+        expires_at: Option<NaiveDateTime>,
+    ) -> Result<AccessToken, TalliiError> {
+        let access_token = sqlx::query_as::<_, AccessToken>(
+            r#"
+                insert into
+                    access_tokens (token_hash, user_id, name, scopes, expires_at)
+                values
+                    ($1, $2, $3, $4, $5)
+                returning
+                    token_id,
+                    user_id,
+                    name,
+                    scopes,
+                    expires_at,
+                    last_used_at,
+                    created_at
+            "#,
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(name)
+        .bind(scopes)
+        .bind(expires_at)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(access_token)
+    }
+
+    /// Counts the access tokens of the user that are not revoked and locks the user until the
+    /// transaction finishes so concurrent requests can not go over the limit
+    pub async fn count_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<i64, TalliiError> {
+        sqlx::query("select user_id from users where user_id = $1 for update")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let count = sqlx::query_as::<_, AccessTokenCount>(
+            r#"
+                select
+                    count(*)
+                from
+                    access_tokens
+                where
+                    user_id = $1
+                and
+                    revoked_at is null
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Ok(count.count)
+    }
+
+    /// Gets an access token by its hash if it is not revoked and has not expired
+    pub async fn get_active(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<Option<AccessToken>, TalliiError> {
+        let access_token = sqlx::query_as::<_, AccessToken>(
+            r#"
+                select
+                    token_id,
+                    user_id,
+                    name,
+                    scopes,
+                    expires_at,
+                    last_used_at,
+                    created_at
+                from
+                    access_tokens
+                where
+                    token_hash = $1
+                and
+                    revoked_at is null
+                and
+                    (expires_at is null or expires_at > current_timestamp)
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(access_token)
+    }
+
+    /// Gets the access tokens of the user that are not revoked, including the expired ones
+    pub async fn get_by_user_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<AccessToken>, TalliiError> {
+        let access_tokens = sqlx::query_as::<_, AccessToken>(
+            r#"
+                select
+                    token_id,
+                    user_id,
+                    name,
+                    scopes,
+                    expires_at,
+                    last_used_at,
+                    created_at
+                from
+                    access_tokens
+                where
+                    user_id = $1
+                and
+                    revoked_at is null
+                order by
+                    created_at desc
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(access_tokens)
+    }
+
+    /// Records that the access token was just used
+    pub async fn touch(pool: &PgPool, token_id: &i32) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    access_tokens
+                set
+                    last_used_at = current_timestamp
+                where
+                    token_id = $1
+            "#,
+        )
+        .bind(token_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes an access token of the user, returns false when the user has no such token
+    pub async fn revoke_for_user(
+        pool: &PgPool,
+        token_id: &i32,
+        user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                update
+                    access_tokens
+                set
+                    revoked_at = current_timestamp
+                where
+                    token_id = $1
+                and
+                    user_id = $2
+                and
+                    revoked_at is null
+            "#,
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Revokes every access token of the user
+    pub async fn revoke_all_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    access_tokens
+                set
+                    revoked_at = current_timestamp
+                where
+                    user_id = $1
+                and
+                    revoked_at is null
+            "#,
+        )
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::crypto::Crypto;
+use crate::errors::TalliiError;
+use crate::services::auth::AuthenticatedUser;
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::AccessTokensTable;
+use super::models::{
+    CreateAccessTokenRequest, CreatedAccessTokenResponse, ACCESS_TOKEN_PREFIX,
+    MAX_ACCESS_TOKENS_PER_USER, MAX_ACCESS_TOKEN_NAME_LENGTH, SCOPES,
+};
+
+/// Gets the access tokens of me that have not been revoked
+pub async fn get_me_tokens(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    let access_tokens = AccessTokensTable::get_by_user_id(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(access_tokens))
+}
+
+/// Creates an access token for me. Only a logged in session can do this, so a leaked access
+/// token can not be used to create more of them.
+pub async fn create_me_token(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    user: AuthenticatedUser,
+    request: web::Json<CreateAccessTokenRequest>,
+) -> TalliiResponse {
+    let name = request.name.trim();
+
+    if name.is_empty() || name.chars().count() > MAX_ACCESS_TOKEN_NAME_LENGTH {
+        return Err(TalliiError::BAD_REQUEST.message(format!(
+            "The name of a token has to be between 1 and {} characters.",
+            MAX_ACCESS_TOKEN_NAME_LENGTH
+        )));
+    }
+
+    if request.scopes.is_empty() {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("A token needs at least one scope."))
+        );
+    }
+
+    if let Some(scope) = request
+        .scopes
+        .iter()
+        .find(|scope| !SCOPES.contains(&scope.as_str()))
+    {
+        return Err(TalliiError::BAD_REQUEST.message(format!(
+            "The scope {} does not exist. The supported scopes are {}.",
+            scope,
+            SCOPES.join(", ")
+        )));
+    }
+
+    if let Some(expires_at) = request.expires_at {
+        if expires_at <= Utc::now().naive_utc() {
+            return Err(TalliiError::BAD_REQUEST.message(String::from(
+                "The expiry of a token has to be in the future.",
+            )));
+        }
+    }
+
+    // keep the scopes in the order they are listed in and drop the duplicates
+    let scopes: Vec<String> = SCOPES
+        .iter()
+        .filter(|scope| request.scopes.iter().any(|requested| requested == *scope))
+        .map(|scope| scope.to_string())
+        .collect();
+
+    let token = format!("{}{}", ACCESS_TOKEN_PREFIX, crypto.generate_opaque_token());
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    if AccessTokensTable::count_for_update(&mut tx, &user.user_id).await?
+        >= MAX_ACCESS_TOKENS_PER_USER
+    {
+        return Err(TalliiError::BAD_REQUEST.message(format!(
+            "You can not have more than {} tokens. Revoke one first.",
+            MAX_ACCESS_TOKENS_PER_USER
+        )));
+    }
+
+    let access_token = AccessTokensTable::create(
+        &mut tx,
+        &user.user_id,
+        &crypto.hash_token(&token),
+        name,
+        &scopes,
+        request.expires_at,
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(CreatedAccessTokenResponse {
+        access_token,
+        token,
+    }))
+}
+
+/// Revokes a single access token of me
+pub async fn delete_me_token(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    token_id: web::Path<i32>,
+) -> TalliiResponse {
+    if !AccessTokensTable::revoke_for_user(&pool, &token_id, &user.user_id).await? {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("REVOKED_ACCESS_TOKEN"),
+        message: String::from("The provided token was revoked."),
+    }))
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// Prefix of every access token so they can be told apart from session jwts
+pub const ACCESS_TOKEN_PREFIX: &str = "tallii_pat_";
+
+/// Max amount of access tokens a user can have that are not revoked
+pub const MAX_ACCESS_TOKENS_PER_USER: i64 = 25;
+
+/// Max length of the name of an access token
+pub const MAX_ACCESS_TOKEN_NAME_LENGTH: usize = 100;
+
+/// Scope to read events along with their teams and comments
+pub const EVENTS_READ: &str = "events:read";
+
+/// Scope to update the scores of teams
+pub const SCORES_WRITE: &str = "scores:write";
+
+/// Scope to create and delete comments
+pub const COMMENTS_WRITE: &str = "comments:write";
+
+/// Every scope an access token can be granted
+pub const SCOPES: [&str; 3] = [EVENTS_READ, SCORES_WRITE, COMMENTS_WRITE];
+
+/// Database representation of an Access Token, the hash is never selected
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct AccessToken {
+    pub token_id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Representation of a request to create an Access Token
+#[derive(Deserialize, Debug)]
+pub struct CreateAccessTokenRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Response of a created Access Token, the only time the token is shown
+#[derive(Serialize, Debug)]
+pub struct CreatedAccessTokenResponse {
+    #[serde(flatten)]
+    pub access_token: AccessToken,
+    pub token: String,
+}
+
+/// Count of the Access Tokens of a user
+#[derive(sqlx::FromRow, Debug)]
+pub struct AccessTokenCount {
+    pub count: i64,
+}
@@ -0,0 +1,15 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for the access tokens of the currently logged in user
+pub fn me_tokens_routes() -> Resource {
+    web::resource("/me/tokens")
+        .route(web::get().to(handlers::get_me_tokens))
+        .route(web::post().to(handlers::create_me_token))
+}
+
+/// Resource routes for a specific access token of the currently logged in user
+pub fn me_tokens_entity_routes() -> Resource {
+    web::resource("/me/tokens/{token_id}").route(web::delete().to(handlers::delete_me_token))
+}
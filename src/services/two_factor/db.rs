@@ -0,0 +1,334 @@
+use chrono::{Duration, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sqlx::{PgPool, Transaction};
+
+use crate::crypto::Crypto;
+use crate::errors::TalliiError;
+
+use super::models::{
+    LoginChallenge, TwoFactor, TwoFactorEnabled, CHALLENGE_MINUTES, RECOVERY_CODE_COUNT,
+    RECOVERY_CODE_LENGTH,
+};
+
+/// Generates a recovery code split in two halves so it is easier to type, i.e. abcde-12345
+fn generate_recovery_code() -> String {
+    let code: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(RECOVERY_CODE_LENGTH)
+        .map(char::from)
+        .collect::<String>()
+        .to_lowercase();
+
+    format!(
+        "{}-{}",
+        &code[..RECOVERY_CODE_LENGTH / 2],
+        &code[RECOVERY_CODE_LENGTH / 2..]
+    )
+}
+
+/// Removes the separator and casing a user may or may not type from a recovery code
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+pub struct TwoFactorTable;
+
+impl TwoFactorTable {
+    /// Stores a new secret for the user, replacing any secret that was never confirmed
+    pub async fn start(pool: &PgPool, user_id: &i32, secret: &str) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                insert into
+                    users_two_factor (user_id, secret)
+                values
+                    ($1, $2)
+                on conflict (user_id) do update set
+                    secret = excluded.secret,
+                    last_used_step = null,
+                    created_at = current_timestamp
+                where
+                    users_two_factor.enabled_at is null
+            "#,
+        )
+        .bind(user_id)
+        .bind(secret)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Checks if the user has 2fa on
+    pub async fn is_enabled(pool: &PgPool, user_id: &i32) -> Result<bool, TalliiError> {
+        let enabled = sqlx::query_as::<_, TwoFactorEnabled>(
+            r#"
+                select exists (
+                    select
+                        1
+                    from
+                        users_two_factor
+                    where
+                        user_id = $1
+                    and
+                        enabled_at is not null
+                )
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(enabled.exists)
+    }
+
+    /// Gets the 2fa of the user and locks it until the transaction finishes
+    pub async fn get_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<Option<TwoFactor>, TalliiError> {
+        let two_factor = sqlx::query_as::<_, TwoFactor>(
+            r#"
+                select
+                    user_id,
+                    secret,
+                    last_used_step,
+                    enabled_at,
+                    created_at
+                from
+                    users_two_factor
+                where
+                    user_id = $1
+                for update
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(two_factor)
+    }
+
+    /// Turns 2fa on for the user, the step of the confirming code can not be used again
+    pub async fn enable(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        step: i64,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    users_two_factor
+                set
+                    enabled_at = current_timestamp,
+                    last_used_step = $1
+                where
+                    user_id = $2
+            "#,
+        )
+        .bind(step)
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records the step of an accepted code so it can not be replayed
+    pub async fn use_step(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        step: i64,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                update
+                    users_two_factor
+                set
+                    last_used_step = $1
+                where
+                    user_id = $2
+                and
+                    (last_used_step is null or last_used_step < $1)
+            "#,
+        )
+        .bind(step)
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Turns 2fa off for the user and removes their recovery codes
+    pub async fn delete(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query("delete from users_recovery_codes where user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("delete from users_two_factor where user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub struct RecoveryCodesTable;
+
+impl RecoveryCodesTable {
+    /// Replaces the recovery codes of the user and returns the new codes to show to them once
+    pub async fn replace(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        crypto: &Crypto,
+        user_id: &i32,
+    ) -> Result<Vec<String>, TalliiError> {
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| generate_recovery_code())
+            .collect();
+
+        let code_hashes: Vec<String> = codes
+            .iter()
+            .map(|code| crypto.hash_token(&normalize_recovery_code(code)))
+            .collect();
+
+        sqlx::query("delete from users_recovery_codes where user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+                insert into
+                    users_recovery_codes (code_hash, user_id)
+                select
+                    code_hash, $2
+                from
+                    unnest($1::text[]) as code_hash
+            "#,
+        )
+        .bind(&code_hashes)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(codes)
+    }
+
+    /// Uses the recovery code of the user, returns false when it does not exist or was used
+    pub async fn redeem(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        crypto: &Crypto,
+        user_id: &i32,
+        code: &str,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                update
+                    users_recovery_codes
+                set
+                    used_at = current_timestamp
+                where
+                    code_hash = $1
+                and
+                    user_id = $2
+                and
+                    used_at is null
+            "#,
+        )
+        .bind(crypto.hash_token(&normalize_recovery_code(code)))
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+pub struct LoginChallengesTable;
+
+impl LoginChallengesTable {
+    /// Issues a challenge for the user that already provided their password and returns the token
+    pub async fn issue(
+        pool: &PgPool,
+        crypto: &Crypto,
+        user_id: &i32,
+        device_name: Option<String>,
+    ) -> Result<String, TalliiError> {
+        let token = crypto.generate_opaque_token();
+        let expires_at = (Utc::now() + Duration::minutes(CHALLENGE_MINUTES)).naive_utc();
+
+        sqlx::query(
+            r#"
+                insert into
+                    login_challenges (token_hash, user_id, device_name, expires_at)
+                values
+                    ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(crypto.hash_token(&token))
+        .bind(user_id)
+        .bind(device_name)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Gets an unused and unexpired challenge by its hash and locks it until the transaction
+    /// finishes
+    pub async fn get_valid_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<LoginChallenge>, TalliiError> {
+        let challenge = sqlx::query_as::<_, LoginChallenge>(
+            r#"
+                select
+                    token_hash,
+                    user_id,
+                    device_name,
+                    expires_at,
+                    used_at,
+                    created_at
+                from
+                    login_challenges
+                where
+                    token_hash = $1
+                and
+                    used_at is null
+                and
+                    expires_at > current_timestamp
+                for update
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(challenge)
+    }
+
+    /// Marks the challenge as used
+    pub async fn use_challenge(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        token_hash: &str,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            "update login_challenges set used_at = current_timestamp where token_hash = $1",
+        )
+        .bind(token_hash)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::crypto::{Crypto, TokenResponse};
+use crate::errors::TalliiError;
+use crate::limiter::LoginLimiter;
+use crate::oidc::OidcVerifier;
+use crate::services::auth::AuthenticatedUser;
+use crate::services::identities::reauthentication::reauthenticate;
+use crate::services::sessions::db::SessionsTable;
+use crate::services::sessions::models::NewSession;
+use crate::services::users::db::UsersTable;
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::{LoginChallengesTable, RecoveryCodesTable, TwoFactorTable};
+use super::models::{
+    ConfirmTwoFactorRequest, DisableTwoFactorRequest, LoginTwoFactorRequest, RecoveryCodesResponse,
+    TwoFactorEnrollment, TOTP_ISSUER,
+};
+use super::totp;
+
+/// Starts enrolling the user in 2fa, it is only turned on once a code is confirmed
+pub async fn enroll(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    if TwoFactorTable::is_enabled(&pool, &user.user_id).await? {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "Two factor authentication is already enabled.",
+        )));
+    }
+
+    let me = UsersTable::get_by_id(&pool, &user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    let secret = totp::generate_secret();
+
+    TwoFactorTable::start(&pool, &user.user_id, &totp::encode_secret(&secret)).await?;
+
+    Ok(HttpResponse::Ok().json(TwoFactorEnrollment {
+        secret: totp::encode_secret(&secret),
+        otpauth_uri: totp::otpauth_uri(TOTP_ISSUER, &me.email, &secret),
+    }))
+}
+
+/// Turns 2fa on once the user provides a valid code and responds with their recovery codes
+pub async fn confirm(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    user: AuthenticatedUser,
+    request: web::Json<ConfirmTwoFactorRequest>,
+) -> TalliiResponse {
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let two_factor = match TwoFactorTable::get_for_update(&mut tx, &user.user_id).await? {
+        Some(two_factor) if two_factor.enabled_at.is_none() => two_factor,
+        Some(_) => {
+            return Err(TalliiError::BAD_REQUEST.message(String::from(
+                "Two factor authentication is already enabled.",
+            )))
+        }
+        None => {
+            return Err(TalliiError::BAD_REQUEST.message(String::from(
+                "Two factor authentication has to be enrolled in first.",
+            )))
+        }
+    };
+
+    let secret = totp::decode_secret(&two_factor.secret)
+        .ok_or_else(|| TalliiError::INTERNAL_SERVER_ERROR.default())?;
+
+    let step = totp::verify(
+        &secret,
+        &request.code,
+        Utc::now().timestamp(),
+        two_factor.last_used_step,
+    )
+    .ok_or_else(|| TalliiError::INVALID_TWO_FACTOR_CODE.default())?;
+
+    TwoFactorTable::enable(&mut tx, &user.user_id, step).await?;
+
+    let recovery_codes = RecoveryCodesTable::replace(&mut tx, &crypto, &user.user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Turns 2fa off, the user has to provide their password and a current code
+pub async fn disable(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    verifier: web::Data<OidcVerifier>,
+    user: AuthenticatedUser,
+    request: web::Json<DisableTwoFactorRequest>,
+) -> TalliiResponse {
+    let me = UsersTable::get_by_id(&pool, &user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    reauthenticate(&pool, &crypto, &verifier, &me, &request.reauthentication).await?;
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let two_factor = TwoFactorTable::get_for_update(&mut tx, &user.user_id)
+        .await?
+        .filter(|two_factor| two_factor.enabled_at.is_some())
+        .ok_or_else(|| {
+            TalliiError::BAD_REQUEST
+                .message(String::from("Two factor authentication is not enabled."))
+        })?;
+
+    let secret = totp::decode_secret(&two_factor.secret)
+        .ok_or_else(|| TalliiError::INTERNAL_SERVER_ERROR.default())?;
+
+    // the code is spent like any other so it can not be replayed
+    let accepted = match totp::verify(
+        &secret,
+        &request.code,
+        Utc::now().timestamp(),
+        two_factor.last_used_step,
+    ) {
+        Some(step) => TwoFactorTable::use_step(&mut tx, &user.user_id, step).await?,
+        None => false,
+    };
+
+    if !accepted {
+        return Err(TalliiError::INVALID_TWO_FACTOR_CODE.default());
+    }
+
+    TwoFactorTable::delete(&mut tx, &user.user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("DISABLED_TWO_FACTOR"),
+        message: String::from("Two factor authentication was disabled."),
+    }))
+}
+
+/// Exchanges the challenge from login and a totp or recovery code for a session
+pub async fn login_two_factor(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    limiter: web::Data<LoginLimiter>,
+    request: web::Json<LoginTwoFactorRequest>,
+) -> TalliiResponse {
+    let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+    let token_hash = crypto.hash_token(&request.challenge_token);
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let challenge = LoginChallengesTable::get_valid_for_update(&mut tx, &token_hash)
+        .await?
+        .ok_or_else(|| TalliiError::INVALID_TOKEN.default())?;
+
+    let user = UsersTable::get_by_id(&pool, &challenge.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::INVALID_TOKEN.default())?;
+
+    // codes are guessed against the same lockouts as passwords
+    limiter.check(ip_address.as_deref(), &user.email).await?;
+
+    let two_factor = TwoFactorTable::get_for_update(&mut tx, &user.user_id)
+        .await?
+        .filter(|two_factor| two_factor.enabled_at.is_some())
+        .ok_or_else(|| TalliiError::INVALID_TOKEN.default())?;
+
+    let secret = totp::decode_secret(&two_factor.secret)
+        .ok_or_else(|| TalliiError::INTERNAL_SERVER_ERROR.default())?;
+
+    let accepted = match totp::verify(
+        &secret,
+        &request.code,
+        Utc::now().timestamp(),
+        two_factor.last_used_step,
+    ) {
+        Some(step) => TwoFactorTable::use_step(&mut tx, &user.user_id, step).await?,
+        None => RecoveryCodesTable::redeem(&mut tx, &crypto, &user.user_id, &request.code).await?,
+    };
+
+    if !accepted {
+        // roll back before recording the failure so the challenge can be retried
+        tx.rollback().await?;
+
+        limiter
+            .record_failure(ip_address.as_deref(), &user.email)
+            .await?;
+
+        return Err(TalliiError::INVALID_TWO_FACTOR_CODE.default());
+    }
+
+    LoginChallengesTable::use_challenge(&mut tx, &token_hash).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    limiter.record_success(&user.email).await?;
+
+    if user.is_suspended(Utc::now().naive_utc()) {
+        return Err(TalliiError::ACCOUNT_SUSPENDED.default());
+    }
+
+    // start a new session for the user on the device they logged in from
+    let new_session = NewSession::from_request(&req, challenge.device_name);
+    let (session, refresh_token) =
+        SessionsTable::start(&pool, &crypto, &user.user_id, &new_session).await?;
+
+    // create a new jwt for the newly authorized user
+    let token = crypto
+        .generate_jwt(user.user_id, user.username, session.session_id)
+        .await?;
+
+    // respond with the token
+    Ok(HttpResponse::Ok().json(TokenResponse {
+        token,
+        refresh_token,
+    }))
+}
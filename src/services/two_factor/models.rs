@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::services::identities::models::Reauthentication;
+
+/// Minutes a login challenge is valid for
+pub const CHALLENGE_MINUTES: i64 = 5;
+
+/// Amount of recovery codes a user gets when enabling 2fa
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Length of a recovery code, excluding the separator
+pub const RECOVERY_CODE_LENGTH: usize = 10;
+
+/// Issuer shown in authenticator apps
+pub const TOTP_ISSUER: &str = "Tallii";
+
+/// Database representation of the 2fa of a user
+#[derive(sqlx::FromRow, Debug)]
+pub struct TwoFactor {
+    pub user_id: i32,
+    pub secret: String,
+    pub last_used_step: Option<i64>,
+    pub enabled_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Database representation of a Login Challenge
+#[derive(sqlx::FromRow, Debug)]
+pub struct LoginChallenge {
+    pub token_hash: String,
+    pub user_id: i32,
+    pub device_name: Option<String>,
+    pub expires_at: chrono::NaiveDateTime,
+    pub used_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Represents 2fa being enabled for a user
+#[derive(sqlx::FromRow, Debug)]
+pub struct TwoFactorEnabled {
+    pub exists: bool,
+}
+
+/// The secret to add to an authenticator app, 2fa is on once a code is confirmed
+#[derive(Serialize, Debug)]
+pub struct TwoFactorEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// The recovery codes of a user, only shown once
+#[derive(Serialize, Debug)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Returned by login instead of a token when the user has 2fa on
+#[derive(Serialize, Debug)]
+pub struct TwoFactorChallengeResponse {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+}
+
+/// Representation of the confirm 2fa request
+#[derive(Deserialize, Debug)]
+pub struct ConfirmTwoFactorRequest {
+    pub code: String,
+}
+
+/// Representation of the disable 2fa request
+#[derive(Deserialize, Debug)]
+pub struct DisableTwoFactorRequest {
+    #[serde(flatten)]
+    pub reauthentication: Reauthentication,
+    pub code: String,
+}
+
+/// Representation of the 2fa login request, the code is a totp code or a recovery code
+#[derive(Deserialize, Debug)]
+pub struct LoginTwoFactorRequest {
+    pub challenge_token: String,
+    pub code: String,
+}
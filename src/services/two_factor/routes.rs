@@ -0,0 +1,20 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for finishing a login with a 2fa code
+pub fn login_two_factor_routes() -> Resource {
+    web::resource("/login/2fa").route(web::post().to(handlers::login_two_factor))
+}
+
+/// Resource routes for enrolling in and disabling 2fa for the currently logged in user
+pub fn me_two_factor_routes() -> Resource {
+    web::resource("/me/2fa")
+        .route(web::post().to(handlers::enroll))
+        .route(web::delete().to(handlers::disable))
+}
+
+/// Resource routes for confirming 2fa for the currently logged in user
+pub fn me_two_factor_confirm_routes() -> Resource {
+    web::resource("/me/2fa/confirm").route(web::post().to(handlers::confirm))
+}
@@ -0,0 +1,202 @@
+use hmac::{Hmac, Mac, NewMac};
+use rand::RngCore;
+use sha1::Sha1;
+
+/// Seconds a code is valid for
+pub const STEP_SECONDS: i64 = 30;
+
+/// Digits in a code
+pub const DIGITS: u32 = 6;
+
+/// Steps before and after the current one that are still accepted to allow for clock drift
+pub const ALLOWED_DRIFT_STEPS: i64 = 1;
+
+/// Length of a generated secret in bytes
+const SECRET_LENGTH: usize = 20;
+
+/// Generates a new random secret
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0_u8; SECRET_LENGTH];
+    rand::thread_rng().fill_bytes(&mut secret);
+
+    secret
+}
+
+/// Encodes the secret the way authenticator apps expect it
+pub fn encode_secret(secret: &[u8]) -> String {
+    base32::encode(base32::Alphabet::RFC4648 { padding: false }, secret)
+}
+
+/// Decodes a secret encoded with encode_secret
+pub fn decode_secret(encoded: &str) -> Option<Vec<u8>> {
+    base32::decode(base32::Alphabet::RFC4648 { padding: false }, encoded)
+}
+
+/// Creates the uri authenticator apps read from a qr code
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = percent_encode(issuer),
+        account = percent_encode(account),
+        secret = encode_secret(secret),
+        digits = DIGITS,
+        period = STEP_SECONDS
+    )
+}
+
+/// The step the unix timestamp falls in
+pub fn step_at(unix_time: i64) -> i64 {
+    unix_time.div_euclid(STEP_SECONDS)
+}
+
+/// Generates the code for the step
+pub fn code_at_step(secret: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_varkey(secret).expect("hmac accepts keys of any length");
+    mac.update(&(step as u64).to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        value % 10_u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// Checks the code against the steps around the unix timestamp and returns the step it matched.
+/// Steps at or before the last used step are rejected so a code can not be replayed. The time is
+/// passed in rather than read from the clock so the checks can be exercised for any moment.
+pub fn verify(
+    secret: &[u8],
+    code: &str,
+    unix_time: i64,
+    last_used_step: Option<i64>,
+) -> Option<i64> {
+    let code = code.trim();
+
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = step_at(unix_time);
+
+    (current - ALLOWED_DRIFT_STEPS..=current + ALLOWED_DRIFT_STEPS)
+        .filter(|step| last_used_step.map_or(true, |last| *step > last))
+        .find(|step| constant_time_eq(code_at_step(secret, *step).as_bytes(), code.as_bytes()))
+}
+
+/// Compares without returning early so the time taken does not reveal how much of a code matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Percent encodes the label parts of the otpauth uri
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The sha1 secret of the rfc 6238 test vectors
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    /// The 8 digit codes of the rfc 6238 sha1 test vectors, a 6 digit code is the last 6 digits
+    const RFC_VECTORS: &[(i64, &str)] = &[
+        (59, "94287082"),
+        (1_111_111_109, "07081804"),
+        (1_111_111_111, "14050471"),
+        (1_234_567_890, "89005924"),
+        (2_000_000_000, "69279037"),
+        (20_000_000_000, "65353130"),
+    ];
+
+    #[test]
+    fn code_at_step_matches_rfc_6238_vectors() {
+        for (unix_time, code) in RFC_VECTORS {
+            assert_eq!(
+                code_at_step(RFC_SECRET, step_at(*unix_time)),
+                code[code.len() - DIGITS as usize..],
+                "code at {}",
+                unix_time
+            );
+        }
+    }
+
+    #[test]
+    fn verify_accepts_rfc_6238_vectors() {
+        for (unix_time, code) in RFC_VECTORS {
+            let code = &code[code.len() - DIGITS as usize..];
+
+            assert_eq!(
+                verify(RFC_SECRET, code, *unix_time, None),
+                Some(step_at(*unix_time))
+            );
+        }
+    }
+
+    #[test]
+    fn verify_accepts_one_step_of_drift() {
+        let now = 1_234_567_890;
+        let current = step_at(now);
+
+        for step in &[current - 1, current, current + 1] {
+            let code = code_at_step(RFC_SECRET, *step);
+
+            assert_eq!(verify(RFC_SECRET, &code, now, None), Some(*step));
+        }
+    }
+
+    #[test]
+    fn verify_rejects_codes_outside_the_window() {
+        let now = 1_234_567_890;
+        let current = step_at(now);
+
+        for step in &[current - 2, current + 2] {
+            let code = code_at_step(RFC_SECRET, *step);
+
+            assert_eq!(verify(RFC_SECRET, &code, now, None), None);
+        }
+    }
+
+    #[test]
+    fn verify_rejects_replayed_steps() {
+        let now = 1_234_567_890;
+        let current = step_at(now);
+        let code = code_at_step(RFC_SECRET, current);
+
+        assert_eq!(verify(RFC_SECRET, &code, now, Some(current)), None);
+        assert_eq!(verify(RFC_SECRET, &code, now, Some(current + 1)), None);
+        assert_eq!(
+            verify(RFC_SECRET, &code, now, Some(current - 1)),
+            Some(current)
+        );
+    }
+
+    #[test]
+    fn verify_rejects_malformed_codes() {
+        let now = 1_234_567_890;
+
+        assert_eq!(verify(RFC_SECRET, "", now, None), None);
+        assert_eq!(verify(RFC_SECRET, "12345", now, None), None);
+        assert_eq!(verify(RFC_SECRET, "1234567", now, None), None);
+        assert_eq!(verify(RFC_SECRET, "abcdef", now, None), None);
+    }
+}
@@ -1,12 +1,14 @@
-use futures::future::try_join_all;
 use nanoid::generate;
 use rand::prelude::*;
-use sqlx::PgPool;
+use sqlx::postgres::PgDatabaseError;
+use sqlx::{PgPool, Transaction};
 
 use crate::crypto::Crypto;
 use crate::errors::TalliiError;
 
-use super::models::{InviteCode, NewUser, PublicUser, User, UserQuery};
+use super::models::{
+    InviteCode, InviteCodeCount, InviteCodeExists, MeUser, NewUser, PublicUser, User, UserVerified,
+};
 
 /// Emoji list for users
 #[allow(clippy::non_ascii_literal)]
@@ -23,6 +25,40 @@ const BACKGROUNDS: &[&str] = &[
     "#F3F4F6", "#FEE2E2", "#FEF3C7", "#D1FAE5", "#DBEAFE", "#E0E7FF", "#EDE9FE", "#FCE7F3",
 ];
 
+/// Emoji given to users that deleted their account
+#[allow(clippy::non_ascii_literal)]
+const DELETED_EMOJI: &str = "👻";
+
+/// Max length of the bio of a user
+pub const MAX_BIO_LENGTH: usize = 160;
+
+/// Max length of a username
+pub const MAX_USERNAME_LENGTH: usize = 100;
+
+/// Days a previous username keeps resolving to its user after a rename
+pub const USERNAME_GRACE_DAYS: i32 = 30;
+
+/// Amount of invite codes every verified user can share
+pub const INVITE_CODES_PER_USER: i64 = 5;
+
+/// Max amount of invite codes created in a single request
+pub const MAX_INVITE_CODES_PER_REQUEST: i32 = 1000;
+
+/// Maps a unique violation of the username to USERNAME_TAKEN, two requests can both find a
+/// username free and only the first one to insert it succeeds
+fn username_taken(err: sqlx::Error) -> TalliiError {
+    let taken = err
+        .as_database_error()
+        .and_then(|err| err.try_downcast_ref::<PgDatabaseError>())
+        .map_or(false, |err| err.constraint() == Some("users_username_key"));
+
+    if taken {
+        TalliiError::USERNAME_TAKEN.default()
+    } else {
+        TalliiError::from(err)
+    }
+}
+
 /// Represents the avatar of the user
 pub struct Avatar {
     background: &'static str,
@@ -42,59 +78,164 @@ impl Avatar {
 
         return Avatar { emoji, background };
     }
+
+    /// Checks if the emoji is one of the emojis users can pick from
+    pub fn is_valid_emoji(emoji: &str) -> bool {
+        !emoji.trim().is_empty() && EMOJIS.contains(&emoji)
+    }
+
+    /// Checks if the background is one of the colors users can pick from
+    pub fn is_valid_background(background: &str) -> bool {
+        BACKGROUNDS.contains(&background)
+    }
 }
 
 pub struct InviteCodesTable;
 
 impl InviteCodesTable {
-    /// Checks if the provided invite code is valid
+    /// Checks if the provided invite code exists, has uses left and has not expired
     pub async fn is_valid(pool: &PgPool, id: &String) -> Result<bool, TalliiError> {
-        // get the invite code, if it exists
-        let invite_code =
-            sqlx::query_as::<_, InviteCode>("select * from invite_codes where id = $1")
-                .bind(id)
-                .fetch_optional(pool)
-                .await?;
+        let exists = sqlx::query_as::<_, InviteCodeExists>(
+            r#"
+                select exists (
+                    select
+                        1
+                    from
+                        invite_codes
+                    where
+                        id = $1
+                    and
+                        uses < max_uses
+                    and
+                        (expires_at is null or expires_at > current_timestamp)
+                )
+            "#,
+        )
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
 
-        // if invite code doesnt exist return false
-        if invite_code.is_none() {
-            Ok(false)
-        } else {
-            Ok(true)
-        }
+        Ok(exists.exists)
     }
 
     /// Gets all invite codes
-    /// TODO: Hide this behind the citadel
     pub async fn get_all(pool: &PgPool) -> Result<Vec<InviteCode>, TalliiError> {
-        let all_invite_codes = sqlx::query_as::<_, InviteCode>("select * from invite_codes")
-            .fetch_all(pool)
-            .await?;
+        let all_invite_codes =
+            sqlx::query_as::<_, InviteCode>("select * from invite_codes order by created_at desc")
+                .fetch_all(pool)
+                .await?;
 
         Ok(all_invite_codes)
     }
 
-    /// Creates invite codes for the specified amount
-    /// Note, this query may not be the most performant query
-    /// because it is not a bulk insert but that is fine.
-    pub async fn create_many(pool: &PgPool, amount: i32) -> Result<(), TalliiError> {
-        let mut new_codes_queries = Vec::new();
+    /// Gets the invite codes in the quota of the user
+    pub async fn get_by_creator(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<InviteCode>, TalliiError> {
+        let invite_codes = sqlx::query_as::<_, InviteCode>(
+            "select * from invite_codes where created_by = $1 and quota order by created_at",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
 
+        Ok(invite_codes)
+    }
+
+    /// Creates invite codes for the specified amount in a single insert, only codes created with
+    /// quota count against the quota of their creator
+    pub async fn create_many(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        amount: i32,
+        created_by: Option<i32>,
+        max_uses: i32,
+        expires_at: Option<chrono::NaiveDateTime>,
+        quota: bool,
+    ) -> Result<Vec<InviteCode>, TalliiError> {
         // generate invite codes for the given amount
-        for _ in 0..amount {
-            let code = generate(6);
-
-            new_codes_queries.push(
-                sqlx::query("insert into invite_codes (id) values ($1)")
-                    .bind(code)
-                    .execute(pool),
-            )
-        }
+        let codes: Vec<String> = (0..amount).map(|_| generate(6)).collect();
+
+        // a clashing code is skipped rather than failing the whole batch
+        let invite_codes = sqlx::query_as::<_, InviteCode>(
+            r#"
+                insert into
+                    invite_codes (id, created_by, max_uses, expires_at, quota)
+                select
+                    code, $2, $3, $4, $5
+                from
+                    unnest($1::text[]) as code
+                on conflict do nothing
+                returning
+                    *
+            "#,
+        )
+        .bind(&codes)
+        .bind(created_by)
+        .bind(max_uses)
+        .bind(expires_at)
+        .bind(quota)
+        .fetch_all(tx)
+        .await?;
 
-        try_join_all(new_codes_queries).await?;
+        Ok(invite_codes)
+    }
+
+    /// Creates the invite codes the user has left in their quota
+    pub async fn fill_quota(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        // lock the user so concurrent requests can not mint the quota twice
+        sqlx::query("select user_id from users where user_id = $1 for update")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let created = sqlx::query_as::<_, InviteCodeCount>(
+            "select count(*) as count from invite_codes where created_by = $1 and quota",
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let remaining = INVITE_CODES_PER_USER - created.count;
+
+        if remaining > 0 {
+            InviteCodesTable::create_many(tx, remaining as i32, Some(*user_id), 1, None, true)
+                .await?;
+        }
 
         Ok(())
     }
+
+    /// Uses the invite code once if it is still valid, returning the code that was used
+    pub async fn redeem(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        id: &str,
+    ) -> Result<Option<InviteCode>, TalliiError> {
+        let invite_code = sqlx::query_as::<_, InviteCode>(
+            r#"
+                update
+                    invite_codes
+                set
+                    uses = uses + 1
+                where
+                    id = $1
+                and
+                    uses < max_uses
+                and
+                    (expires_at is null or expires_at > current_timestamp)
+                returning
+                    *
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(invite_code)
+    }
 }
 
 pub struct UsersTable;
@@ -110,70 +251,82 @@ impl UsersTable {
         Ok(user_with_email)
     }
 
-    /// Fetches a user with the provided username
+    /// Fetches a user with the provided username, previous usernames still resolve to their user
+    /// during the grace period after a rename
     pub async fn get_by_username(
         pool: &PgPool,
         username: &String,
     ) -> Result<Option<PublicUser>, TalliiError> {
         let user_with_username = sqlx::query_as::<_, PublicUser>(
-            "select user_id, emoji, bg_color, username, bio from users where username = $1",
+            r#"
+                select
+                    user_id,
+                    emoji,
+                    bg_color,
+                    username,
+                    bio
+                from (
+                    select
+                        u.user_id,
+                        u.emoji,
+                        u.bg_color,
+                        u.username,
+                        u.bio,
+                        0 as priority,
+                        u.created_at as matched_at
+                    from
+                        users u
+                    where
+                        u.username = $1
+                    union all
+                    select
+                        u.user_id,
+                        u.emoji,
+                        u.bg_color,
+                        u.username,
+                        u.bio,
+                        1 as priority,
+                        h.changed_at as matched_at
+                    from
+                        username_history h
+                    inner join
+                        users u
+                    on
+                        h.user_id = u.user_id
+                    where
+                        h.username = $1
+                    and
+                        h.changed_at > current_timestamp - make_interval(days => $2)
+                ) matches
+                order by
+                    priority,
+                    matched_at desc
+                limit 1
+            "#,
         )
         .bind(username)
+        .bind(USERNAME_GRACE_DAYS)
         .fetch_optional(pool)
         .await?;
 
         Ok(user_with_username)
     }
 
-    /// Fetches a username that matches the provided username and user_id.
-    pub async fn get_by_username_and_id(
-        pool: &PgPool,
-        user_id: &i32,
-        username: &String,
-    ) -> Result<Option<User>, TalliiError> {
-        let user_with_id_and_username =
-            sqlx::query_as::<_, User>("select * from users where user_id = $1 and username = $2")
-                .bind(user_id)
-                .bind(username)
-                .fetch_optional(pool)
-                .await?;
-
-        Ok(user_with_id_and_username)
-    }
-
-    /// Fetches a user that holds the provided invite code
-    pub async fn get_by_invite_code(
-        pool: &PgPool,
-        invite_code: &str,
-    ) -> Result<Option<PublicUser>, TalliiError> {
-        let user_with_invite_code =
-            sqlx::query_as::<_, PublicUser>("select * from users where invite_code = $1")
-                .bind(invite_code)
-                .fetch_optional(pool)
-                .await?;
-
-        Ok(user_with_invite_code)
-    }
-
-    /// Fetches a user with the provided username
-    pub async fn search_by_username(
-        pool: &PgPool,
-        params: &UserQuery,
-    ) -> Result<Vec<PublicUser>, TalliiError> {
-        let matching_users = sqlx::query_as::<_, PublicUser>(
-            "select user_id, emoji, bg_color, username, bio from users where username like $1 limit 10",
-        )
-        .bind(format!("%{}%", &params.q))
-        .fetch_all(pool)
-        .await?;
+    /// Fetches a user with the provided user_id
+    pub async fn get_by_id(pool: &PgPool, user_id: &i32) -> Result<Option<User>, TalliiError> {
+        let user_with_id = sqlx::query_as::<_, User>("select * from users where user_id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
 
-        Ok(matching_users)
+        Ok(user_with_id)
     }
 
     /// Creates a user
     pub async fn create(
-        pool: &PgPool,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
         new_user: &NewUser,
+        invited_by: Option<i32>,
         crypto: &Crypto,
     ) -> Result<PublicUser, TalliiError> {
         // hash the password
@@ -184,7 +337,7 @@ impl UsersTable {
 
         // create the user and return the public user
         let user = sqlx::query_as::<_, PublicUser>(
-            "insert into users (email, password, emoji, bg_color, invite_code, username) values ($1, $2, $3, $4, $5, $6) returning user_id, emoji, bg_color, email, username, bio, verified",
+            "insert into users (email, password, emoji, bg_color, invite_code, username, invited_by) values ($1, $2, $3, $4, $5, $6, $7) returning user_id, emoji, bg_color, email, username, bio, verified",
         )
             .bind(&new_user.email)
             .bind(hashed_password)
@@ -192,9 +345,290 @@ impl UsersTable {
             .bind(&avatar.background)
             .bind(&new_user.invite_code)
             .bind(&new_user.username)
-            .fetch_one(pool)
-            .await?;
+            .bind(invited_by)
+            .fetch_one(tx)
+            .await
+            .map_err(username_taken)?;
 
         Ok(user)
     }
+
+    /// Creates a user that logs in through an oidc provider rather than with a password
+    pub async fn create_without_password(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        email: &str,
+        username: &str,
+        invite_code: &str,
+        invited_by: Option<i32>,
+        verified: bool,
+    ) -> Result<PublicUser, TalliiError> {
+        // generate default emoji, bg_color combo
+        let avatar = Avatar::new();
+
+        // an empty password never matches so the user can only log in through the provider
+        let user = sqlx::query_as::<_, PublicUser>(
+            "insert into users (email, password, emoji, bg_color, invite_code, username, invited_by, verified) values ($1, '', $2, $3, $4, $5, $6, $7) returning user_id, emoji, bg_color, email, username, bio, verified",
+        )
+            .bind(email)
+            .bind(&avatar.emoji)
+            .bind(&avatar.background)
+            .bind(invite_code)
+            .bind(username)
+            .bind(invited_by)
+            .bind(verified)
+            .fetch_one(tx)
+            .await
+            .map_err(username_taken)?;
+
+        Ok(user)
+    }
+
+    /// Checks if the user has verified their email
+    pub async fn is_verified(pool: &PgPool, user_id: &i32) -> Result<bool, TalliiError> {
+        let verified = sqlx::query_as::<_, UserVerified>(
+            "select coalesce(verified, false) as verified from users where user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(verified.map_or(false, |user| user.verified))
+    }
+
+    /// Marks the email of the user as verified
+    pub async fn set_verified(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query("update users set verified = true where user_id = $1")
+            .bind(user_id)
+            .execute(tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Updates the password of the user to the provided hashed password
+    pub async fn update_password(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        hashed_password: &str,
+    ) -> Result<(), TalliiError> {
+        sqlx::query("update users set password = $1 where user_id = $2")
+            .bind(hashed_password)
+            .bind(user_id)
+            .execute(tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fetches the logged in user with the provided user_id
+    pub async fn get_me(pool: &PgPool, user_id: &i32) -> Result<Option<MeUser>, TalliiError> {
+        let me = sqlx::query_as::<_, MeUser>(
+            "select user_id, emoji, bg_color, email, username, verified, bio, private, discoverable from users where user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(me)
+    }
+
+    /// Updates the avatar, bio, privacy and discoverability of the user, the contact hash is only
+    /// kept while the user is discoverable
+    pub async fn update_profile(
+        pool: &PgPool,
+        user_id: &i32,
+        emoji: &str,
+        bg_color: &str,
+        bio: Option<&str>,
+        private: bool,
+        contact_hash: Option<&str>,
+    ) -> Result<MeUser, TalliiError> {
+        let me = sqlx::query_as::<_, MeUser>(
+            "update users set emoji = $1, bg_color = $2, bio = $3, private = $5, discoverable = $6 is not null, contact_hash = $6 where user_id = $4 returning user_id, emoji, bg_color, email, username, verified, bio, private, discoverable",
+        )
+        .bind(emoji)
+        .bind(bg_color)
+        .bind(bio)
+        .bind(user_id)
+        .bind(private)
+        .bind(contact_hash)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(me)
+    }
+
+    /// Changes the username of the user and records the previous one in the history
+    pub async fn update_username(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        username: &str,
+    ) -> Result<MeUser, TalliiError> {
+        sqlx::query(
+            "insert into username_history (username, user_id) select username, user_id from users where user_id = $1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let me = sqlx::query_as::<_, MeUser>(
+            "update users set username = $1 where user_id = $2 returning user_id, emoji, bg_color, email, username, verified, bio, private, discoverable",
+        )
+        .bind(username)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(username_taken)?;
+
+        Ok(me)
+    }
+
+    /// Changes the email of the user to an email they have just verified, a discoverable user
+    /// becomes discoverable by the hash of the new email
+    pub async fn update_email(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        email: &str,
+        contact_hash: &str,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            "update users set email = $1, verified = true, contact_hash = case when discoverable then $3 end where user_id = $2",
+        )
+        .bind(email)
+        .bind(user_id)
+        .bind(contact_hash)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Locks the user until the end of the transaction, currency is posted to the same row so
+    /// nothing can be staked by the user while it is held
+    pub async fn lock_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query("select user_id from users where user_id = $1 for update")
+            .bind(user_id)
+            .execute(tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Anonymises the user so that the events and comments other users rely on stay intact,
+    /// frees their username and previous usernames and removes their personal data
+    pub async fn anonymise(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query("delete from username_history where user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+                update
+                    users
+                set
+                    username = 'deleted-' || user_id,
+                    email = 'deleted-' || user_id || '@deleted.tallii',
+                    password = '',
+                    emoji = $2,
+                    bg_color = $3,
+                    bio = null,
+                    verified = false,
+                    discoverable = false,
+                    contact_hash = null,
+                    deleted_at = current_timestamp
+                where
+                    user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(DELETED_EMOJI)
+        .bind(BACKGROUNDS[0])
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sets the global role of the user. Returns false if there is no such user.
+    pub async fn update_role(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        role: &str,
+    ) -> Result<bool, TalliiError> {
+        let result =
+            sqlx::query("update users set role = $1 where user_id = $2 and deleted_at is null")
+                .bind(role)
+                .bind(user_id)
+                .execute(tx)
+                .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Suspends the user with the reason until the provided time, or until it is lifted when no
+    /// time is provided. Returns false if there is no such user.
+    pub async fn suspend(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        reason: &str,
+        until: Option<chrono::NaiveDateTime>,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                update
+                    users
+                set
+                    suspended_at = current_timestamp,
+                    suspended_until = $1,
+                    suspension_reason = $2
+                where
+                    user_id = $3
+                and
+                    deleted_at is null
+            "#,
+        )
+        .bind(until)
+        .bind(reason)
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Lifts the suspension of the user. Returns false if the user is not suspended.
+    pub async fn unsuspend(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                update
+                    users
+                set
+                    suspended_at = null,
+                    suspended_until = null,
+                    suspension_reason = null
+                where
+                    user_id = $1
+                and
+                    suspended_at is not null
+            "#,
+        )
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
 }
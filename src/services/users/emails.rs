@@ -0,0 +1,14 @@
+use crate::mailer::{Email, Mailer};
+
+/// Creates the email that tells a user their account was locked after too many failed logins
+pub fn account_locked_email(mailer: &Mailer, to: &str, lockout_seconds: i64) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: String::from("Your Tallii account was locked"),
+        body: format!(
+            "There were too many failed attempts to log in to your Tallii account, so logging in is blocked for the next {} minutes.\n\nIf this was not you, we recommend resetting your password:\n\n{}/forgot-password",
+            (lockout_seconds + 59) / 60,
+            mailer.app_url
+        ),
+    }
+}
@@ -1,21 +1,49 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{rt, web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
 use sqlx::PgPool;
+use tracing::error;
 
 use crate::crypto::{Crypto, TokenResponse};
 use crate::errors::TalliiError;
-use crate::services::auth::AuthenticatedUser;
+use crate::limiter::LoginLimiter;
+use crate::mailer::Mailer;
+use crate::oidc::OidcVerifier;
+use crate::services::admin::db::AuditLogTable;
+use crate::services::admin::models::AuditAction;
+use crate::services::auth::{AdminUser, AuthenticatedUser, VerifiedUser};
+use crate::services::currency::db::CurrencyTable;
+use crate::services::currency::models::{TransactionReason, SIGNUP_GRANT};
+use crate::services::events::comments::db::EventCommentsTable;
+use crate::services::events::db::EventsTable;
+use crate::services::events::members::db::EventMembersTable;
+use crate::services::events::pools::db::EventPoolBetsTable;
+use crate::services::events::teams::db::EventTeamMembersTable;
+use crate::services::friends::db::FriendsTable;
+use crate::services::identities::db::IdentitiesTable;
+use crate::services::identities::reauthentication::reauthenticate;
+use crate::services::sessions::db::SessionsTable;
+use crate::services::sessions::models::NewSession;
+use crate::services::tokens::db::AccessTokensTable;
+use crate::services::two_factor::db::{LoginChallengesTable, TwoFactorTable};
+use crate::services::two_factor::models::TwoFactorChallengeResponse;
+use crate::services::verification::db::VerificationTokensTable;
+use crate::services::verification::emails::verification_email;
+use crate::services::verification::models::{MAX_SENDS_PER_DAY, RESEND_INTERVAL_SECONDS};
+use crate::services::wagers::db::WagersTable;
 use crate::services::{SuccessResponse, TalliiResponse};
 
-use super::db::{InviteCodesTable, UsersTable};
-use super::models::{CreateInviteCode, LoginUser, NewUser, UserQuery};
+use super::db::{
+    Avatar, InviteCodesTable, UsersTable, MAX_BIO_LENGTH, MAX_INVITE_CODES_PER_REQUEST,
+    MAX_USERNAME_LENGTH,
+};
+use super::emails::account_locked_email;
+use super::models::{
+    ChangeEmailRequest, ChangeUsernameRequest, CreateInviteCode, DeleteMeRequest, EditUser,
+    LoginUser, NewUser, UserExport,
+};
 
 /// Gets all invite codes
-pub async fn get_invite_codes(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
-    // check if the user is me to make sure that no one can make invite codes
-    if user.username != String::from("adamaho") {
-        return Err(TalliiError::UNAUTHORIZED.default());
-    }
-
+pub async fn get_invite_codes(pool: web::Data<PgPool>, _admin: AdminUser) -> TalliiResponse {
     // execute the query
     let all_invite_codes = InviteCodesTable::get_all(&pool).await?;
 
@@ -23,15 +51,28 @@ pub async fn get_invite_codes(pool: web::Data<PgPool>, user: AuthenticatedUser)
     Ok(HttpResponse::Ok().json(all_invite_codes))
 }
 
+/// Gets the invite codes the verified user can share, creating their quota on the first request
+pub async fn get_my_invite_codes(pool: web::Data<PgPool>, user: VerifiedUser) -> TalliiResponse {
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    InviteCodesTable::fill_quota(&mut tx, &user.user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    let invite_codes = InviteCodesTable::get_by_creator(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(invite_codes))
+}
+
 /// Checks the validity of the invite code
 pub async fn check_invite_code(pool: web::Data<PgPool>, code: web::Path<String>) -> TalliiResponse {
     // execute the query
     let is_valid = InviteCodesTable::is_valid(&pool, &code).await?;
 
-    let user = UsersTable::get_by_invite_code(&pool, &code).await?;
-
     // if not valid return an error
-    if !is_valid || user.is_some() {
+    if !is_valid {
         Err(TalliiError::INVALID_INVITE_CODE.default())
     } else {
         Ok(HttpResponse::Ok().json(SuccessResponse {
@@ -45,18 +86,62 @@ pub async fn check_invite_code(pool: web::Data<PgPool>, code: web::Path<String>)
 pub async fn create_invite_codes(
     pool: web::Data<PgPool>,
     new_codes: web::Json<CreateInviteCode>,
-    user: AuthenticatedUser,
+    admin: AdminUser,
 ) -> TalliiResponse {
-    // check if the user is me to make sure that no one can make invite codes
-    if user.username != String::from("adamaho") {
-        return Err(TalliiError::UNAUTHORIZED.default());
+    if new_codes.amount < 1 || new_codes.amount > MAX_INVITE_CODES_PER_REQUEST {
+        return Err(TalliiError::BAD_REQUEST.message(format!(
+            "The amount must be between 1 and {}.",
+            MAX_INVITE_CODES_PER_REQUEST
+        )));
     }
 
-    // execute the query
-    InviteCodesTable::create_many(&pool, new_codes.amount).await?;
+    let max_uses = new_codes.max_uses.unwrap_or(1);
+
+    if max_uses < 1 {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("The max uses must be at least 1."))
+        );
+    }
+
+    if let Some(expires_at) = new_codes.expires_at {
+        if expires_at <= Utc::now().naive_utc() {
+            return Err(
+                TalliiError::BAD_REQUEST.message(String::from("The expiry must be in the future."))
+            );
+        }
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let invite_codes = InviteCodesTable::create_many(
+        &mut tx,
+        new_codes.amount,
+        Some(admin.user_id),
+        max_uses,
+        new_codes.expires_at,
+        false,
+    )
+    .await?;
 
-    // response with created
-    Ok(HttpResponse::Created().finish())
+    AuditLogTable::record(
+        &mut tx,
+        &admin.user_id,
+        AuditAction::CreateInviteCodes,
+        None,
+        Some(format!(
+            "amount={};max_uses={}",
+            invite_codes.len(),
+            max_uses
+        )),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    // respond with the created codes
+    Ok(HttpResponse::Created().json(invite_codes))
 }
 
 /// Gets a user by the provided username
@@ -86,72 +171,177 @@ pub async fn check_user_email(pool: web::Data<PgPool>, email: web::Path<String>)
     }
 }
 
-/// Logs the user in if the provided credentials are correct
+/// Logs the user in if the provided credentials are correct, repeated failures lock out the ip
+/// address and the account for progressively longer
 pub async fn login(
+    req: HttpRequest,
     pool: web::Data<PgPool>,
     crypto: web::Data<Crypto>,
+    mailer: web::Data<Mailer>,
+    limiter: web::Data<LoginLimiter>,
     person: web::Json<LoginUser>,
 ) -> Result<HttpResponse, TalliiError> {
+    // the ip address of the connection, forwarded headers are set by the client and cannot be
+    // trusted to identify it
+    let ip_address = req.peer_addr().map(|addr| addr.ip().to_string());
+
+    // refuse before doing any password hashing while locked out
+    limiter.check(ip_address.as_deref(), &person.email).await?;
+
     // check if there is a user with the provided email
-    let user = match UsersTable::get_by_email(&pool, &person.email).await? {
-        Some(u) => u,
-        None => {
+    let user = UsersTable::get_by_email(&pool, &person.email).await?;
+
+    // verify the provided password
+    let verified = match &user {
+        Some(user) => {
+            crypto
+                .verify_password(&person.password, &user.password)
+                .await?
+        }
+        None => false,
+    };
+
+    let user = match user {
+        Some(user) if verified => user,
+        user => {
+            let lockout_seconds = limiter
+                .record_failure(ip_address.as_deref(), &person.email)
+                .await?;
+
+            // let the owner of the account know it was locked, off the request path so the
+            // response does not take longer when there is an account for the email
+            if let (Some(user), Some(lockout_seconds)) = (user, lockout_seconds) {
+                let email = account_locked_email(&mailer, &user.email, lockout_seconds);
+                let mailer = mailer.clone();
+
+                rt::spawn(async move {
+                    if let Err(err) = mailer.send(email).await {
+                        error!("Failed to send account locked email: {:?}", err);
+                    }
+                });
+            }
+
             return Err(TalliiError::INVALID_LOGIN.default());
         }
     };
 
-    // verify the provided password
-    if !crypto
-        .verify_password(&person.password, &user.password)
-        .await?
-    {
-        return Err(TalliiError::INVALID_LOGIN.default());
+    limiter.record_success(&person.email).await?;
+
+    if user.is_suspended(Utc::now().naive_utc()) {
+        return Err(TalliiError::ACCOUNT_SUSPENDED.default());
     }
 
+    // the session is only started once the 2fa code is provided to /login/2fa
+    if TwoFactorTable::is_enabled(&pool, &user.user_id).await? {
+        let challenge_token =
+            LoginChallengesTable::issue(&pool, &crypto, &user.user_id, person.device_name.clone())
+                .await?;
+
+        return Ok(HttpResponse::Ok().json(TwoFactorChallengeResponse {
+            two_factor_required: true,
+            challenge_token,
+        }));
+    }
+
+    // start a new session for the newly authorized user on their device
+    let new_session = NewSession::from_request(&req, person.device_name.clone());
+    let (session, refresh_token) =
+        SessionsTable::start(&pool, &crypto, &user.user_id, &new_session).await?;
+
     // create a new jwt for the newly authorized user
-    let token = crypto.generate_jwt(user.user_id, user.username).await?;
+    let token = crypto
+        .generate_jwt(user.user_id, user.username, session.session_id)
+        .await?;
 
     // respond with the token
-    Ok(HttpResponse::Ok().json(TokenResponse { token }))
+    Ok(HttpResponse::Ok().json(TokenResponse {
+        token,
+        refresh_token,
+    }))
 }
 
 /// Signs a user up with the provided credentials
 pub async fn signup(
+    req: HttpRequest,
     pool: web::Data<PgPool>,
     crypto: web::Data<Crypto>,
+    mailer: web::Data<Mailer>,
     new_user: web::Json<NewUser>,
 ) -> TalliiResponse {
-    // check to make sure the invite code is valid
-    let is_valid_invite_code = InviteCodesTable::is_valid(&pool, &new_user.invite_code).await?;
-
-    // if not valid return an InvalidInviteCode error
-    if !is_valid_invite_code {
-        return Err(TalliiError::INVALID_INVITE_CODE.default());
+    // a username is taken while it or a recent rename from it resolves to a user
+    if UsersTable::get_by_username(&pool, &new_user.username)
+        .await?
+        .is_some()
+    {
+        return Err(TalliiError::USERNAME_TAKEN.default());
     }
 
-    // check to make sure the invite code is not taken by another user
-    if let Some(_) = UsersTable::get_by_invite_code(&pool, &new_user.invite_code).await? {
-        return Err(TalliiError::INVALID_INVITE_CODE.default());
-    }
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    // use the invite code, this fails when it has no uses left or has expired
+    let invite_code = InviteCodesTable::redeem(&mut tx, &new_user.invite_code)
+        .await?
+        .ok_or_else(|| TalliiError::INVALID_INVITE_CODE.default())?;
 
     // create the new user in the database
-    let created_user = UsersTable::create(&pool, &new_user, &crypto).await?;
+    let created_user =
+        UsersTable::create(&mut tx, &new_user, invite_code.created_by, &crypto).await?;
+
+    // give the new user their starting currency
+    CurrencyTable::post(
+        &mut tx,
+        &created_user.user_id,
+        SIGNUP_GRANT,
+        TransactionReason::SignupGrant,
+        None,
+    )
+    .await?;
+
+    // create the token the user verifies their email with
+    let verification_token =
+        VerificationTokensTable::issue(&mut tx, &crypto, &created_user.user_id, None).await?;
 
-    // TODO: send verification email to user
+    // commit the transaction
+    tx.commit().await?;
+
+    // the user can always request another verification email so failing to send is not fatal
+    if let Err(err) = mailer
+        .send(verification_email(
+            &mailer,
+            &new_user.email,
+            &verification_token,
+        ))
+        .await
+    {
+        error!("Failed to send verification email: {:?}", err);
+    }
+
+    // start a new session for that user on their device
+    let new_session = NewSession::from_request(&req, new_user.device_name.clone());
+    let (session, refresh_token) =
+        SessionsTable::start(&pool, &crypto, &created_user.user_id, &new_session).await?;
 
     // create a new jwt token for that user
     let token = crypto
-        .generate_jwt(created_user.user_id, created_user.username)
+        .generate_jwt(
+            created_user.user_id,
+            created_user.username,
+            session.session_id,
+        )
         .await?;
 
     // respond with the newly created token
-    Ok(HttpResponse::Ok().json(TokenResponse { token }))
+    Ok(HttpResponse::Ok().json(TokenResponse {
+        token,
+        refresh_token,
+    }))
 }
 
 /// Gets me
 pub async fn get_me(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
     // get me from the database
-    let me = UsersTable::get_by_username(&pool, &user.username).await?;
+    let me = UsersTable::get_me(&pool, &user.user_id).await?;
 
     if me.is_some() {
         // response with json of me
@@ -161,6 +351,240 @@ pub async fn get_me(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiR
     }
 }
 
+/// Updates the avatar, bio and privacy of me
+pub async fn update_me(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    user: AuthenticatedUser,
+    edit_user: web::Json<EditUser>,
+) -> TalliiResponse {
+    let me = UsersTable::get_me(&pool, &user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // only the provided fields are changed
+    let emoji = edit_user.emoji.as_deref().unwrap_or(&me.emoji);
+    let bg_color = edit_user.bg_color.as_deref().unwrap_or(&me.bg_color);
+    let bio = match &edit_user.bio {
+        Some(bio) => Some(bio.trim()).filter(|bio| !bio.is_empty()),
+        None => me.bio.as_deref(),
+    };
+    let private = edit_user.private.unwrap_or(me.private);
+    let discoverable = edit_user.discoverable.unwrap_or(me.discoverable);
+
+    // only the provided fields are validated, so a value that is no longer offered can be kept
+    if edit_user.emoji.is_some() && !Avatar::is_valid_emoji(emoji) {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("The provided emoji is not available."))
+        );
+    }
+
+    if edit_user.bg_color.is_some() && !Avatar::is_valid_background(bg_color) {
+        return Err(TalliiError::BAD_REQUEST
+            .message(String::from("The provided bg_color is not available.")));
+    }
+
+    if edit_user.bio.is_some() && bio.map_or(false, |bio| bio.chars().count() > MAX_BIO_LENGTH) {
+        return Err(TalliiError::BAD_REQUEST.message(format!(
+            "The bio cannot be longer than {} characters.",
+            MAX_BIO_LENGTH
+        )));
+    }
+
+    // discoverable users can be found by the hash of their email
+    let contact_hash = Some(crypto.hash_contact(&me.email)).filter(|_| discoverable);
+
+    let updated_me = UsersTable::update_profile(
+        &pool,
+        &user.user_id,
+        emoji,
+        bg_color,
+        bio,
+        private,
+        contact_hash.as_deref(),
+    )
+    .await?;
+
+    // the pending requests are followers as soon as anyone can follow me
+    if me.private && !private {
+        FriendsTable::approve_all_requests(&pool, &user.user_id).await?;
+    }
+
+    Ok(HttpResponse::Ok().json(updated_me))
+}
+
+/// Changes my username, my previous username keeps resolving to me for a grace period
+pub async fn change_username(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    request: web::Json<ChangeUsernameRequest>,
+) -> TalliiResponse {
+    let username = request.username.trim();
+
+    if username.is_empty()
+        || username.chars().count() > MAX_USERNAME_LENGTH
+        || username.contains(|c: char| c.is_whitespace() || c == '/')
+    {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "The username cannot be empty or contain spaces or slashes.",
+        )));
+    }
+
+    if username == user.username {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "The provided username is already your username.",
+        )));
+    }
+
+    // a username is taken while it or a recent rename from it resolves to another user
+    if let Some(existing) = UsersTable::get_by_username(&pool, &username.to_string()).await? {
+        if existing.user_id != user.user_id {
+            return Err(TalliiError::USERNAME_TAKEN.default());
+        }
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let me = UsersTable::update_username(&mut tx, &user.user_id, username).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(me))
+}
+
+/// Sends a verification email to the new email, my email is changed once it is verified
+pub async fn change_email(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    mailer: web::Data<Mailer>,
+    user: AuthenticatedUser,
+    request: web::Json<ChangeEmailRequest>,
+) -> TalliiResponse {
+    let email = request.email.trim().to_string();
+
+    if !email.contains('@') {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("The provided email is invalid."))
+        );
+    }
+
+    if UsersTable::get_by_email(&pool, &email).await?.is_some() {
+        return Err(TalliiError::EMAIL_TAKEN.default());
+    }
+
+    // changing the email sends a verification email so it is throttled the same way
+    let recent = VerificationTokensTable::get_recent_for_user(&pool, &user.user_id).await?;
+
+    let sent_recently = recent.last_created_at.map_or(false, |created_at| {
+        Utc::now().naive_utc() - created_at < Duration::seconds(RESEND_INTERVAL_SECONDS)
+    });
+
+    if sent_recently || recent.count >= MAX_SENDS_PER_DAY {
+        return Err(TalliiError::TOO_MANY_REQUESTS.default());
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let token =
+        VerificationTokensTable::issue(&mut tx, &crypto, &user.user_id, Some(&email)).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    mailer
+        .send(verification_email(&mailer, &email, &token))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("SENT_EMAIL_VERIFICATION"),
+        message: String::from("A verification email was sent to the new email. The email changes once it is verified."),
+    }))
+}
+
+/// Exports all of my personal data
+pub async fn export_me(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    let profile = UsersTable::get_me(&pool, &user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    let export = UserExport {
+        profile,
+        followers: FriendsTable::get_followers_by_id(&pool, &user.user_id).await?,
+        following: FriendsTable::get_following_by_id(&pool, &user.user_id).await?,
+        events: EventsTable::get_events_for_user_id(&pool, &user.user_id, "active").await?,
+        event_invitations: EventsTable::get_events_for_user_id(&pool, &user.user_id, "pending")
+            .await?,
+        memberships: EventMembersTable::get_by_user_id(&pool, &user.user_id).await?,
+        teams: EventTeamMembersTable::get_by_user_id(&pool, &user.user_id).await?,
+        comments: EventCommentsTable::get_comments_by_user_id(&pool, &user.user_id).await?,
+        wagers: WagersTable::get_wagers_for_me(&pool, &user.user_id).await?,
+        wager_invitations: WagersTable::get_invitations_for_user_id(&pool, &user.user_id).await?,
+        pool_bets: EventPoolBetsTable::get_by_user_id(&pool, &user.user_id).await?,
+        currency_transactions: CurrencyTable::get_all_transactions(&pool, &user.user_id).await?,
+        sessions: SessionsTable::get_active_for_user(&pool, &user.user_id).await?,
+        identities: IdentitiesTable::get_by_user_id(&pool, &user.user_id).await?,
+        access_tokens: AccessTokensTable::get_by_user_id(&pool, &user.user_id).await?,
+        exported_at: Utc::now().naive_utc(),
+    };
+
+    Ok(HttpResponse::Ok().json(export))
+}
+
+/// Deletes my account. The user is anonymised rather than removed so that the events and
+/// comments other users rely on stay intact.
+pub async fn delete_me(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    verifier: web::Data<OidcVerifier>,
+    user: AuthenticatedUser,
+    request: web::Json<DeleteMeRequest>,
+) -> TalliiResponse {
+    let me = UsersTable::get_by_id(&pool, &user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // re-check the password or the identity before doing anything irreversible
+    reauthenticate(&pool, &crypto, &verifier, &me, &request.reauthentication).await?;
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    // lock the user so no wager or bet can be made while the account is deleted
+    UsersTable::lock_for_update(&mut tx, &user.user_id).await?;
+
+    // currency held in escrow for other users has to be resolved first
+    if WagersTable::has_open_for_user(&mut tx, &user.user_id).await?
+        || EventPoolBetsTable::has_active_for_user(&mut tx, &user.user_id).await?
+    {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "Resolve your pending and accepted wagers and active bets before deleting your account.",
+        )));
+    }
+
+    FriendsTable::delete_all_for_user(&mut tx, &user.user_id).await?;
+
+    TwoFactorTable::delete(&mut tx, &user.user_id).await?;
+
+    IdentitiesTable::delete_all_for_user(&mut tx, &user.user_id).await?;
+
+    UsersTable::anonymise(&mut tx, &user.user_id).await?;
+
+    SessionsTable::revoke_all_for_user(&mut tx, &user.user_id).await?;
+
+    AccessTokensTable::revoke_all_for_user(&mut tx, &user.user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("DELETED_ACCOUNT"),
+        message: String::from("The account was deleted."),
+    }))
+}
+
 /// Gets the profile of a specific user
 pub async fn get_user_by_username(
     pool: web::Data<PgPool>,
@@ -177,16 +601,3 @@ pub async fn get_user_by_username(
         Err(TalliiError::NOT_FOUND.default())
     }
 }
-
-/// Gets maximum users that match the provided username
-pub async fn search_users(
-    pool: web::Data<PgPool>,
-    _user: AuthenticatedUser,
-    params: web::Query<UserQuery>,
-) -> TalliiResponse {
-    // search for users in db
-    let users = UsersTable::search_by_username(&pool, &params).await?;
-
-    // response with json of users
-    Ok(HttpResponse::Ok().json(users))
-}
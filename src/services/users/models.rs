@@ -1,5 +1,16 @@
 use serde::{Deserialize, Serialize};
 
+use crate::services::currency::models::CurrencyTransaction;
+use crate::services::events::comments::models::EventComment;
+use crate::services::events::members::models::EventMember;
+use crate::services::events::models::EventResponse;
+use crate::services::events::pools::models::EventPoolBet;
+use crate::services::events::teams::models::TeamAssignment;
+use crate::services::identities::models::{Reauthentication, UserIdentity};
+use crate::services::sessions::models::Session;
+use crate::services::tokens::models::AccessToken;
+use crate::services::wagers::models::WagerResponse;
+
 /// Database representation of a User
 #[derive(sqlx::FromRow, Serialize)]
 pub struct User {
@@ -12,9 +23,20 @@ pub struct User {
     pub username: String,
     pub bio: Option<String>,
     pub verified: Option<bool>,
+    pub role: String,
+    pub suspended_at: Option<chrono::NaiveDateTime>,
+    pub suspended_until: Option<chrono::NaiveDateTime>,
+    pub suspension_reason: Option<String>,
     pub created_at: chrono::NaiveDateTime,
 }
 
+impl User {
+    /// Checks if the user is suspended at the provided time
+    pub fn is_suspended(&self, now: chrono::NaiveDateTime) -> bool {
+        self.suspended_at.is_some() && self.suspended_until.map_or(true, |until| until > now)
+    }
+}
+
 /// Representation of a user that can be publicized
 #[derive(sqlx::FromRow, Serialize)]
 pub struct MeUser {
@@ -25,6 +47,14 @@ pub struct MeUser {
     pub username: String,
     pub verified: Option<bool>,
     pub bio: Option<String>,
+    pub private: bool,
+    pub discoverable: bool,
+}
+
+/// Whether or not a user has verified their email
+#[derive(sqlx::FromRow, Debug)]
+pub struct UserVerified {
+    pub verified: bool,
 }
 
 /// Representation of a user that can be publicized
@@ -44,14 +74,57 @@ pub struct NewUser {
     pub invite_code: String,
     pub password: String,
     pub username: String,
+    pub device_name: Option<String>,
 }
 
-/// Representation of an User to Update
+/// Representation of an User to Update, fields that are not provided are left unchanged
 #[derive(Debug, Deserialize)]
 pub struct EditUser {
-    pub emoji: String,
-    pub bg_color: String,
-    pub bio: String,
+    pub emoji: Option<String>,
+    pub bg_color: Option<String>,
+    pub bio: Option<String>,
+    pub private: Option<bool>,
+    pub discoverable: Option<bool>,
+}
+
+/// Representation of the change username request
+#[derive(Debug, Deserialize)]
+pub struct ChangeUsernameRequest {
+    pub username: String,
+}
+
+/// Representation of the change email request
+#[derive(Debug, Deserialize)]
+pub struct ChangeEmailRequest {
+    pub email: String,
+}
+
+/// Representation of the delete me request
+#[derive(Debug, Deserialize)]
+pub struct DeleteMeRequest {
+    #[serde(flatten)]
+    pub reauthentication: Reauthentication,
+}
+
+/// Archive of all the personal data of a user
+#[derive(Serialize)]
+pub struct UserExport {
+    pub profile: MeUser,
+    pub followers: Vec<PublicUser>,
+    pub following: Vec<PublicUser>,
+    pub events: Vec<EventResponse>,
+    pub event_invitations: Vec<EventResponse>,
+    pub memberships: Vec<EventMember>,
+    pub teams: Vec<TeamAssignment>,
+    pub comments: Vec<EventComment>,
+    pub wagers: Vec<WagerResponse>,
+    pub wager_invitations: Vec<WagerResponse>,
+    pub pool_bets: Vec<EventPoolBet>,
+    pub currency_transactions: Vec<CurrencyTransaction>,
+    pub sessions: Vec<Session>,
+    pub identities: Vec<UserIdentity>,
+    pub access_tokens: Vec<AccessToken>,
+    pub exported_at: chrono::NaiveDateTime,
 }
 
 /// Representation of an User to Login
@@ -59,22 +132,36 @@ pub struct EditUser {
 pub struct LoginUser {
     pub email: String,
     pub password: String,
+    pub device_name: Option<String>,
 }
 
 /// Database representation of an InviteCode
 #[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
 pub struct InviteCode {
     pub id: String,
+    pub created_by: Option<i32>,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Represents an InviteCode existing
+#[derive(sqlx::FromRow, Debug)]
+pub struct InviteCodeExists {
+    pub exists: bool,
+}
+
+/// Represents the amount of InviteCodes a user created
+#[derive(sqlx::FromRow, Debug)]
+pub struct InviteCodeCount {
+    pub count: i64,
 }
 
 /// Representation of struct for creating InviteCodes
 #[derive(Debug, Deserialize)]
 pub struct CreateInviteCode {
     pub amount: i32,
-}
-
-/// Representation of the search query
-#[derive(Debug, Deserialize)]
-pub struct UserQuery {
-    pub q: String,
+    pub max_uses: Option<i32>,
+    pub expires_at: Option<chrono::NaiveDateTime>,
 }
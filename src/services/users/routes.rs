@@ -26,7 +26,30 @@ pub fn users_signup() -> Resource {
 
 /// Resource routes for getting the currently logged in user
 pub fn users_me_routes() -> Resource {
-    web::resource("/me").route(web::get().to(handlers::get_me))
+    web::resource("/me")
+        .route(web::get().to(handlers::get_me))
+        .route(web::patch().to(handlers::update_me))
+        .route(web::delete().to(handlers::delete_me))
+}
+
+/// Resource routes for the invite codes of the currently logged in user
+pub fn users_me_invite_codes_routes() -> Resource {
+    web::resource("/me/invite-codes").route(web::get().to(handlers::get_my_invite_codes))
+}
+
+/// Resource routes for exporting the data of the currently logged in user
+pub fn users_me_export_routes() -> Resource {
+    web::resource("/me/export").route(web::get().to(handlers::export_me))
+}
+
+/// Resource routes for changing the username of the currently logged in user
+pub fn users_me_username_routes() -> Resource {
+    web::resource("/me/username").route(web::put().to(handlers::change_username))
+}
+
+/// Resource routes for changing the email of the currently logged in user
+pub fn users_me_email_routes() -> Resource {
+    web::resource("/me/email").route(web::put().to(handlers::change_email))
 }
 
 /// Resource routes for user username
@@ -43,8 +66,3 @@ pub fn users_email_check_routes() -> Resource {
 pub fn users_username_routes() -> Resource {
     web::resource("/users/{username}").route(web::get().to(handlers::get_user_by_username))
 }
-
-/// Resource routes for user username search
-pub fn users_username_search_routes() -> Resource {
-    web::resource("/search/users").route(web::get().to(handlers::search_users))
-}
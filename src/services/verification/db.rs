@@ -0,0 +1,135 @@
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Transaction};
+
+use crate::crypto::Crypto;
+use crate::errors::TalliiError;
+
+use super::models::{RecentVerificationTokens, VerificationToken, VERIFICATION_TOKEN_HOURS};
+
+pub struct VerificationTokensTable;
+
+impl VerificationTokensTable {
+    /// Issues a new verification token for the user and returns the token to email to them. When
+    /// an email is provided verifying the token changes the email of the user to it.
+    pub async fn issue(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        crypto: &Crypto,
+        user_id: &i32,
+        email: Option<&str>,
+    ) -> Result<String, TalliiError> {
+        let token = crypto.generate_opaque_token();
+
+        VerificationTokensTable::create(tx, user_id, &crypto.hash_token(&token), email).await?;
+
+        Ok(token)
+    }
+
+    /// Creates a verification token for the user
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        token_hash: &str,
+        email: Option<&str>,
+    ) -> Result<(), TalliiError> {
+        let expires_at = (Utc::now() + Duration::hours(VERIFICATION_TOKEN_HOURS)).naive_utc();
+
+        sqlx::query(
+            r#"
+                insert into
+                    users_verification_tokens (token_hash, user_id, email, expires_at)
+                values
+                    ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(email)
+        .bind(expires_at)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets an unused and unexpired verification token by its hash and locks it until the
+    /// transaction finishes
+    pub async fn get_valid_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        token_hash: &str,
+    ) -> Result<Option<VerificationToken>, TalliiError> {
+        let token = sqlx::query_as::<_, VerificationToken>(
+            r#"
+                select
+                    token_hash,
+                    user_id,
+                    email,
+                    expires_at,
+                    used_at,
+                    created_at
+                from
+                    users_verification_tokens
+                where
+                    token_hash = $1
+                and
+                    used_at is null
+                and
+                    expires_at > current_timestamp
+                for update
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Marks every unused verification token of the user as used
+    pub async fn use_all_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    users_verification_tokens
+                set
+                    used_at = current_timestamp
+                where
+                    user_id = $1
+                and
+                    used_at is null
+            "#,
+        )
+        .bind(user_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets how many verification tokens were created for the user in the last day
+    pub async fn get_recent_for_user(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<RecentVerificationTokens, TalliiError> {
+        let recent = sqlx::query_as::<_, RecentVerificationTokens>(
+            r#"
+                select
+                    count(*) as count,
+                    max(created_at) as last_created_at
+                from
+                    users_verification_tokens
+                where
+                    user_id = $1
+                and
+                    created_at > current_timestamp - interval '1 day'
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(recent)
+    }
+}
@@ -0,0 +1,13 @@
+use crate::mailer::{Email, Mailer};
+
+/// Creates the email that asks a user to verify their email with the provided token
+pub fn verification_email(mailer: &Mailer, to: &str, token: &str) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: String::from("Verify your Tallii email"),
+        body: format!(
+            "Verify the email of your Tallii account by following the link below:\n\n{}/verify-email?token={}\n\nThe link expires in a day.",
+            mailer.app_url, token
+        ),
+    }
+}
@@ -0,0 +1,101 @@
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+
+use crate::crypto::Crypto;
+use crate::errors::TalliiError;
+use crate::mailer::Mailer;
+use crate::services::auth::AuthenticatedUser;
+use crate::services::users::db::UsersTable;
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::VerificationTokensTable;
+use super::emails::verification_email;
+use super::models::{VerifyEmailRequest, MAX_SENDS_PER_DAY, RESEND_INTERVAL_SECONDS};
+
+/// Verifies the email of the user the provided token was sent to
+pub async fn verify_email(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    request: web::Json<VerifyEmailRequest>,
+) -> TalliiResponse {
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let token =
+        VerificationTokensTable::get_valid_for_update(&mut tx, &crypto.hash_token(&request.token))
+            .await?
+            .ok_or_else(|| TalliiError::INVALID_TOKEN.default())?;
+
+    // every outstanding token of the user is spent once one of them is used
+    VerificationTokensTable::use_all_for_user(&mut tx, &token.user_id).await?;
+
+    match &token.email {
+        // the token was sent to confirm a new email
+        Some(email) => {
+            if let Some(other) = UsersTable::get_by_email(&pool, email).await? {
+                if other.user_id != token.user_id {
+                    return Err(TalliiError::EMAIL_TAKEN.default());
+                }
+            }
+
+            UsersTable::update_email(&mut tx, &token.user_id, email, &crypto.hash_contact(email))
+                .await?;
+        }
+        None => UsersTable::set_verified(&mut tx, &token.user_id).await?,
+    }
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("VERIFIED_EMAIL"),
+        message: String::from("The email of the user was verified."),
+    }))
+}
+
+/// Sends me a new verification email
+pub async fn resend_verification(
+    pool: web::Data<PgPool>,
+    crypto: web::Data<Crypto>,
+    mailer: web::Data<Mailer>,
+    user: AuthenticatedUser,
+) -> TalliiResponse {
+    let me = UsersTable::get_by_id(&pool, &user.user_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if me.verified.unwrap_or(false) {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("The email is already verified."))
+        );
+    }
+
+    // throttle how often verification emails can be sent
+    let recent = VerificationTokensTable::get_recent_for_user(&pool, &user.user_id).await?;
+
+    let sent_recently = recent.last_created_at.map_or(false, |created_at| {
+        Utc::now().naive_utc() - created_at < Duration::seconds(RESEND_INTERVAL_SECONDS)
+    });
+
+    if sent_recently || recent.count >= MAX_SENDS_PER_DAY {
+        return Err(TalliiError::TOO_MANY_REQUESTS.default());
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let token = VerificationTokensTable::issue(&mut tx, &crypto, &user.user_id, None).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    mailer
+        .send(verification_email(&mailer, &me.email, &token))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("SENT_VERIFICATION_EMAIL"),
+        message: String::from("A new verification email was sent."),
+    }))
+}
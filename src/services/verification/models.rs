@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+/// Hours a verification token is valid for
+pub const VERIFICATION_TOKEN_HOURS: i64 = 24;
+
+/// Seconds a user has to wait before another verification email can be sent
+pub const RESEND_INTERVAL_SECONDS: i64 = 60;
+
+/// Maximum amount of verification emails that can be sent to a user in a day
+pub const MAX_SENDS_PER_DAY: i64 = 5;
+
+/// Database representation of a VerificationToken
+#[derive(sqlx::FromRow, Debug)]
+pub struct VerificationToken {
+    pub token_hash: String,
+    pub user_id: i32,
+    pub email: Option<String>,
+    pub expires_at: chrono::NaiveDateTime,
+    pub used_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Verification tokens sent to a user in the last day
+#[derive(sqlx::FromRow, Debug)]
+pub struct RecentVerificationTokens {
+    pub count: i64,
+    pub last_created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Representation of the verify email request
+#[derive(Deserialize, Debug)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
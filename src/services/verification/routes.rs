@@ -0,0 +1,13 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for verifying an email
+pub fn verify_email_routes() -> Resource {
+    web::resource("/verify-email").route(web::post().to(handlers::verify_email))
+}
+
+/// Resource routes for resending the verification email of the currently logged in user
+pub fn me_resend_verification_routes() -> Resource {
+    web::resource("/me/resend-verification").route(web::post().to(handlers::resend_verification))
+}
@@ -1,43 +1,440 @@
-use sqlx::PgPool;
+use sqlx::{PgPool, Transaction};
 
 use crate::errors::TalliiError;
-use super::models::{Wager, NewWager};
-use crate::services::auth::AuthenticatedUser;
+
+use super::models::{NewWager, Wager, WagerExists, WagerResponse, WagerRow};
+
+/// Selects a wager joined with both users and the team it is placed on
+const SELECT_WAGER_ROWS: &str = r#"
+    select
+        w.wager_id,
+        w.team_id,
+        t.name as team_name,
+        t.event_id,
+        w.state,
+        w.currency,
+        w.winner_user_id,
+        w.settled_at,
+        w.created_at,
+        hu.user_id as home_user_id,
+        hu.emoji as home_emoji,
+        hu.bg_color as home_bg_color,
+        hu.username as home_username,
+        hu.bio as home_bio,
+        au.user_id as away_user_id,
+        au.emoji as away_emoji,
+        au.bg_color as away_bg_color,
+        au.username as away_username,
+        au.bio as away_bio
+    from
+        wagers w
+    inner join
+        events_teams t
+    on
+        w.team_id = t.team_id
+    inner join
+        users hu
+    on
+        w.home_user_id = hu.user_id
+    inner join
+        users au
+    on
+        w.away_user_id = au.user_id
+"#;
 
 pub struct WagersTable;
 
 impl WagersTable {
-    /// Gets wagers for user
-    pub async fn get_wagers_by_user_id(pool: &PgPool, user: &AuthenticatedUser) {
+    /// Creates a wager from the user against the friend in the new wager
+    pub async fn create(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+        new_wager: &NewWager,
+    ) -> Result<Wager, TalliiError> {
+        let wager = sqlx::query_as::<_, Wager>(
+            r#"
+                insert into
+                    wagers (home_user_id, away_user_id, team_id, state, currency)
+                values
+                    ($1, $2, $3, 'pending', $4)
+                returning
+                    *
+            "#,
+        )
+        .bind(user_id)
+        .bind(&new_wager.friend_user_id)
+        .bind(&new_wager.team_id)
+        .bind(&new_wager.currency)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(wager)
+    }
+
+    /// Gets a single wager response by its id
+    pub async fn get_response_by_id(
+        pool: &PgPool,
+        wager_id: &i32,
+    ) -> Result<Option<WagerResponse>, TalliiError> {
+        let wager =
+            sqlx::query_as::<_, WagerRow>(&format!("{} where w.wager_id = $1", SELECT_WAGER_ROWS))
+                .bind(wager_id)
+                .fetch_optional(pool)
+                .await?
+                .map(WagerResponse::from);
+
+        Ok(wager)
+    }
+
+    /// Gets the wagers the user created, has accepted or has settled
+    pub async fn get_wagers_for_me(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<WagerResponse>, TalliiError> {
+        let wagers = sqlx::query_as::<_, WagerRow>(&format!(
+            r#"
+                {}
+                where
+                    (w.home_user_id = $1 and w.state in ('pending', 'accepted', 'settled'))
+                or
+                    (w.away_user_id = $1 and w.state in ('accepted', 'settled'))
+                order by
+                    w.created_at desc
+            "#,
+            SELECT_WAGER_ROWS
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(WagerResponse::from)
+        .collect();
+
+        Ok(wagers)
+    }
+
+    /// Gets the pending wagers other users have made against the user
+    pub async fn get_invitations_for_user_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<WagerResponse>, TalliiError> {
+        let wagers = sqlx::query_as::<_, WagerRow>(&format!(
+            r#"
+                {}
+                where
+                    w.away_user_id = $1
+                and
+                    w.state = 'pending'
+                order by
+                    w.created_at desc
+            "#,
+            SELECT_WAGER_ROWS
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(WagerResponse::from)
+        .collect();
+
+        Ok(wagers)
+    }
+
+    /// Gets the accepted and settled wagers that the user is a part of
+    pub async fn get_accepted_for_user_id(
+        pool: &PgPool,
+        user_id: &i32,
+    ) -> Result<Vec<WagerResponse>, TalliiError> {
+        let wagers = sqlx::query_as::<_, WagerRow>(&format!(
+            r#"
+                {}
+                where
+                    (w.home_user_id = $1 or w.away_user_id = $1)
+                and
+                    w.state in ('accepted', 'settled')
+                order by
+                    w.created_at desc
+            "#,
+            SELECT_WAGER_ROWS
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(WagerResponse::from)
+        .collect();
+
+        Ok(wagers)
+    }
+
+    /// Gets a single wager by its id and locks it until the transaction finishes
+    pub async fn get_by_id_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        wager_id: &i32,
+    ) -> Result<Option<Wager>, TalliiError> {
+        let wager = sqlx::query_as::<_, Wager>(
+            r#"
+                select
+                    wager_id,
+                    home_user_id,
+                    away_user_id,
+                    team_id,
+                    state,
+                    currency,
+                    winner_user_id,
+                    settled_at,
+                    created_at
+                from
+                    wagers
+                where
+                    wager_id = $1
+                for update
+            "#,
+        )
+        .bind(wager_id)
+        .fetch_optional(tx)
+        .await?;
+
+        Ok(wager)
+    }
+
+    /// Updates the currency of a wager
+    pub async fn update_currency(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        wager_id: &i32,
+        currency: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    wagers
+                set
+                    currency = $1
+                where
+                    wager_id = $2
+            "#,
+        )
+        .bind(currency)
+        .bind(wager_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates the state of a wager
+    pub async fn update_state(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        wager_id: &i32,
+        state: &str,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    wagers
+                set
+                    state = $1
+                where
+                    wager_id = $2
+            "#,
+        )
+        .bind(state)
+        .bind(wager_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the pending and accepted wagers on the teams of an event and locks them until the
+    /// transaction finishes
+    pub async fn get_open_for_event_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<Vec<Wager>, TalliiError> {
         let wagers = sqlx::query_as::<_, Wager>(
             r#"
+                select
+                    w.wager_id,
+                    w.home_user_id,
+                    w.away_user_id,
+                    w.team_id,
+                    w.state,
+                    w.currency,
+                    w.winner_user_id,
+                    w.settled_at,
+                    w.created_at
+                from
+                    wagers w
+                inner join
+                    events_teams t
+                on
+                    w.team_id = t.team_id
+                where
+                    t.event_id = $1
+                and
+                    w.state in ('pending', 'accepted')
+                order by
+                    w.wager_id
+                for update of w
+            "#,
+        )
+        .bind(event_id)
+        .fetch_all(tx)
+        .await?;
 
-            "#
+        Ok(wagers)
+    }
+
+    /// Gets the pending and accepted wagers on a team and locks them until the transaction
+    /// finishes
+    pub async fn get_open_for_team_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        team_id: &i32,
+    ) -> Result<Vec<Wager>, TalliiError> {
+        let wagers = sqlx::query_as::<_, Wager>(
+            r#"
+                select
+                    wager_id,
+                    home_user_id,
+                    away_user_id,
+                    team_id,
+                    state,
+                    currency,
+                    winner_user_id,
+                    settled_at,
+                    created_at
+                from
+                    wagers
+                where
+                    team_id = $1
+                and
+                    state in ('pending', 'accepted')
+                order by
+                    wager_id
+                for update
+            "#,
         )
-            .bind(&user.user_id)
-            .fetch_all(&pool)
-            .await?;
+        .bind(team_id)
+        .fetch_all(tx)
+        .await?;
+
+        Ok(wagers)
     }
-    /// Creates a user
-    pub async fn create(
-        pool: &PgPool,
-        new_wager: &NewWager,
-        user: &AuthenticatedUser
+
+    /// Gets the settled wagers on the teams of an event and locks them until the transaction
+    /// finishes
+    pub async fn get_settled_for_event_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<Vec<Wager>, TalliiError> {
+        let wagers = sqlx::query_as::<_, Wager>(
+            r#"
+                select
+                    w.wager_id,
+                    w.home_user_id,
+                    w.away_user_id,
+                    w.team_id,
+                    w.state,
+                    w.currency,
+                    w.winner_user_id,
+                    w.settled_at,
+                    w.created_at
+                from
+                    wagers w
+                inner join
+                    events_teams t
+                on
+                    w.team_id = t.team_id
+                where
+                    t.event_id = $1
+                and
+                    w.state = 'settled'
+                order by
+                    w.wager_id
+                for update of w
+            "#,
+        )
+        .bind(event_id)
+        .fetch_all(tx)
+        .await?;
+
+        Ok(wagers)
+    }
+
+    /// Marks a wager as settled in favour of the winner, a winner of none means it was void
+    pub async fn settle(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        wager_id: &i32,
+        winner_user_id: Option<i32>,
     ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    wagers
+                set
+                    state = 'settled',
+                    winner_user_id = $1,
+                    settled_at = current_timestamp
+                where
+                    wager_id = $2
+            "#,
+        )
+        .bind(winner_user_id)
+        .bind(wager_id)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
 
-        // Create the wager for user that is creating the wager
-        sqlx::query_as::<_, Wager>(
-            "insert into wagers (home_user_id, away_user_id, team_id, state, currency) values ($1, $2, $3, $4, $5)",
+    /// Moves a settled wager back to accepted
+    pub async fn unsettle(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        wager_id: &i32,
+    ) -> Result<(), TalliiError> {
+        sqlx::query(
+            r#"
+                update
+                    wagers
+                set
+                    state = 'accepted',
+                    winner_user_id = null,
+                    settled_at = null
+                where
+                    wager_id = $1
+            "#,
         )
-            .bind()
-            .bind(hashed_password)
-            .bind(&avatar.emoji)
-            .bind(&avatar.background)
-            .bind(&new_user.invite_code)
-            .bind(&new_user.username)
-            .fetch_one(pool)
-            .await?;
+        .bind(wager_id)
+        .execute(tx)
+        .await?;
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Checks if the user is a part of any pending or accepted wagers
+    pub async fn has_open_for_user(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let exists = sqlx::query_as::<_, WagerExists>(
+            r#"
+                select exists (
+                    select
+                        1
+                    from
+                        wagers
+                    where
+                        (home_user_id = $1 or away_user_id = $1)
+                    and
+                        state in ('pending', 'accepted')
+                )
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(tx)
+        .await?;
+
+        Ok(exists.exists)
+    }
+}
@@ -1,32 +1,401 @@
-use actix_web::{HttpResponse, web};
+use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 
-use crate::services::TalliiResponse;
+use crate::errors::TalliiError;
+use crate::services::auth::{AuthenticatedUser, VerifiedUser};
+use crate::services::currency::db::CurrencyTable;
+use crate::services::currency::models::TransactionReason;
+use crate::services::events::db::EventsTable;
+use crate::services::events::members::db::EventMembersTable;
+use crate::services::events::pools::settlement::PoolSettlement;
+use crate::services::events::teams::db::EventsTeamsTable;
+use crate::services::friends::db::FriendsTable;
+use crate::services::users::db::UsersTable;
+use crate::services::{SuccessResponse, TalliiResponse};
 
-pub async fn get_me_wagers(pool: web::Data<PgPool>,) -> TalliiResponse {
-    Ok(HttpResponse::Ok().json(""))
+use super::db::WagersTable;
+use super::models::{CreatedWagerResponse, NewWager, UpdateWagerRequest};
+use super::settlement::WagerSettlement;
+
+/// Checks that the user is an active member of the event
+async fn is_active_member(
+    pool: &PgPool,
+    event_id: &i32,
+    user_id: &i32,
+) -> Result<bool, TalliiError> {
+    let member = EventMembersTable::get_member_by_user_id(pool, event_id, user_id).await?;
+
+    Ok(matches!(member, Some(m) if m.state == "active"))
 }
 
-pub async fn get_me_invitations(pool: web::Data<PgPool>,) -> TalliiResponse {
-    Ok(HttpResponse::Ok().json(""))
+/// Checks if the event has been decided, either by being finalised or by having a winner marked
+async fn is_decided(pool: &PgPool, event_id: &i32) -> Result<bool, TalliiError> {
+    let event = EventsTable::get_event_by_id(pool, event_id).await?;
+    let teams = EventsTeamsTable::get_many(pool, event_id).await?;
+
+    Ok(event.finalized_at.is_some() || teams.iter().any(|t| t.winner))
 }
 
-pub async fn get_users_wagers(pool: web::Data<PgPool>,) -> TalliiResponse {
-    Ok(HttpResponse::Ok().json(""))
+/// Gets the wagers of me
+pub async fn get_me_wagers(pool: web::Data<PgPool>, user: AuthenticatedUser) -> TalliiResponse {
+    let wagers = WagersTable::get_wagers_for_me(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(wagers))
 }
 
-pub async fn create_wager(pool: web::Data<PgPool>,) -> TalliiResponse {
-    Ok(HttpResponse::Ok().json(""))
+/// Gets the pending wagers other users have made against me
+pub async fn get_me_invitations(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> TalliiResponse {
+    let wagers = WagersTable::get_invitations_for_user_id(&pool, &user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(wagers))
 }
 
-pub async fn get_wager(pool: web::Data<PgPool>,) -> TalliiResponse {
-    Ok(HttpResponse::Ok().json(""))
+/// Gets the accepted and settled wagers of the user matching the provided username
+pub async fn get_users_wagers(
+    pool: web::Data<PgPool>,
+    username: web::Path<String>,
+    _user: AuthenticatedUser,
+) -> TalliiResponse {
+    if let Some(user) = UsersTable::get_by_username(&pool, &username).await? {
+        let wagers = WagersTable::get_accepted_for_user_id(&pool, &user.user_id).await?;
+
+        Ok(HttpResponse::Ok().json(wagers))
+    } else {
+        Err(TalliiError::NOT_FOUND.default())
+    }
+}
+
+/// Creates a wager against a friend on a team
+pub async fn create_wager(
+    pool: web::Data<PgPool>,
+    user: VerifiedUser,
+    new_wager: web::Json<NewWager>,
+) -> TalliiResponse {
+    if new_wager.currency <= 0 {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "The wager currency must be greater than zero.",
+        )));
+    }
+
+    if new_wager.friend_user_id == user.user_id {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("Cannot wager against yourself."))
+        );
+    }
+
+    if FriendsTable::is_blocked(&pool, &user.user_id, &new_wager.friend_user_id).await? {
+        return Err(
+            TalliiError::FORBIDDEN.message(String::from("You cannot wager against this user."))
+        );
+    }
+
+    // only friends can be wagered against
+    if !FriendsTable::is_following(&pool, &user.user_id, &new_wager.friend_user_id).await? {
+        return Err(TalliiError::FORBIDDEN
+            .message(String::from("You can only wager against users you follow.")));
+    }
+
+    // get the team the wager is being placed on
+    let team = EventsTeamsTable::get_by_id(&pool, &new_wager.team_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // wagers can only be made on events that have not been decided yet
+    if is_decided(&pool, &team.event_id).await? {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "Cannot wager on an event that has already been decided.",
+        )));
+    }
+
+    // both users need to be active members of the event the team is in
+    if !is_active_member(&pool, &team.event_id, &user.user_id).await?
+        || !is_active_member(&pool, &team.event_id, &new_wager.friend_user_id).await?
+    {
+        return Err(TalliiError::FORBIDDEN.message(String::from(
+            "Both users must be active members of the event.",
+        )));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let wager = WagersTable::create(&mut tx, &user.user_id, &new_wager).await?;
+
+    // hold the stake of the creator in escrow until the wager is resolved
+    CurrencyTable::post(
+        &mut tx,
+        &user.user_id,
+        -wager.currency,
+        TransactionReason::WagerEscrow,
+        Some(wager.wager_id),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(CreatedWagerResponse {
+        wager_id: wager.wager_id,
+    }))
 }
 
-pub async fn update_wager_currency(pool: web::Data<PgPool>,) -> TalliiResponse {
-    Ok(HttpResponse::Ok().json(""))
+/// Gets a single wager
+pub async fn get_wager(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    wager_id: web::Path<i32>,
+) -> TalliiResponse {
+    let wager = WagersTable::get_response_by_id(&pool, &wager_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // pending wagers are only visible to the users that are a part of them
+    if wager.state != "accepted"
+        && wager.state != "settled"
+        && wager.home_user.user_id != user.user_id
+        && wager.away_user.user_id != user.user_id
+    {
+        return Err(TalliiError::NOT_FOUND.default());
+    }
+
+    Ok(HttpResponse::Ok().json(wager))
 }
 
-pub async fn decline_wager_invite(pool: web::Data<PgPool>,) -> TalliiResponse {
-    Ok(HttpResponse::Ok().json(""))
-}
\ No newline at end of file
+/// Updates the currency of a pending wager
+pub async fn update_wager_currency(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    wager_id: web::Path<i32>,
+    request: web::Json<UpdateWagerRequest>,
+) -> TalliiResponse {
+    if request.currency <= 0 {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "The wager currency must be greater than zero.",
+        )));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let wager = WagersTable::get_by_id_for_update(&mut tx, &wager_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // only the creator of the wager can change the stake
+    if wager.home_user_id != user.user_id {
+        return Err(TalliiError::FORBIDDEN.default());
+    }
+
+    if wager.state != "pending" {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("Only pending wagers can be updated."))
+        );
+    }
+
+    // the stake of a wager can not change once its event has been decided
+    let team = EventsTeamsTable::get_by_id(&pool, &wager.team_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if is_decided(&pool, &team.event_id).await? {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "Cannot wager on an event that has already been decided.",
+        )));
+    }
+
+    // move the difference in stake in or out of escrow
+    let difference = request.currency - wager.currency;
+
+    if difference != 0 {
+        let reason = if difference > 0 {
+            TransactionReason::WagerEscrow
+        } else {
+            TransactionReason::Refund
+        };
+
+        CurrencyTable::post(
+            &mut tx,
+            &user.user_id,
+            -difference,
+            reason,
+            Some(wager.wager_id),
+        )
+        .await?;
+    }
+
+    WagersTable::update_currency(&mut tx, &wager_id, &request.currency).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("UPDATED_WAGER"),
+        message: String::from("The provided wager was updated."),
+    }))
+}
+
+/// Accepts a wager invitation
+pub async fn accept_wager_invite(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    wager_id: web::Path<i32>,
+) -> TalliiResponse {
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let wager = WagersTable::get_by_id_for_update(&mut tx, &wager_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    // only the invited user can accept the wager
+    if wager.away_user_id != user.user_id {
+        return Err(TalliiError::FORBIDDEN.default());
+    }
+
+    if wager.state != "pending" {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("Only pending wagers can be accepted."))
+        );
+    }
+
+    // a wager can not be accepted once its event has been decided
+    let team = EventsTeamsTable::get_by_id(&pool, &wager.team_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    if is_decided(&pool, &team.event_id).await? {
+        return Err(TalliiError::BAD_REQUEST.message(String::from(
+            "Cannot wager on an event that has already been decided.",
+        )));
+    }
+
+    // a block made after the invite stops it from being accepted
+    if FriendsTable::is_blocked(&pool, &wager.home_user_id, &user.user_id).await? {
+        return Err(
+            TalliiError::FORBIDDEN.message(String::from("You cannot wager against this user."))
+        );
+    }
+
+    // match the stake of the creator in escrow
+    CurrencyTable::post(
+        &mut tx,
+        &user.user_id,
+        -wager.currency,
+        TransactionReason::WagerEscrow,
+        Some(wager.wager_id),
+    )
+    .await?;
+
+    WagersTable::update_state(&mut tx, &wager_id, "accepted").await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("ACCEPTED_WAGER"),
+        message: String::from("The provided wager was accepted."),
+    }))
+}
+
+/// Declines a wager invitation, or cancels it if the creator removes it
+pub async fn decline_wager_invite(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    wager_id: web::Path<i32>,
+) -> TalliiResponse {
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let wager = WagersTable::get_by_id_for_update(&mut tx, &wager_id)
+        .await?
+        .ok_or_else(|| TalliiError::NOT_FOUND.default())?;
+
+    let state = if wager.away_user_id == user.user_id {
+        "declined"
+    } else if wager.home_user_id == user.user_id {
+        "cancelled"
+    } else {
+        return Err(TalliiError::FORBIDDEN.default());
+    };
+
+    if wager.state != "pending" {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("Only pending wagers can be declined."))
+        );
+    }
+
+    // give the creator their stake back
+    CurrencyTable::post(
+        &mut tx,
+        &wager.home_user_id,
+        wager.currency,
+        TransactionReason::Refund,
+        Some(wager.wager_id),
+    )
+    .await?;
+
+    WagersTable::update_state(&mut tx, &wager_id, state).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("DECLINED_WAGER"),
+        message: String::from("The provided wager was declined."),
+    }))
+}
+
+/// Finalises an event and settles all of its open wagers and its pool
+pub async fn settle_event_wagers(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    event_id: web::Path<i32>,
+) -> TalliiResponse {
+    if !EventMembersTable::is_admin(&pool, &event_id, &user.user_id).await? {
+        return Err(TalliiError::FORBIDDEN.default());
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    WagerSettlement::settle_event(&mut tx, &event_id).await?;
+    PoolSettlement::settle_event(&mut tx, &event_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("SETTLED_EVENT_WAGERS"),
+        message: String::from("The wagers and pool of the provided event were settled."),
+    }))
+}
+
+/// Reverses the settlement of all the wagers and the pool of an event
+pub async fn reverse_event_wagers(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    event_id: web::Path<i32>,
+) -> TalliiResponse {
+    if !EventMembersTable::is_admin(&pool, &event_id, &user.user_id).await? {
+        return Err(TalliiError::FORBIDDEN.default());
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    WagerSettlement::reverse_event(&mut tx, &event_id).await?;
+    PoolSettlement::reverse_event(&mut tx, &event_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("REVERSED_EVENT_WAGERS"),
+        message: String::from(
+            "The settlement of the wagers and pool of the provided event was reversed.",
+        ),
+    }))
+}
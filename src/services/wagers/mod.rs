@@ -2,3 +2,4 @@ pub mod db;
 pub mod handlers;
 pub mod models;
 pub mod routes;
+pub mod settlement;
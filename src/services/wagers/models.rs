@@ -1,6 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+use crate::services::users::models::PublicUser;
 
 /// Database representation of a Wager
-#[derive(sqlx::FromRow, Serialize, Deserialize)]
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug)]
 pub struct Wager {
     pub wager_id: i32,
     pub home_user_id: i32,
@@ -8,25 +11,103 @@ pub struct Wager {
     pub team_id: i32,
     pub state: String,
     pub currency: i32,
-    pub created_at: chrono::NaiveDateTime
+    pub winner_user_id: Option<i32>,
+    pub settled_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Represents a Wager existing
+#[derive(sqlx::FromRow, Debug)]
+pub struct WagerExists {
+    pub exists: bool,
 }
 
-/// Database representation of a Wager to insert
-#[derive(sqlx::FromRow, Deserialize)]
+/// Representation of a Wager to insert
+#[derive(Deserialize, Debug)]
 pub struct NewWager {
     pub friend_user_id: i32,
     pub team_id: i32,
+    pub currency: i32,
+}
+
+/// Representation of a Wager to update
+#[derive(Deserialize, Debug)]
+pub struct UpdateWagerRequest {
+    pub currency: i32,
+}
+
+/// Wager row that is queried
+#[derive(sqlx::FromRow, Debug)]
+pub struct WagerRow {
+    pub wager_id: i32,
+    pub team_id: i32,
+    pub team_name: String,
+    pub event_id: i32,
     pub state: String,
     pub currency: i32,
+    pub winner_user_id: Option<i32>,
+    pub settled_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub home_user_id: i32,
+    pub home_emoji: String,
+    pub home_bg_color: String,
+    pub home_username: String,
+    pub home_bio: Option<String>,
+    pub away_user_id: i32,
+    pub away_emoji: String,
+    pub away_bg_color: String,
+    pub away_username: String,
+    pub away_bio: Option<String>,
 }
 
-/// Database representation of a Wager to insert
-#[derive(sqlx::FromRow, Deserialize)]
+/// Wager response payload
+#[derive(Serialize, Debug)]
 pub struct WagerResponse {
     pub wager_id: i32,
-
-    pub friend_user_id: i32,
-    pub team: i32,
+    pub team_id: i32,
+    pub team_name: String,
+    pub event_id: i32,
     pub state: String,
     pub currency: i32,
-}
\ No newline at end of file
+    pub home_user: PublicUser,
+    pub away_user: PublicUser,
+    pub winner_user_id: Option<i32>,
+    pub settled_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl From<WagerRow> for WagerResponse {
+    fn from(row: WagerRow) -> WagerResponse {
+        WagerResponse {
+            wager_id: row.wager_id,
+            team_id: row.team_id,
+            team_name: row.team_name,
+            event_id: row.event_id,
+            state: row.state,
+            currency: row.currency,
+            home_user: PublicUser {
+                user_id: row.home_user_id,
+                emoji: row.home_emoji,
+                bg_color: row.home_bg_color,
+                username: row.home_username,
+                bio: row.home_bio,
+            },
+            away_user: PublicUser {
+                user_id: row.away_user_id,
+                emoji: row.away_emoji,
+                bg_color: row.away_bg_color,
+                username: row.away_username,
+                bio: row.away_bio,
+            },
+            winner_user_id: row.winner_user_id,
+            settled_at: row.settled_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Database representation of a created Wager
+#[derive(Serialize, Debug)]
+pub struct CreatedWagerResponse {
+    pub wager_id: i32,
+}
@@ -2,21 +2,24 @@ use actix_web::{web, Resource};
 
 use super::handlers;
 
-/// Resource routes for wagers
+/// Resource routes for creating wagers
+pub fn wagers_routes() -> Resource {
+    web::resource("/wagers").route(web::post().to(handlers::create_wager))
+}
+
+/// Resource routes for the wagers of the currently logged in user
 pub fn me_wagers_routes() -> Resource {
     web::resource("/me/wagers").route(web::get().to(handlers::get_me_wagers))
 }
 
-/// Resource routes for wagers
+/// Resource routes for the wager invitations of the currently logged in user
 pub fn me_wagers_invitations_routes() -> Resource {
     web::resource("/me/wagers/invitations").route(web::get().to(handlers::get_me_invitations))
 }
 
-/// Resource routes for a specific wagers
+/// Resource routes for user username wagers
 pub fn user_wagers_routes() -> Resource {
-    web::resource("/wagers/{username}")
-        .route(web::get().to(handlers::get_users_wagers))
-        .route(web::post().to(handlers::create_wager))
+    web::resource("/users/{username}/wagers").route(web::get().to(handlers::get_users_wagers))
 }
 
 /// Resource routes for a specific wager
@@ -25,4 +28,16 @@ pub fn wager_entity_routes() -> Resource {
         .route(web::get().to(handlers::get_wager))
         .route(web::patch().to(handlers::update_wager_currency))
         .route(web::delete().to(handlers::decline_wager_invite))
-}
\ No newline at end of file
+}
+
+/// Resource routes for accepting a specific wager
+pub fn wager_accept_routes() -> Resource {
+    web::resource("/wagers/{wager_id}/accept").route(web::put().to(handlers::accept_wager_invite))
+}
+
+/// Resource routes for settling the wagers of an event
+pub fn event_wagers_settlement_routes() -> Resource {
+    web::resource("/events/{event_id}/settlement")
+        .route(web::put().to(handlers::settle_event_wagers))
+        .route(web::delete().to(handlers::reverse_event_wagers))
+}
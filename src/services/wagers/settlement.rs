@@ -0,0 +1,235 @@
+use sqlx::Transaction;
+
+use crate::errors::TalliiError;
+use crate::services::currency::db::CurrencyTable;
+use crate::services::currency::models::TransactionReason;
+use crate::services::events::db::EventsTable;
+use crate::services::events::teams::db::EventsTeamsTable;
+
+use super::db::WagersTable;
+use super::models::Wager;
+
+/// The result of an accepted wager once its event has been decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    HomeWins,
+    AwayWins,
+    Void,
+}
+
+impl Outcome {
+    /// Decides the outcome of a wager on the team given the winning teams of its event.
+    ///
+    /// The home user backs the team and the away user backs the field, so a single winner
+    /// decides the wager while no winner or a tie between several winners voids it.
+    pub fn decide(team_id: i32, winning_team_ids: &[i32]) -> Outcome {
+        match winning_team_ids {
+            [winner] if *winner == team_id => Outcome::HomeWins,
+            [_] => Outcome::AwayWins,
+            _ => Outcome::Void,
+        }
+    }
+}
+
+pub struct WagerSettlement;
+
+impl WagerSettlement {
+    /// Settles every open wager on the event and finalises it in the provided transaction.
+    ///
+    /// Wagers are decided by the teams marked as winners, without a winner they are voided. Only
+    /// pending and accepted wagers are touched, so settling an event a second time never pays
+    /// out twice.
+    pub async fn settle_event(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<(), TalliiError> {
+        let winning_team_ids: Vec<i32> = EventsTeamsTable::get_winners(tx, event_id)
+            .await?
+            .into_iter()
+            .map(|team| team.team_id)
+            .collect();
+
+        let wagers = WagersTable::get_open_for_event_for_update(tx, event_id).await?;
+
+        for wager in wagers {
+            // wagers that were never accepted are cancelled and the creator gets their stake back
+            if wager.state == "pending" {
+                CurrencyTable::post(
+                    tx,
+                    &wager.home_user_id,
+                    wager.currency,
+                    TransactionReason::Refund,
+                    Some(wager.wager_id),
+                )
+                .await?;
+
+                WagersTable::update_state(tx, &wager.wager_id, "cancelled").await?;
+
+                continue;
+            }
+
+            let winner_user_id = match Outcome::decide(wager.team_id, &winning_team_ids) {
+                Outcome::HomeWins => Some(wager.home_user_id),
+                Outcome::AwayWins => Some(wager.away_user_id),
+                Outcome::Void => None,
+            };
+
+            match winner_user_id {
+                // the winner takes both stakes out of escrow
+                Some(user_id) => {
+                    CurrencyTable::post(
+                        tx,
+                        &user_id,
+                        wager.currency * 2,
+                        TransactionReason::WagerPayout,
+                        Some(wager.wager_id),
+                    )
+                    .await?;
+                }
+                // both users get their stake back
+                None => {
+                    for user_id in &[wager.home_user_id, wager.away_user_id] {
+                        CurrencyTable::post(
+                            tx,
+                            user_id,
+                            wager.currency,
+                            TransactionReason::Refund,
+                            Some(wager.wager_id),
+                        )
+                        .await?;
+                    }
+                }
+            }
+
+            WagersTable::settle(tx, &wager.wager_id, winner_user_id).await?;
+        }
+
+        EventsTable::update_finalized_by_id(tx, event_id, true).await?;
+
+        Ok(())
+    }
+
+    /// Cancels the open wagers on a team that is about to be deleted and gives every user their
+    /// stake back. Returns whether there were any.
+    pub async fn cancel_team(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        team_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let wagers = WagersTable::get_open_for_team_for_update(tx, team_id).await?;
+
+        WagerSettlement::cancel(tx, wagers).await
+    }
+
+    /// Cancels the open wagers on an event that is about to be deleted and gives every user their
+    /// stake back. Returns whether there were any.
+    pub async fn cancel_event(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let wagers = WagersTable::get_open_for_event_for_update(tx, event_id).await?;
+
+        WagerSettlement::cancel(tx, wagers).await
+    }
+
+    async fn cancel(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        wagers: Vec<Wager>,
+    ) -> Result<bool, TalliiError> {
+        let any = !wagers.is_empty();
+
+        for wager in wagers {
+            // only accepted wagers hold the stake of the invited user
+            let user_ids = if wager.state == "accepted" {
+                vec![wager.home_user_id, wager.away_user_id]
+            } else {
+                vec![wager.home_user_id]
+            };
+
+            for user_id in &user_ids {
+                CurrencyTable::post(
+                    tx,
+                    user_id,
+                    wager.currency,
+                    TransactionReason::Refund,
+                    Some(wager.wager_id),
+                )
+                .await?;
+            }
+
+            WagersTable::update_state(tx, &wager.wager_id, "cancelled").await?;
+        }
+
+        Ok(any)
+    }
+
+    /// Reverses the settlement of every settled wager on the event in the provided transaction.
+    ///
+    /// Payouts and refunds are moved back into escrow with a reversal entry in the ledger and the
+    /// wagers are accepted again, so they will be settled the next time the event is settled. A
+    /// user that already spent their payout is left with a negative balance.
+    pub async fn reverse_event(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        event_id: &i32,
+    ) -> Result<(), TalliiError> {
+        let wagers = WagersTable::get_settled_for_event_for_update(tx, event_id).await?;
+
+        for wager in wagers {
+            match wager.winner_user_id {
+                Some(user_id) => {
+                    CurrencyTable::post(
+                        tx,
+                        &user_id,
+                        -(wager.currency * 2),
+                        TransactionReason::SettlementReversal,
+                        Some(wager.wager_id),
+                    )
+                    .await?;
+                }
+                None => {
+                    for user_id in &[wager.home_user_id, wager.away_user_id] {
+                        CurrencyTable::post(
+                            tx,
+                            user_id,
+                            -wager.currency,
+                            TransactionReason::SettlementReversal,
+                            Some(wager.wager_id),
+                        )
+                        .await?;
+                    }
+                }
+            }
+
+            WagersTable::unsettle(tx, &wager.wager_id).await?;
+        }
+
+        EventsTable::update_finalized_by_id(tx, event_id, false).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decide_home_wins_when_the_team_is_the_only_winner() {
+        assert_eq!(Outcome::decide(1, &[1]), Outcome::HomeWins);
+    }
+
+    #[test]
+    fn decide_away_wins_when_another_team_is_the_only_winner() {
+        assert_eq!(Outcome::decide(1, &[2]), Outcome::AwayWins);
+    }
+
+    #[test]
+    fn decide_voids_without_a_winner() {
+        assert_eq!(Outcome::decide(1, &[]), Outcome::Void);
+    }
+
+    #[test]
+    fn decide_voids_with_several_winners() {
+        assert_eq!(Outcome::decide(1, &[1, 2]), Outcome::Void);
+        assert_eq!(Outcome::decide(1, &[2, 3]), Outcome::Void);
+    }
+}
@@ -0,0 +1,249 @@
+use sqlx::{PgPool, Transaction};
+
+use crate::errors::TalliiError;
+use crate::services::admin::models::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+
+use super::models::{WaitlistAhead, WaitlistEntry, WaitlistQuery};
+
+pub struct WaitlistTable;
+
+impl WaitlistTable {
+    /// Adds the email to the waitlist, returns false when the email is already on it
+    pub async fn join(
+        pool: &PgPool,
+        email: &str,
+        referrer_user_id: Option<i32>,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                insert into
+                    waitlist (email, referrer_user_id)
+                values
+                    ($1, $2)
+                on conflict (email) do nothing
+            "#,
+        )
+        .bind(email)
+        .bind(referrer_user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Gets the waitlist entry of the email
+    pub async fn get_by_email(
+        pool: &PgPool,
+        email: &str,
+    ) -> Result<Option<WaitlistEntry>, TalliiError> {
+        let entry = sqlx::query_as::<_, WaitlistEntry>(
+            r#"
+                select
+                    waitlist_id,
+                    email,
+                    referrer_user_id,
+                    vouches,
+                    invite_code,
+                    approved_at,
+                    created_at
+                from
+                    waitlist
+                where
+                    email = $1
+            "#,
+        )
+        .bind(email)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(entry)
+    }
+
+    /// Counts the people that are waiting ahead of the entry, people with more vouches go first
+    pub async fn get_ahead(pool: &PgPool, entry: &WaitlistEntry) -> Result<i64, TalliiError> {
+        let ahead = sqlx::query_as::<_, WaitlistAhead>(
+            r#"
+                select
+                    count(*) as ahead
+                from
+                    waitlist
+                where
+                    approved_at is null
+                and
+                    (vouches > $1 or (vouches = $1 and created_at < $2))
+            "#,
+        )
+        .bind(entry.vouches)
+        .bind(entry.created_at)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(ahead.ahead)
+    }
+
+    /// Records that the position was emailed to the entry, returns false when it was already
+    /// emailed in the last interval
+    pub async fn mark_position_sent(
+        pool: &PgPool,
+        waitlist_id: &i32,
+        interval_seconds: i64,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                update
+                    waitlist
+                set
+                    position_sent_at = current_timestamp
+                where
+                    waitlist_id = $1
+                and (
+                    position_sent_at is null
+                    or position_sent_at < current_timestamp - make_interval(secs => $2)
+                )
+            "#,
+        )
+        .bind(waitlist_id)
+        .bind(interval_seconds as f64)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Records the vouch of the user for the entry, returns false when the user already vouched
+    pub async fn vouch(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        waitlist_id: &i32,
+        user_id: &i32,
+    ) -> Result<bool, TalliiError> {
+        let result = sqlx::query(
+            r#"
+                insert into
+                    waitlist_vouches (waitlist_id, user_id)
+                values
+                    ($1, $2)
+                on conflict do nothing
+            "#,
+        )
+        .bind(waitlist_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("update waitlist set vouches = vouches + 1 where waitlist_id = $1")
+            .bind(waitlist_id)
+            .execute(&mut *tx)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Gets the waitlist in the order people are approved in
+    pub async fn get_entries(
+        pool: &PgPool,
+        params: &WaitlistQuery,
+    ) -> Result<Vec<WaitlistEntry>, TalliiError> {
+        let limit = params
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .max(1)
+            .min(MAX_PAGE_SIZE);
+
+        let entries = sqlx::query_as::<_, WaitlistEntry>(
+            r#"
+                select
+                    waitlist_id,
+                    email,
+                    referrer_user_id,
+                    vouches,
+                    invite_code,
+                    approved_at,
+                    created_at
+                from
+                    waitlist
+                where
+                    (approved_at is not null) = $1
+                order by
+                    approved_at desc nulls last,
+                    vouches desc,
+                    created_at
+                limit
+                    $2
+            "#,
+        )
+        .bind(params.approved.unwrap_or(false))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Gets the next people in line and locks them until the transaction finishes, people that
+    /// are being approved by another transaction are skipped
+    pub async fn get_next_for_update(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        amount: i64,
+    ) -> Result<Vec<WaitlistEntry>, TalliiError> {
+        let entries = sqlx::query_as::<_, WaitlistEntry>(
+            r#"
+                select
+                    waitlist_id,
+                    email,
+                    referrer_user_id,
+                    vouches,
+                    invite_code,
+                    approved_at,
+                    created_at
+                from
+                    waitlist
+                where
+                    approved_at is null
+                order by
+                    vouches desc,
+                    created_at
+                limit
+                    $1
+                for update skip locked
+            "#,
+        )
+        .bind(amount)
+        .fetch_all(tx)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Approves the entries, giving each the invite code at the same index
+    pub async fn approve_many(
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+        waitlist_ids: &[i32],
+        invite_codes: &[String],
+    ) -> Result<Vec<WaitlistEntry>, TalliiError> {
+        let entries = sqlx::query_as::<_, WaitlistEntry>(
+            r#"
+                update
+                    waitlist w
+                set
+                    approved_at = current_timestamp,
+                    invite_code = a.invite_code
+                from
+                    unnest($1::integer[], $2::text[]) as a (waitlist_id, invite_code)
+                where
+                    w.waitlist_id = a.waitlist_id
+                returning
+                    w.*
+            "#,
+        )
+        .bind(waitlist_ids)
+        .bind(invite_codes)
+        .fetch_all(tx)
+        .await?;
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,33 @@
+use crate::mailer::{Email, Mailer};
+
+use super::models::WAITLIST_INVITE_DAYS;
+
+/// Creates the email that tells a person their position on the waitlist, none once they were
+/// approved
+pub fn waitlist_position_email(to: &str, position: Option<i64>) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: String::from("Your place on the Tallii waitlist"),
+        body: match position {
+            Some(position) => format!(
+                "You are number {} on the Tallii waitlist. Ask your friends on Tallii to vouch for you to move up.",
+                position
+            ),
+            None => String::from(
+                "You made it off the Tallii waitlist, your invite code was sent to you in a separate email.",
+            ),
+        },
+    }
+}
+
+/// Creates the email that sends an approved person their invite code
+pub fn waitlist_invite_email(mailer: &Mailer, to: &str, invite_code: &str) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: String::from("You're in! Your Tallii invite code"),
+        body: format!(
+            "You made it off the Tallii waitlist. Sign up with your invite code {} by following the link below:\n\n{}/signup?invite_code={}\n\nThe invite code expires in {} days.",
+            invite_code, mailer.app_url, invite_code, WAITLIST_INVITE_DAYS
+        ),
+    }
+}
@@ -0,0 +1,240 @@
+use actix_web::{rt, web, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::errors::TalliiError;
+use crate::mailer::Mailer;
+use crate::services::admin::db::AuditLogTable;
+use crate::services::admin::models::AuditAction;
+use crate::services::auth::{AdminUser, ModeratorUser, VerifiedUser};
+use crate::services::users::db::{InviteCodesTable, UsersTable};
+use crate::services::{SuccessResponse, TalliiResponse};
+
+use super::db::WaitlistTable;
+use super::emails::{waitlist_invite_email, waitlist_position_email};
+use super::models::{
+    is_valid_email, ApproveWaitlistRequest, JoinWaitlistRequest, VouchRequest,
+    WaitlistPositionQuery, WaitlistQuery, MAX_APPROVALS_PER_BATCH, POSITION_INTERVAL_SECONDS,
+    WAITLIST_INVITE_DAYS,
+};
+
+/// Adds the email to the waitlist unless it already has an account
+async fn join(
+    pool: &PgPool,
+    email: String,
+    referrer_user_id: Option<i32>,
+) -> Result<(), TalliiError> {
+    if UsersTable::get_by_email(pool, &email).await?.is_none() {
+        // joining twice keeps the original place on the waitlist
+        WaitlistTable::join(pool, &email, referrer_user_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Adds the email to the waitlist, optionally crediting the user that referred them. Always
+/// succeeds for a valid email so that it does not reveal which emails have an account.
+pub async fn join_waitlist(
+    pool: web::Data<PgPool>,
+    request: web::Json<JoinWaitlistRequest>,
+) -> TalliiResponse {
+    let email = request.email.trim().to_string();
+
+    if !is_valid_email(&email) {
+        return Err(
+            TalliiError::BAD_REQUEST.message(String::from("The provided email is not valid."))
+        );
+    }
+
+    let referrer_user_id = match &request.referrer {
+        Some(referrer) => Some(
+            UsersTable::get_by_username(&pool, referrer)
+                .await?
+                .ok_or_else(|| {
+                    TalliiError::BAD_REQUEST
+                        .message(String::from("The provided referrer does not exist."))
+                })?
+                .user_id,
+        ),
+        None => None,
+    };
+
+    // the email is joined off the request path, so the response takes the same time whether or
+    // not there is an account for the email
+    rt::spawn(async move {
+        if let Err(err) = join(&pool, email, referrer_user_id).await {
+            error!("Failed to join the waitlist: {:?}", err);
+        }
+    });
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("JOINED_WAITLIST"),
+        message: String::from("The provided email is on the waitlist."),
+    }))
+}
+
+/// Emails the position on the waitlist to the email if it is on the waitlist and the position
+/// was not emailed to it recently
+async fn send_position(pool: &PgPool, mailer: &Mailer, email: String) -> Result<(), TalliiError> {
+    let entry = match WaitlistTable::get_by_email(pool, &email).await? {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+
+    if !WaitlistTable::mark_position_sent(pool, &entry.waitlist_id, POSITION_INTERVAL_SECONDS)
+        .await?
+    {
+        return Ok(());
+    }
+
+    let position = match entry.approved_at {
+        Some(_) => None,
+        None => Some(WaitlistTable::get_ahead(pool, &entry).await? + 1),
+    };
+
+    mailer
+        .send(waitlist_position_email(&entry.email, position))
+        .await
+}
+
+/// Emails the position on the waitlist to the email. Responds the same whether or not the email
+/// is on the waitlist so that it does not reveal which emails are on the waitlist.
+pub async fn get_waitlist_position(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<Mailer>,
+    params: web::Query<WaitlistPositionQuery>,
+) -> TalliiResponse {
+    let email = params.into_inner().email.trim().to_string();
+
+    rt::spawn(async move {
+        if let Err(err) = send_position(&pool, &mailer, email).await {
+            error!("Failed to send waitlist position email: {:?}", err);
+        }
+    });
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("SENT_WAITLIST_POSITION"),
+        message: String::from(
+            "If the provided email is on the waitlist its position was emailed to it.",
+        ),
+    }))
+}
+
+/// Vouches for the email if it is still waiting, a user can only vouch for an email once
+async fn vouch_for(pool: &PgPool, email: String, user_id: i32) -> Result<(), TalliiError> {
+    let entry = match WaitlistTable::get_by_email(pool, &email).await? {
+        Some(entry) if entry.approved_at.is_none() => entry,
+        _ => return Ok(()),
+    };
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    WaitlistTable::vouch(&mut tx, &entry.waitlist_id, &user_id).await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Vouches for a person on the waitlist to move them up. Responds the same whether or not the
+/// email is on the waitlist so that it does not reveal which emails are on the waitlist.
+pub async fn vouch(
+    pool: web::Data<PgPool>,
+    user: VerifiedUser,
+    request: web::Json<VouchRequest>,
+) -> TalliiResponse {
+    let email = request.into_inner().email.trim().to_string();
+    let user_id = user.user_id;
+
+    rt::spawn(async move {
+        if let Err(err) = vouch_for(&pool, email, user_id).await {
+            error!("Failed to vouch for the waitlist: {:?}", err);
+        }
+    });
+
+    Ok(HttpResponse::Ok().json(SuccessResponse {
+        code: String::from("VOUCHED"),
+        message: String::from(
+            "If the provided email is waiting on the waitlist your vouch was counted.",
+        ),
+    }))
+}
+
+/// Gets the people that are waiting, or the people that were approved
+pub async fn get_waitlist(
+    pool: web::Data<PgPool>,
+    _moderator: ModeratorUser,
+    params: web::Query<WaitlistQuery>,
+) -> TalliiResponse {
+    let entries = WaitlistTable::get_entries(&pool, &params).await?;
+
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+/// Approves the next people on the waitlist and emails each of them a new invite code
+pub async fn approve_waitlist(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<Mailer>,
+    admin: AdminUser,
+    request: web::Json<ApproveWaitlistRequest>,
+) -> TalliiResponse {
+    if request.amount < 1 || request.amount > MAX_APPROVALS_PER_BATCH {
+        return Err(TalliiError::BAD_REQUEST.message(format!(
+            "The amount must be between 1 and {}.",
+            MAX_APPROVALS_PER_BATCH
+        )));
+    }
+
+    // start the transaction
+    let mut tx = pool.begin().await?;
+
+    let next = WaitlistTable::get_next_for_update(&mut tx, request.amount).await?;
+
+    let invite_codes = InviteCodesTable::create_many(
+        &mut tx,
+        next.len() as i32,
+        Some(admin.user_id),
+        1,
+        Some(Utc::now().naive_utc() + Duration::days(WAITLIST_INVITE_DAYS)),
+        false,
+    )
+    .await?;
+
+    // a code that clashed with an existing one was skipped, those people stay on the waitlist
+    let waitlist_ids: Vec<i32> = next
+        .iter()
+        .take(invite_codes.len())
+        .map(|entry| entry.waitlist_id)
+        .collect();
+    let codes: Vec<String> = invite_codes.into_iter().map(|code| code.id).collect();
+
+    let approved = WaitlistTable::approve_many(&mut tx, &waitlist_ids, &codes).await?;
+
+    AuditLogTable::record(
+        &mut tx,
+        &admin.user_id,
+        AuditAction::ApproveWaitlist,
+        None,
+        Some(format!("approved={}", approved.len())),
+    )
+    .await?;
+
+    // commit the transaction
+    tx.commit().await?;
+
+    for entry in &approved {
+        if let Some(invite_code) = &entry.invite_code {
+            if let Err(err) = mailer
+                .send(waitlist_invite_email(&mailer, &entry.email, invite_code))
+                .await
+            {
+                error!("Failed to send waitlist invite email: {:?}", err);
+            }
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(approved))
+}
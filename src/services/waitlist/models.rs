@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+/// Days the invite code sent to an approved person is valid for
+pub const WAITLIST_INVITE_DAYS: i64 = 30;
+
+/// Max amount of people approved in a single batch
+pub const MAX_APPROVALS_PER_BATCH: i64 = 500;
+
+/// Seconds a person has to wait before their position is emailed to them again
+pub const POSITION_INTERVAL_SECONDS: i64 = 60 * 60;
+
+/// Max length of an email address
+pub const MAX_EMAIL_LENGTH: usize = 254;
+
+/// Checks that the email looks like an address mail can be delivered to, a single @ between a
+/// local part and a domain with a dot, without whitespace, control characters or slashes
+pub fn is_valid_email(email: &str) -> bool {
+    let at = match email.find('@') {
+        Some(at) => at,
+        None => return false,
+    };
+
+    let (local, domain) = (&email[..at], &email[at + 1..]);
+
+    email.len() <= MAX_EMAIL_LENGTH
+        && !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '/' || c == '\\')
+}
+
+/// Database representation of a Waitlist Entry
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct WaitlistEntry {
+    pub waitlist_id: i32,
+    pub email: String,
+    pub referrer_user_id: Option<i32>,
+    pub vouches: i32,
+    pub invite_code: Option<String>,
+    pub approved_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// The amount of people ahead of an entry on the waitlist
+#[derive(sqlx::FromRow, Debug)]
+pub struct WaitlistAhead {
+    pub ahead: i64,
+}
+
+/// Representation of the join waitlist request
+#[derive(Deserialize, Debug)]
+pub struct JoinWaitlistRequest {
+    pub email: String,
+    pub referrer: Option<String>,
+}
+
+/// Query params for emailing the position on the waitlist
+#[derive(Deserialize, Debug)]
+pub struct WaitlistPositionQuery {
+    pub email: String,
+}
+
+/// Representation of the vouch request
+#[derive(Deserialize, Debug)]
+pub struct VouchRequest {
+    pub email: String,
+}
+
+/// Query params for listing the waitlist
+#[derive(Deserialize, Debug)]
+pub struct WaitlistQuery {
+    pub approved: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+/// Representation of the approve waitlist request
+#[derive(Deserialize, Debug)]
+pub struct ApproveWaitlistRequest {
+    pub amount: i64,
+}
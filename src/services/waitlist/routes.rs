@@ -0,0 +1,28 @@
+use actix_web::{web, Resource};
+
+use super::handlers;
+
+/// Resource routes for joining the waitlist
+pub fn waitlist_routes() -> Resource {
+    web::resource("/waitlist").route(web::post().to(handlers::join_waitlist))
+}
+
+/// Resource routes for the position on the waitlist
+pub fn waitlist_position_routes() -> Resource {
+    web::resource("/waitlist/position").route(web::get().to(handlers::get_waitlist_position))
+}
+
+/// Resource routes for vouching for a person on the waitlist
+pub fn waitlist_vouches_routes() -> Resource {
+    web::resource("/waitlist/vouches").route(web::post().to(handlers::vouch))
+}
+
+/// Resource routes for listing the waitlist, registered in the admin scope
+pub fn admin_waitlist_routes() -> Resource {
+    web::resource("/waitlist").route(web::get().to(handlers::get_waitlist))
+}
+
+/// Resource routes for approving the next people on the waitlist, registered in the admin scope
+pub fn admin_waitlist_approvals_routes() -> Resource {
+    web::resource("/waitlist/approvals").route(web::post().to(handlers::approve_waitlist))
+}